@@ -46,7 +46,8 @@ impl<R: BufRead + Seek> PngDecoder<R> {
 
         let max_bytes = usize::try_from(limits.max_alloc.unwrap_or(u64::MAX)).unwrap_or(usize::MAX);
         let mut decoder = png::Decoder::new_with_limits(r, png::Limits { bytes: max_bytes });
-        decoder.set_ignore_text_chunk(true);
+        // Text chunks are kept for `text_chunk`. They're small enough not to slow decoding down.
+        decoder.set_ignore_text_chunk(false);
 
         let info = decoder.read_header_info().map_err(ImageError::from_png)?;
         limits.check_dimensions(info.width, info.height)?;
@@ -134,6 +135,17 @@ impl<R: BufRead + Seek> PngDecoder<R> {
             .map(|x| f64::from(x.into_scaled()) / 100_000.0))
     }
 
+    /// Returns the text of the first uncompressed `tEXt` chunk with the given keyword, or None if
+    /// there is no such chunk before the image data.
+    pub fn text_chunk(&self, keyword: &str) -> Option<&str> {
+        self.reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == keyword)
+            .map(|chunk| chunk.text.as_str())
+    }
+
     /// Turn this into an iterator over the animation frames.
     ///
     /// Reading the complete animation requires more memory than reading the data from the IDAT
@@ -499,6 +511,7 @@ pub struct PngEncoder<W: Write> {
     compression: CompressionType,
     filter: FilterType,
     icc_profile: Vec<u8>,
+    text_chunks: Vec<(String, String)>,
 }
 
 /// Compression level of a PNG encoder. The default setting is `Fast`.
@@ -553,6 +566,7 @@ impl<W: Write> PngEncoder<W> {
             compression: CompressionType::default(),
             filter: FilterType::default(),
             icc_profile: Vec::new(),
+            text_chunks: Vec::new(),
         }
     }
 
@@ -578,9 +592,18 @@ impl<W: Write> PngEncoder<W> {
             compression,
             filter,
             icc_profile: Vec::new(),
+            text_chunks: Vec::new(),
         }
     }
 
+    /// Adds an uncompressed Latin-1 `tEXt` chunk with the given keyword to the encoded image.
+    ///
+    /// The keyword must be 1-79 bytes long. Invalid keywords are reported as an encoding error
+    /// once the image is written.
+    pub fn add_text_chunk(&mut self, keyword: impl Into<String>, text: impl Into<String>) {
+        self.text_chunks.push((keyword.into(), text.into()));
+    }
+
     fn encode_inner(
         self,
         data: &[u8],
@@ -637,6 +660,11 @@ impl<W: Write> PngEncoder<W> {
         encoder.set_compression(comp);
         encoder.set_filter(filter);
         encoder.set_adaptive_filter(adaptive_filter);
+        for (keyword, text) in self.text_chunks {
+            encoder
+                .add_text_chunk(keyword, text)
+                .map_err(|e| ImageError::IoError(e.into()))?;
+        }
         let mut writer = encoder
             .write_header()
             .map_err(|e| ImageError::IoError(e.into()))?;
@@ -792,4 +820,36 @@ mod tests {
         let mut target = Cursor::new(vec![]);
         let _ = image.write_to(&mut target, ImageFormat::Png);
     }
+
+    #[test]
+    fn encode_text_chunks() {
+        let mut target = Vec::new();
+        let mut encoder = PngEncoder::new(&mut target);
+        encoder.add_text_chunk("Thumb::URI", "file:///tmp/a.png");
+        encoder
+            .write_image(&[0, 0, 0, 255], 1, 1, ExtendedColorType::Rgba8)
+            .unwrap();
+
+        let decoder = png::Decoder::new(Cursor::new(&target));
+        let reader = decoder.read_info().unwrap();
+        let text = &reader.info().uncompressed_latin1_text;
+
+        assert_eq!(text.len(), 1);
+        assert_eq!(text[0].keyword, "Thumb::URI");
+        assert_eq!(text[0].text, "file:///tmp/a.png");
+    }
+
+    #[test]
+    fn decode_text_chunk() {
+        let mut target = Vec::new();
+        let mut encoder = PngEncoder::new(&mut target);
+        encoder.add_text_chunk("Thumb::MTime", "1700000000");
+        encoder
+            .write_image(&[0, 0, 0, 255], 1, 1, ExtendedColorType::Rgba8)
+            .unwrap();
+
+        let decoder = PngDecoder::new(Cursor::new(&target)).unwrap();
+        assert_eq!(decoder.text_chunk("Thumb::MTime"), Some("1700000000"));
+        assert_eq!(decoder.text_chunk("Thumb::URI"), None);
+    }
 }
@@ -264,7 +264,7 @@ where
         let new_r = matrix[0] * r + matrix[1] * g + matrix[2] * b;
         let new_g = matrix[3] * r + matrix[4] * g + matrix[5] * b;
         let new_b = matrix[6] * r + matrix[7] * g + matrix[8] * b;
-        let max = 255f64;
+        let max: f64 = NumCast::from(S::DEFAULT_MAX_VALUE).unwrap();
 
         #[allow(deprecated)]
         let outpixel = Pixel::from_channels(
@@ -332,7 +332,8 @@ where
             let new_r = matrix[0] * r + matrix[1] * g + matrix[2] * b;
             let new_g = matrix[3] * r + matrix[4] * g + matrix[5] * b;
             let new_b = matrix[6] * r + matrix[7] * g + matrix[8] * b;
-            let max = 255f64;
+            let max: f64 =
+                NumCast::from(<I::Pixel as Pixel>::Subpixel::DEFAULT_MAX_VALUE).unwrap();
 
             #[allow(deprecated)]
             let outpixel = Pixel::from_channels(
@@ -347,6 +348,66 @@ where
     }
 }
 
+/// Gamma correct the supplied image in place.
+/// ```gamma``` is the gamma to correct by.
+/// Values above 1 brighten the midtones and values below 1 darken them.
+pub fn gamma_in_place<I>(image: &mut I, gamma: f32)
+where
+    I: GenericImage,
+{
+    let (width, height) = image.dimensions();
+
+    let max = <I::Pixel as Pixel>::Subpixel::DEFAULT_MAX_VALUE;
+    let max: f32 = NumCast::from(max).unwrap();
+
+    for y in 0..height {
+        for x in 0..width {
+            let e = image.get_pixel(x, y).map_with_alpha(
+                |b| {
+                    let c: f32 = NumCast::from(b).unwrap();
+                    let d = (c / max).powf(1.0 / gamma) * max;
+
+                    NumCast::from(clamp(d, 0.0, max)).unwrap()
+                },
+                |alpha| alpha,
+            );
+
+            image.put_pixel(x, y, e);
+        }
+    }
+}
+
+/// Adjust the saturation of the supplied image in place.
+/// ```saturation``` is how far each channel is pushed away from the luma of its pixel.
+/// 0 turns the image gray, 1 does nothing and values above 1 saturate it.
+pub fn saturate_in_place<I>(image: &mut I, saturation: f32)
+where
+    I: GenericImage,
+{
+    let (width, height) = image.dimensions();
+
+    let max = <I::Pixel as Pixel>::Subpixel::DEFAULT_MAX_VALUE;
+    let max: f32 = NumCast::from(max).unwrap();
+
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = image.get_pixel(x, y);
+            let luma: f32 = NumCast::from(pixel.to_luma().0[0]).unwrap();
+            let e = pixel.map_with_alpha(
+                |b| {
+                    let c: f32 = NumCast::from(b).unwrap();
+                    let d = luma + (c - luma) * saturation;
+
+                    NumCast::from(clamp(d, 0.0, max)).unwrap()
+                },
+                |alpha| alpha,
+            );
+
+            image.put_pixel(x, y, e);
+        }
+    }
+}
+
 /// A color map
 pub trait ColorMap {
     /// The color type on which the map operates on
@@ -546,7 +607,7 @@ where
 mod test {
 
     use super::*;
-    use crate::GrayImage;
+    use crate::{GrayImage, Rgb, RgbImage};
 
     macro_rules! assert_pixels_eq {
         ($actual:expr, $expected:expr) => {{
@@ -633,6 +694,45 @@ mod test {
         assert_pixels_eq!(&image, &expected);
     }
 
+    #[test]
+    fn test_gamma_place() {
+        let mut image: GrayImage =
+            ImageBuffer::from_raw(3, 2, vec![0u8, 64u8, 128u8, 192u8, 255u8, 16u8]).unwrap();
+
+        let expected = image.clone();
+        gamma_in_place(&mut image, 1.0);
+        assert_pixels_eq!(&image, &expected);
+
+        let expected: GrayImage =
+            ImageBuffer::from_raw(3, 2, vec![0u8, 127u8, 180u8, 221u8, 255u8, 63u8]).unwrap();
+
+        gamma_in_place(&mut image, 2.0);
+        assert_pixels_eq!(&image, &expected);
+    }
+
+    #[test]
+    fn test_saturate_place() {
+        let mut image: RgbImage =
+            ImageBuffer::from_raw(2, 1, vec![255u8, 0, 0, 10, 20, 30]).unwrap();
+
+        let expected = image.clone();
+        saturate_in_place(&mut image, 1.0);
+        assert_pixels_eq!(&image, &expected);
+
+        saturate_in_place(&mut image, 0.0);
+        assert!(image.pixels().all(|p| p[0] == p[1] && p[1] == p[2]));
+    }
+
+    #[test]
+    fn test_huerotate_16_bit() {
+        let mut image: ImageBuffer<Rgb<u16>, Vec<u16>> =
+            ImageBuffer::from_raw(1, 1, vec![40000u16, 40000, 40000]).unwrap();
+
+        // Gray has no hue to rotate, and mustn't be clamped to the 8-bit maximum.
+        huerotate_in_place(&mut image, 90);
+        assert!(image.pixels().all(|p| p.0.iter().all(|&c| c.abs_diff(40000) <= 1)));
+    }
+
     #[allow(clippy::type_complexity)]
     fn pixel_diffs<I, J, P>(left: &I, right: &J) -> Vec<((u32, u32, P), (u32, u32, P))>
     where
@@ -22,8 +22,9 @@ pub use self::sample::{
 
 /// Color operations
 pub use self::colorops::{
-    brighten, contrast, dither, grayscale, grayscale_alpha, grayscale_with_type,
-    grayscale_with_type_alpha, huerotate, index_colors, invert, BiLevel, ColorMap,
+    brighten, contrast, dither, gamma_in_place, grayscale, grayscale_alpha, grayscale_with_type,
+    grayscale_with_type_alpha, huerotate, index_colors, invert, saturate_in_place, BiLevel,
+    ColorMap,
 };
 
 mod affine;
@@ -126,6 +126,15 @@
 // even to people using the crate as a dependency,
 // so we have to suppress those warnings.
 #![allow(unexpected_cfgs)]
+// This copy of the crate predates some lints of newer toolchains. They're allowed rather than
+// fixed so that it doesn't drift further from upstream.
+#![allow(mismatched_lifetime_syntaxes)]
+#![cfg_attr(test, allow(unused_parens))]
+#![allow(
+    clippy::collapsible_match,
+    clippy::needless_range_loop,
+    clippy::unbuffered_bytes
+)]
 
 #[cfg(all(test, feature = "benchmarks"))]
 extern crate test;
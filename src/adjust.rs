@@ -0,0 +1,392 @@
+//! Defines colour adjustments of images. They're previewed by the channels shader without touching
+//! the image, and only baked into it with the `colorops` functions of the image crate when it's
+//! saved.
+
+use std::path::{Path, PathBuf};
+
+use image::{ColorType, DynamicImage, ImageReader, imageops::colorops};
+use raylib::prelude::*;
+
+use crate::{Foximg, FoximgDraw, images::FoximgEdit, keybinds::FoximgAction, resources};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FoximgAdjustments {
+    /// Added to every channel, from -255 to 255.
+    pub brightness: f32,
+    /// In percent, from -100 to 100.
+    pub contrast: f32,
+    pub gamma: f32,
+    /// 0 removes all colour, and 1 leaves it as it is.
+    pub saturation: f32,
+    /// The hue rotation in degrees.
+    pub hue: f32,
+    pub grayscale: bool,
+    pub invert: bool,
+}
+
+impl Default for FoximgAdjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.,
+            contrast: 0.,
+            gamma: 1.,
+            saturation: 1.,
+            hue: 0.,
+            grayscale: false,
+            invert: false,
+        }
+    }
+}
+
+impl FoximgAdjustments {
+    /// Returns true if the adjustments leave the image as it is.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns a copy of `image` with the adjustments baked in through the `colorops` functions.
+    /// Images deeper than 8 bits are baked in 16 bits and converted back to their colour type at
+    /// the end, since `brighten` would clamp floats at 1. Alpha is left as it is.
+    pub fn bake(&self, image: &DynamicImage) -> DynamicImage {
+        let deep = image.color().bytes_per_pixel() > image.color().channel_count();
+        let (mut rgb, max) = if deep {
+            (
+                DynamicImage::ImageRgb16(image.to_rgb16()),
+                i32::from(u16::MAX),
+            )
+        } else {
+            (DynamicImage::ImageRgb8(image.to_rgb8()), i32::from(u8::MAX))
+        };
+
+        // Brightness is from -255 to 255 whatever the depth, like in the shader.
+        rgb = rgb
+            .brighten(self.brightness.round() as i32 * max / 255)
+            .adjust_contrast(self.contrast);
+        match rgb {
+            DynamicImage::ImageRgb8(ref mut rgb) => {
+                colorops::gamma_in_place(rgb, self.gamma);
+                colorops::saturate_in_place(rgb, self.saturation);
+            }
+            DynamicImage::ImageRgb16(ref mut rgb) => {
+                colorops::gamma_in_place(rgb, self.gamma);
+                colorops::saturate_in_place(rgb, self.saturation);
+            }
+            _ => unreachable!(),
+        }
+        rgb = rgb.huerotate(self.hue.round() as i32);
+        if self.grayscale {
+            let gray = rgb.grayscale();
+            rgb = if deep {
+                DynamicImage::ImageRgb16(gray.to_rgb16())
+            } else {
+                DynamicImage::ImageRgb8(gray.to_rgb8())
+            };
+        }
+        if self.invert {
+            rgb.invert();
+        }
+
+        // The colour is adjusted without alpha, since `adjust_contrast` would stretch it too.
+        let adjusted = match rgb {
+            DynamicImage::ImageRgb8(rgb) if image.color().has_alpha() => {
+                let mut rgba = image.to_rgba8();
+                for (pixel, adjusted) in rgba.pixels_mut().zip(rgb.pixels()) {
+                    pixel.0[..3].copy_from_slice(&adjusted.0);
+                }
+                DynamicImage::ImageRgba8(rgba)
+            }
+            DynamicImage::ImageRgb16(rgb) if image.color().has_alpha() => {
+                let mut rgba = image.to_rgba16();
+                for (pixel, adjusted) in rgba.pixels_mut().zip(rgb.pixels()) {
+                    pixel.0[..3].copy_from_slice(&adjusted.0);
+                }
+                DynamicImage::ImageRgba16(rgba)
+            }
+            rgb => rgb,
+        };
+
+        match image.color() {
+            ColorType::L8 => DynamicImage::ImageLuma8(adjusted.to_luma8()),
+            ColorType::La8 => DynamicImage::ImageLumaA8(adjusted.to_luma_alpha8()),
+            ColorType::L16 => DynamicImage::ImageLuma16(adjusted.to_luma16()),
+            ColorType::La16 => DynamicImage::ImageLumaA16(adjusted.to_luma_alpha16()),
+            ColorType::Rgb32F => DynamicImage::ImageRgb32F(adjusted.to_rgb32f()),
+            ColorType::Rgba32F => DynamicImage::ImageRgba32F(adjusted.to_rgba32f()),
+            _ => adjusted,
+        }
+    }
+}
+
+/// Returns `image` in 8 bits per channel, for formats that can't hold deeper images.
+fn to_8_bits(image: &DynamicImage) -> DynamicImage {
+    if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    }
+}
+
+/// What was clicked in the adjustments panel.
+#[derive(Clone, Copy)]
+enum FoximgAdjustAction {
+    Reset,
+    Save,
+    Close,
+}
+
+/// Returns where the adjusted copy of the image at `path` is saved by default: next to it, with
+/// "-adjusted" after its name.
+fn adjusted_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().unwrap_or("png".as_ref()).to_string_lossy();
+    path.with_file_name(format!("{stem}-adjusted.{ext}"))
+}
+
+impl Foximg {
+    /// Returns the colour adjustments of the current image, or none if it isn't loaded.
+    fn current_adjustments(&self) -> FoximgAdjustments {
+        self.images
+            .as_ref()
+            .and_then(|images| images.loaded(images.img_current() - 1))
+            .map(|img| img.borrow().adjustments())
+            .unwrap_or_default()
+    }
+
+    /// Passes the colour adjustments of the current image on to the shader that draws it.
+    pub fn update_adjust_shader(&mut self) {
+        let adjustments = self.current_adjustments();
+        let shader = self.resources.channels.get_mut();
+        let brightness = shader.get_shader_location("brightness");
+        let contrast = shader.get_shader_location("contrast");
+        let gamma = shader.get_shader_location("gamma");
+        let saturation = shader.get_shader_location("saturation");
+        let hue = shader.get_shader_location("hue");
+        let grayscale = shader.get_shader_location("grayscale");
+        let invert = shader.get_shader_location("invert");
+
+        // Round the same way as baking does, so that the preview matches the saved image.
+        shader.set_shader_value(brightness, adjustments.brightness.round() / 255.);
+        shader.set_shader_value(contrast, adjustments.contrast);
+        shader.set_shader_value(gamma, adjustments.gamma);
+        shader.set_shader_value(saturation, adjustments.saturation);
+        shader.set_shader_value(hue, adjustments.hue.round());
+        shader.set_shader_value(grayscale, adjustments.grayscale as i32);
+        shader.set_shader_value(invert, adjustments.invert as i32);
+    }
+
+    /// Shows `adjustments` on the current image. They're remembered in its history as a single
+    /// edit from `from` if `remember` is set.
+    fn adjust_current(
+        &mut self,
+        from: FoximgAdjustments,
+        adjustments: FoximgAdjustments,
+        remember: bool,
+    ) {
+        if let Some(ref mut images) = self.images {
+            images.img_with(&mut self.rl, &self.rl_thread, |mut img| {
+                if remember && from != adjustments {
+                    img.edit(FoximgEdit::Adjust {
+                        from,
+                        to: adjustments,
+                    });
+                } else {
+                    img.preview_adjustments(adjustments);
+                }
+            });
+        }
+        self.update_adjust_shader();
+    }
+
+    /// Goes back to showing the current image without any colour adjustments.
+    pub fn reset_adjustments(&mut self) {
+        let from = self.current_adjustments();
+        self.adjust_current(from, FoximgAdjustments::default(), true);
+        self.toast("Reset colour adjustments");
+    }
+
+    /// Shows the colour adjustments panel. This runs its own loop until the panel is closed with
+    /// its Close button or Escape. The adjustments stay applied to the current image afterwards,
+    /// as a single edit that can be undone. Does nothing if the UI is locked.
+    pub fn adjust_colors(&mut self) {
+        if self.images.is_none() || self.lock.is_some() {
+            return;
+        }
+
+        let from = self.current_adjustments();
+        let mut adjustments = from;
+        let mut action = None;
+        while !self.rl.window_should_close() {
+            self.update();
+
+            match action.take() {
+                Some(FoximgAdjustAction::Reset) => adjustments = FoximgAdjustments::default(),
+                Some(FoximgAdjustAction::Save) => self.save_adjusted(),
+                Some(FoximgAdjustAction::Close) => break,
+                None => (),
+            }
+
+            if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) || self.images.is_none() {
+                break;
+            }
+
+            self.adjust_current(from, adjustments, false);
+            FoximgDraw::begin(self, |mut d, images| {
+                if let Some(images) = images {
+                    d.draw_current_img(images);
+                    action = d.draw_adjust_panel(&mut adjustments);
+                }
+            });
+        }
+
+        self.adjust_current(from, adjustments, true);
+    }
+
+    /// Opens the colour adjustments panel if `AdjustColors` is pressed. Returns true if so.
+    pub fn adjust_colors_key(&mut self) -> bool {
+        if self.images.is_some() && self.is_action_pressed(FoximgAction::AdjustColors) {
+            self.adjust_colors();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Bakes the colour adjustments into a copy of the current image, and saves it where the user
+    /// picks. The rotation and mirroring of the image aren't baked.
+    pub fn save_adjusted(&mut self) {
+        let Some(ref images) = self.images else {
+            return;
+        };
+
+        let path = images.img_path().to_path_buf();
+        let adjustments = self.current_adjustments();
+        let Some(output) = tinyfiledialogs::save_file_dialog(
+            "Save Adjusted Image",
+            &self::adjusted_path(&path).to_string_lossy(),
+        ) else {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_DEBUG,
+                "FOXIMG: Cancelled saving adjusted image",
+            );
+            return;
+        };
+
+        let output = PathBuf::from(output);
+        let name = output.file_name().unwrap_or_default().to_string_lossy();
+        let saved = ImageReader::open(&path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(image::ImageError::IoError)
+            .and_then(|reader| reader.decode())
+            .and_then(|image| {
+                let adjusted = adjustments.bake(&image);
+                match adjusted.save(&output) {
+                    Err(image::ImageError::Unsupported(_)) => {
+                        self::to_8_bits(&adjusted).save(&output)
+                    }
+                    saved => saved,
+                }
+            });
+
+        match saved {
+            Ok(()) => self.toast(format!("Saved \"{name}\"")),
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    &format!("FOXIMG: Failed to save adjusted image to {output:?}:"),
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                self.toast(format!("Couldn't save \"{name}\""));
+            }
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the colour adjustments panel in the top right of the window, and returns what was
+    /// clicked in it, if anything.
+    fn draw_adjust_panel(
+        &mut self,
+        adjustments: &mut FoximgAdjustments,
+    ) -> Option<FoximgAdjustAction> {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const WIDTH: f32 = 360.;
+        const PADDING: f32 = 10.;
+        const LABEL_WIDTH: f32 = 90.;
+        const VALUE_WIDTH: f32 = 50.;
+        const ROW: f32 = 26.;
+        const CONTROL: f32 = 18.;
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let height = PADDING * 2. + ROW * 8.;
+        let rect = rrect(screen_width - WIDTH - PADDING, PADDING, WIDTH, height);
+        let x = rect.x + PADDING;
+        let mut y = rect.y + PADDING;
+
+        self.d.draw_rectangle_rec(rect, self.style.bg.alpha(0.9));
+        self.d
+            .draw_rectangle_lines_ex(rect, 1., self.style.accent.alpha(1.));
+        self.d.draw_text_ex(
+            &self.resources.yudit,
+            "Adjust Colors",
+            rvec2(x, y),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent,
+        );
+        y += ROW;
+
+        let sliders = [
+            ("Brightness", &mut adjustments.brightness, -255., 255., 0),
+            ("Contrast", &mut adjustments.contrast, -100., 100., 0),
+            ("Gamma", &mut adjustments.gamma, 0.1, 4., 2),
+            ("Saturation", &mut adjustments.saturation, 0., 2., 2),
+            ("Hue", &mut adjustments.hue, 0., 360., 0),
+        ];
+        let slider_width = WIDTH - PADDING * 2. - LABEL_WIDTH - VALUE_WIDTH;
+        for (label, value, min, max, decimals) in sliders {
+            let text = format!("{:.decimals$}", *value);
+            self.d.gui_slider_bar(
+                rrect(x + LABEL_WIDTH, y, slider_width, CONTROL),
+                label,
+                &text,
+                value,
+                min,
+                max,
+            );
+            y += ROW;
+        }
+
+        self.d.gui_check_box(
+            rrect(x, y, CONTROL, CONTROL),
+            "Grayscale",
+            &mut adjustments.grayscale,
+        );
+        self.d.gui_check_box(
+            rrect(x + WIDTH / 2., y, CONTROL, CONTROL),
+            "Invert",
+            &mut adjustments.invert,
+        );
+        y += ROW;
+
+        let button_width = (WIDTH - PADDING * 4.) / 3.;
+        let buttons = [
+            ("Reset", FoximgAdjustAction::Reset),
+            ("Save...", FoximgAdjustAction::Save),
+            ("Close", FoximgAdjustAction::Close),
+        ];
+        let mut clicked = None;
+        for (i, (text, action)) in buttons.into_iter().enumerate() {
+            let bx = x + (button_width + PADDING) * i as f32;
+            if self
+                .d
+                .gui_button(rrect(bx, y, button_width, CONTROL + 4.), text)
+            {
+                clicked = Some(action);
+            }
+        }
+
+        clicked
+    }
+}
@@ -0,0 +1,134 @@
+//! Defines viewing the channels of an image on their own, which helps with inspecting textures that
+//! pack several maps into their channels, and alpha masks.
+
+use std::cell::RefCell;
+
+use raylib::prelude::*;
+
+use crate::{Foximg, FoximgDraw, images::FoximgImage, keybinds::FoximgAction};
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum FoximgChannelView {
+    /// Shows the image as it is.
+    #[default]
+    Color,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    /// Shows the image with its alpha ignored.
+    Opaque,
+    /// Shows the colour of the image premultiplied by its alpha.
+    Premultiplied,
+}
+
+impl FoximgChannelView {
+    const ALL: [FoximgChannelView; 7] = [
+        FoximgChannelView::Color,
+        FoximgChannelView::Red,
+        FoximgChannelView::Green,
+        FoximgChannelView::Blue,
+        FoximgChannelView::Alpha,
+        FoximgChannelView::Opaque,
+        FoximgChannelView::Premultiplied,
+    ];
+
+    /// Returns the view `by` views away from this one, wrapping around.
+    fn cycle(self, by: isize) -> Self {
+        let i = Self::ALL.iter().position(|&view| view == self).unwrap_or(0);
+        Self::ALL[(i as isize + by).rem_euclid(Self::ALL.len() as isize) as usize]
+    }
+
+    /// Returns the text shown in the corner of the window for the view, or `None` for `Color`.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            FoximgChannelView::Color => None,
+            FoximgChannelView::Red => Some("R"),
+            FoximgChannelView::Green => Some("G"),
+            FoximgChannelView::Blue => Some("B"),
+            FoximgChannelView::Alpha => Some("A"),
+            FoximgChannelView::Opaque => Some("RGB"),
+            FoximgChannelView::Premultiplied => Some("PM"),
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            FoximgChannelView::Color => "Showing all channels",
+            FoximgChannelView::Red => "Showing the red channel",
+            FoximgChannelView::Green => "Showing the green channel",
+            FoximgChannelView::Blue => "Showing the blue channel",
+            FoximgChannelView::Alpha => "Showing the alpha channel",
+            FoximgChannelView::Opaque => "Ignoring alpha",
+            FoximgChannelView::Premultiplied => "Showing premultiplied colour",
+        }
+    }
+}
+
+impl Foximg {
+    /// Shows the current image through `view`.
+    pub fn set_channel_view(&mut self, view: FoximgChannelView) {
+        self.channel_view = view;
+        let shader = self.resources.channels.get_mut();
+        let mode = shader.get_shader_location("mode");
+        shader.set_shader_value(mode, view as i32);
+        self.toast(view.description());
+    }
+
+    /// Shows `view`, or goes back to showing the image as it is if `view` is already shown.
+    pub fn toggle_channel_view(&mut self, view: FoximgChannelView) {
+        if self.channel_view == view {
+            self.set_channel_view(FoximgChannelView::Color);
+        } else {
+            self.set_channel_view(view);
+        }
+    }
+
+    /// Switches to the next channel view if `NextChannel` is pressed, or the previous one if
+    /// `PreviousChannel` is. The action of each channel toggles its view. Returns true if so.
+    pub fn channel_view_img(&mut self) -> bool {
+        const CHANNEL_ACTIONS: [(FoximgAction, FoximgChannelView); 4] = [
+            (FoximgAction::RedChannel, FoximgChannelView::Red),
+            (FoximgAction::GreenChannel, FoximgChannelView::Green),
+            (FoximgAction::BlueChannel, FoximgChannelView::Blue),
+            (FoximgAction::AlphaChannel, FoximgChannelView::Alpha),
+        ];
+
+        if self.images.is_none() {
+            return false;
+        }
+
+        let by = if self.is_action_pressed(FoximgAction::NextChannel) {
+            Some(1)
+        } else if self.is_action_pressed(FoximgAction::PreviousChannel) {
+            Some(-1)
+        } else {
+            None
+        };
+        if let Some(by) = by {
+            self.set_channel_view(self.channel_view.cycle(by));
+            return true;
+        }
+
+        let view = CHANNEL_ACTIONS
+            .iter()
+            .find(|&&(action, _)| self.is_action_pressed(action))
+            .map(|&(_, view)| view);
+        match view {
+            Some(view) => {
+                self.toggle_channel_view(view);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<'a> FoximgDraw<'a> {
+    /// Returns the shader to draw `img`, the current image, with, or `None` if it's shown as it is.
+    pub fn channel_shader(&self, img: &FoximgImage) -> Option<&'a RefCell<Shader>> {
+        let resources = self.resources;
+        (self.channel_view != FoximgChannelView::Color || !img.adjustments().is_identity())
+            .then_some(&resources.channels)
+    }
+}
@@ -9,7 +9,16 @@ use image::{
 use raylib::prelude::*;
 use serde::{Serialize, ser::SerializeMap};
 
-use crate::{FoximgArgs, FoximgInfoLanguage, foximg_log};
+use crate::{
+    FoximgArgs, FoximgInfoLanguage, foximg_log,
+    histogram::FoximgHistogram,
+    xmp::{self, FoximgXmp},
+};
+
+pub mod config_check;
+pub mod diff;
+pub mod resize;
+pub mod thumbnail;
 
 type FoximgInfoTracelog = Rc<dyn Fn(TraceLogLevel, &str)>;
 
@@ -196,11 +205,14 @@ struct FoximgImageInfo<'a> {
 
     pub animated: Option<FoximgImageAnimationInfo>,
     pub exif: Option<FoximgExifInfo>,
+    pub xmp: Option<FoximgXmp>,
+    pub histogram: Option<FoximgHistogram>,
 }
 
 struct FoximgInfo {
     path: PathBuf,
     no_exif: bool,
+    histogram: bool,
     language: FoximgInfoLanguage,
 
     tracelog: FoximgInfoTracelog,
@@ -230,6 +242,7 @@ impl FoximgInfo {
         tracelog(TraceLogLevel::LOG_DEBUG, "Foximg initialized successfully");
         Ok(Self {
             no_exif: args.quiet,
+            histogram: args.histogram,
             path,
             language,
             tracelog,
@@ -272,12 +285,37 @@ impl FoximgInfo {
         }?;
 
         (self.tracelog)(TraceLogLevel::LOG_DEBUG, "Decoded image successfully");
+        let sidecar = xmp::sidecar_path(&self.path);
+        let xmp = if sidecar.exists() {
+            (self.tracelog)(
+                TraceLogLevel::LOG_DEBUG,
+                &format!("Reading sidecar ({}):", sidecar.display()),
+            );
+            Some(FoximgXmp::read(&self.path)?)
+        } else {
+            None
+        };
+
+        let histogram = if self.histogram {
+            (self.tracelog)(TraceLogLevel::LOG_DEBUG, "Making histogram:");
+            let histogram = FoximgHistogram::open(&self.path)?;
+            (self.tracelog)(
+                TraceLogLevel::LOG_DEBUG,
+                &format!("   > Made {} histogram successfully", histogram.depth),
+            );
+            Some(histogram)
+        } else {
+            None
+        };
+
         let info = FoximgImageInfo {
             width: decoder.dimensions.0,
             height: decoder.dimensions.1,
             color_type: decoder.color_type,
             animated: decoder.animation_info,
             exif: decoder.exif_info,
+            xmp,
+            histogram,
             filename,
             mime,
             extensions,
@@ -0,0 +1,75 @@
+//! Headless config check mode, which reads every config file the same way foximg does on startup
+//! and prints the problems found in them, without changing any of them.
+
+use raylib::prelude::*;
+
+use crate::{
+    FoximgArgs,
+    config::{
+        self, FoximgCompareSettings, FoximgConfig, FoximgCullSettings, FoximgHistogramSettings,
+        FoximgIcon, FoximgOnionSettings, FoximgOverlaySettings, FoximgSettings,
+        FoximgSlideshowSettings, FoximgState, FoximgStyle, FoximgTrashSettings,
+    },
+    foximg_log,
+    keybinds::FoximgKeybinds,
+};
+
+/// Prints what was found in the config file of `C`. Returns whether it has any problems.
+fn check<C>() -> bool
+where
+    C: FoximgConfig,
+{
+    match config::config_check::<C>() {
+        Ok((path, None)) => {
+            println!("{}: Not created yet", path.display());
+            false
+        }
+        Ok((path, Some(problems))) if problems.is_empty() => {
+            println!("{}: OK", path.display());
+            false
+        }
+        Ok((path, Some(problems))) => {
+            println!("{}:", path.display());
+            for problem in problems {
+                let mut lines = problem.lines();
+                if let Some(first) = lines.next() {
+                    println!("    > {first}");
+                }
+                for line in lines {
+                    println!("      {line}");
+                }
+            }
+            true
+        }
+        Err(e) => {
+            println!("{}: Couldn't be read: {e}", C::FILE);
+            true
+        }
+    }
+}
+
+pub fn run(args: FoximgArgs) {
+    let checks = [
+        self::check::<FoximgSettings>(),
+        self::check::<FoximgStyle>(),
+        self::check::<FoximgKeybinds>(),
+        self::check::<FoximgState>(),
+        self::check::<FoximgSlideshowSettings>(),
+        self::check::<FoximgTrashSettings>(),
+        self::check::<FoximgCompareSettings>(),
+        self::check::<FoximgOnionSettings>(),
+        self::check::<FoximgHistogramSettings>(),
+        self::check::<FoximgOverlaySettings>(),
+        self::check::<FoximgCullSettings>(),
+        self::check::<FoximgIcon>(),
+    ];
+
+    if checks.contains(&true) {
+        std::process::exit(1);
+    } else if args.verbose {
+        foximg_log::tracelog(
+            TraceLogLevel::LOG_DEBUG,
+            "Foximg uninitialized successfully. Goodbye!",
+        );
+    }
+}
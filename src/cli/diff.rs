@@ -0,0 +1,109 @@
+//! Headless diff mode, which prints how different two images are as TOML or JSON.
+
+use std::{borrow::Cow, path::Path};
+
+use image::{ImageReader, RgbaImage};
+use raylib::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    FoximgArgs, FoximgInfoLanguage,
+    diff::{FoximgDiff, FoximgDiffInput, FoximgDiffMetrics},
+    foximg_log,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct FoximgDiffImage<'a> {
+    filename: Cow<'a, str>,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct FoximgDiffInfo<'a> {
+    a: FoximgDiffImage<'a>,
+    b: FoximgDiffImage<'a>,
+    metrics: FoximgDiffMetrics,
+}
+
+fn load(path: &Path) -> anyhow::Result<RgbaImage> {
+    let image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+    Ok(image.into_rgba8())
+}
+
+fn diff_image<'a>(path: &'a Path, image: &RgbaImage) -> FoximgDiffImage<'a> {
+    FoximgDiffImage {
+        filename: path.file_name().unwrap_or_default().to_string_lossy(),
+        width: image.width(),
+        height: image.height(),
+    }
+}
+
+fn diff_input(image: &RgbaImage) -> FoximgDiffInput<'_> {
+    FoximgDiffInput {
+        rgba: image.as_raw(),
+        width: image.width(),
+        height: image.height(),
+    }
+}
+
+/// Exit code for when the images differ by more than the threshold. Errors exit with 1.
+const EXIT_DIFFERENT: i32 = 2;
+
+/// Prints the metrics. Returns whether any pixels differ by more than the threshold.
+fn try_run(args: &FoximgArgs, language: FoximgInfoLanguage) -> anyhow::Result<bool> {
+    let (Some(a), Some(b)) = (args.path, args.diff_path) else {
+        anyhow::bail!("\"--diff\" must have an A and B argument");
+    };
+
+    let (a, b) = (Path::new(a), Path::new(b));
+    if args.verbose {
+        foximg_log::tracelog(
+            TraceLogLevel::LOG_DEBUG,
+            &format!("Comparing {} against {}", b.display(), a.display()),
+        );
+    }
+
+    let (a_image, b_image) = (self::load(a)?, self::load(b)?);
+    let diff = FoximgDiff::new(
+        self::diff_input(&a_image),
+        self::diff_input(&b_image),
+        args.diff_threshold,
+    );
+    let different = diff.metrics.differing_pixels > 0 || !diff.metrics.sizes_match;
+    let info = FoximgDiffInfo {
+        a: self::diff_image(a, &a_image),
+        b: self::diff_image(b, &b_image),
+        metrics: diff.metrics,
+    };
+
+    let info = match language {
+        FoximgInfoLanguage::Toml => toml::to_string(&info)?,
+        FoximgInfoLanguage::Json => serde_json::to_string_pretty(&info)?,
+    };
+
+    println!("{info}");
+    Ok(different)
+}
+
+pub fn run(args: FoximgArgs, language: FoximgInfoLanguage) {
+    let different = match self::try_run(&args, language) {
+        Ok(different) => different,
+        Err(e) => {
+            foximg_log::tracelog(TraceLogLevel::LOG_ERROR, &format!("{e}"));
+            std::process::exit(1);
+        }
+    };
+
+    if args.verbose {
+        foximg_log::tracelog(
+            TraceLogLevel::LOG_DEBUG,
+            "Foximg uninitialized successfully. Goodbye!",
+        );
+    }
+    if different {
+        std::process::exit(EXIT_DIFFERENT);
+    }
+}
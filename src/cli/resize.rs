@@ -0,0 +1,60 @@
+//! Headless resize mode, which resizes an image the same way the resize dialog does.
+
+use std::path::Path;
+
+use raylib::prelude::*;
+
+use crate::{
+    FoximgArgs, foximg_log,
+    resize::{self, FoximgResizeTarget},
+};
+
+fn try_run(args: &FoximgArgs) -> anyhow::Result<()> {
+    let (Some(input), Some(output), Some(target)) =
+        (args.path, args.resize_output, args.resize_target)
+    else {
+        anyhow::bail!("\"--resize\" must have an INPUT, OUTPUT and SIZE argument");
+    };
+
+    let (input, output) = (Path::new(input), Path::new(output));
+    let target: FoximgResizeTarget = target.parse().map_err(|e| anyhow::anyhow!("{e}"))?;
+    let (image, format) = resize::open(input)?;
+    let (width, height) = target.dimensions(image.width(), image.height());
+    if width.max(height) > resize::MAX_SIDE as u32 {
+        anyhow::bail!(
+            "Can't resize to {width}x{height}. Sides can be at most {} pixels",
+            resize::MAX_SIDE
+        );
+    }
+    let resized = resize::resize(&image, target, args.resample);
+
+    if args.verbose {
+        foximg_log::tracelog(
+            TraceLogLevel::LOG_DEBUG,
+            &format!(
+                "Resizing {} from {}x{} to {}x{} with {:?}",
+                input.display(),
+                image.width(),
+                image.height(),
+                resized.width(),
+                resized.height(),
+                args.resample
+            ),
+        );
+    }
+
+    resized.save_with_format(output, format)?;
+    Ok(())
+}
+
+pub fn run(args: FoximgArgs) {
+    if let Err(e) = self::try_run(&args) {
+        foximg_log::tracelog(TraceLogLevel::LOG_ERROR, &format!("{e}"));
+        std::process::exit(1);
+    } else if args.verbose {
+        foximg_log::tracelog(
+            TraceLogLevel::LOG_DEBUG,
+            "Foximg uninitialized successfully. Goodbye!",
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Headless thumbnailer mode, meant to be called by file managers through `foximg.thumbnailer`.
+
+use std::path::Path;
+
+use raylib::prelude::*;
+
+use crate::{
+    FoximgArgs, foximg_log,
+    thumbnail::{self, FoximgThumbnailSize},
+};
+
+fn try_run(args: &FoximgArgs) -> anyhow::Result<()> {
+    let (Some(input), Some(output), Some(size)) =
+        (args.path, args.thumbnail_output, args.thumbnail_size)
+    else {
+        anyhow::bail!("\"--thumbnail\" must have an INPUT, OUTPUT and SIZE argument");
+    };
+
+    let input = Path::new(input).canonicalize()?;
+    let size: u32 = size
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid thumbnail size \"{size}\": {e}"))?;
+    let size = FoximgThumbnailSize::from_requested(size);
+
+    if args.verbose {
+        foximg_log::tracelog(
+            TraceLogLevel::LOG_DEBUG,
+            &format!(
+                "Thumbnailing {} as {:?} ({}px)",
+                input.display(),
+                size,
+                size.pixels()
+            ),
+        );
+    }
+
+    let (thumbnail, dimensions) = thumbnail::generate(&input, size)?;
+    thumbnail::write(&thumbnail, &input, dimensions, Path::new(output))?;
+    Ok(())
+}
+
+pub fn run(args: FoximgArgs) {
+    if let Err(e) = self::try_run(&args) {
+        foximg_log::tracelog(TraceLogLevel::LOG_ERROR, &format!("{e}"));
+        // File managers rely on the exit code to know if the thumbnail got created.
+        std::process::exit(1);
+    } else if args.verbose {
+        foximg_log::tracelog(
+            TraceLogLevel::LOG_DEBUG,
+            "Foximg uninitialized successfully. Goodbye!",
+        );
+    }
+}
+
+pub fn thumbnailer() {
+    print!("{}", thumbnail::thumbnailer());
+}
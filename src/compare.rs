@@ -0,0 +1,677 @@
+//! Defines compare mode, where an image is pinned and drawn next to the current one. Both images
+//! share the same zoom, pan, rotation and flips, and the values of the pixels under the cursor are
+//! shown for both.
+
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw,
+    config::{FoximgCompareFit, FoximgCompareLayout, FoximgCompareSettings, FoximgConfig},
+    diff::{FoximgDiff, FoximgDiffInput},
+    images::{FoximgImage, FoximgImages},
+    keybinds::FoximgAction,
+    resources,
+};
+
+/// The pixels of an image as RGBA bytes, kept around to read the colour under the cursor and to
+/// compute differences.
+struct FoximgPixels {
+    path: PathBuf,
+    width: i32,
+    height: i32,
+    rgba: Vec<u8>,
+}
+
+impl FoximgPixels {
+    fn new(path: PathBuf, img: &FoximgImage) -> Option<Self> {
+        let rgba = img
+            .load_colors()?
+            .into_iter()
+            .flat_map(|c| [c.r, c.g, c.b, c.a])
+            .collect();
+
+        Some(Self {
+            path,
+            width: img.width(),
+            height: img.height(),
+            rgba,
+        })
+    }
+
+    fn get(&self, (x, y): (i32, i32)) -> Option<Color> {
+        let i = ((y * self.width + x) * 4) as usize;
+        let rgba = self.rgba.get(i..i + 4)?;
+        Some(Color::new(rgba[0], rgba[1], rgba[2], rgba[3]))
+    }
+
+    fn diff_input(&self) -> FoximgDiffInput<'_> {
+        FoximgDiffInput {
+            rgba: &self.rgba,
+            width: self.width as u32,
+            height: self.height as u32,
+        }
+    }
+}
+
+/// The difference between the pinned image and the current image, with its heatmap.
+struct FoximgCompareDiff {
+    path: PathBuf,
+    diff: FoximgDiff,
+    heatmap: Option<FoximgImage>,
+    amplification: f32,
+}
+
+pub struct FoximgCompare {
+    path: PathBuf,
+    pinned: Rc<RefCell<FoximgImage>>,
+    pinned_pixels: Option<FoximgPixels>,
+    current_pixels: Option<FoximgPixels>,
+    diff: Option<FoximgCompareDiff>,
+    /// Where the slider of the split layout is, from 0 at the left edge to 1 at the right edge.
+    split: f32,
+    dragging: bool,
+}
+
+impl FoximgCompare {
+    /// Returns the pinned image.
+    pub fn pinned_img(&self) -> Rc<RefCell<FoximgImage>> {
+        self.pinned.clone()
+    }
+
+    /// How far from the slider the mouse can grab it.
+    const SPLIT_GRAB: f32 = 6.;
+
+    /// Returns the rectangles the pinned and the current image are laid out in, followed by the
+    /// rectangles they're cut off at. They're only different in the split layout, where both images
+    /// are laid out over the whole screen.
+    fn panels(
+        &self,
+        layout: FoximgCompareLayout,
+        screen: Rectangle,
+    ) -> [(Rectangle, Rectangle); 2] {
+        match layout {
+            FoximgCompareLayout::SideBySide => {
+                let left = rrect(screen.x, screen.y, screen.width / 2., screen.height);
+                let right = rrect(left.x + left.width, screen.y, left.width, screen.height);
+                [(left, left), (right, right)]
+            }
+            FoximgCompareLayout::TopBottom => {
+                let top = rrect(screen.x, screen.y, screen.width, screen.height / 2.);
+                let bottom = rrect(screen.x, top.y + top.height, screen.width, top.height);
+                [(top, top), (bottom, bottom)]
+            }
+            FoximgCompareLayout::Split => {
+                let x = screen.width * self.split;
+                let left = rrect(screen.x, screen.y, x, screen.height);
+                let right = rrect(screen.x + x, screen.y, screen.width - x, screen.height);
+                [(screen, left), (screen, right)]
+            }
+            FoximgCompareLayout::Difference => [(screen, screen), (screen, screen)],
+        }
+    }
+}
+
+impl Foximg {
+    /// Returns the area images are drawn in, leaving out the filmstrip.
+    fn compare_screen(&self) -> Rectangle {
+        rrect(
+            0,
+            0,
+            self.rl.get_screen_width(),
+            self.rl.get_screen_height().as_f32() - self.filmstrip_height(),
+        )
+    }
+
+    /// Pins the current image to compare the other images against, or stops comparing if an image
+    /// is already pinned. Does nothing if the UI is locked.
+    pub fn toggle_compare(&mut self) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        if self.compare.take().is_some() {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Stopped comparing");
+            self.toast("Stopped comparing");
+            return;
+        }
+
+        let Some(ref mut images) = self.images else {
+            return;
+        };
+
+        let path = images.img_path().to_path_buf();
+        let Some(pinned) = images.img_get(&mut self.rl, &self.rl_thread) else {
+            self.toast("Couldn't pin an image that failed to load");
+            return;
+        };
+
+        let pinned_pixels = FoximgPixels::new(path.clone(), &pinned.borrow());
+        self.compare = Some(FoximgCompare {
+            path: path.clone(),
+            pinned,
+            pinned_pixels,
+            current_pixels: None,
+            diff: None,
+            split: 0.5,
+            dragging: false,
+        });
+        self.rl.trace_log(
+            TraceLogLevel::LOG_DEBUG,
+            &format!("FOXIMG: Pinned {path:?} to compare against"),
+        );
+        self.toast(format!(
+            "Pinned \"{}\". Go to another image to compare them",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ));
+    }
+
+    /// Changes the compare settings and saves them.
+    pub fn set_compare_settings(&mut self, f: impl FnOnce(&mut FoximgCompareSettings)) {
+        f(&mut self.compare_settings);
+        self.compare_settings.to_file(&self.rl);
+    }
+
+    /// Pins or unpins the current image if `Compare` is pressed, or switches to the next layout if
+    /// `CompareLayout` is pressed while comparing. Returns true if so.
+    pub fn compare_img(&mut self) -> bool {
+        let layout = self.is_action_pressed(FoximgAction::CompareLayout);
+        if self.images.is_none() || !(layout || self.is_action_pressed(FoximgAction::Compare)) {
+            return false;
+        }
+
+        if self.compare.is_some() && layout {
+            self.set_compare_settings(|settings| {
+                settings.layout = match settings.layout {
+                    FoximgCompareLayout::SideBySide => FoximgCompareLayout::TopBottom,
+                    FoximgCompareLayout::TopBottom => FoximgCompareLayout::Split,
+                    FoximgCompareLayout::Split => FoximgCompareLayout::Difference,
+                    FoximgCompareLayout::Difference => FoximgCompareLayout::SideBySide,
+                }
+            });
+        } else {
+            self.toggle_compare();
+        }
+        true
+    }
+
+    /// Returns whether the slider of the split layout is being dragged.
+    pub fn compare_dragging(&self) -> bool {
+        self.compare
+            .as_ref()
+            .is_some_and(|compare| compare.dragging)
+    }
+
+    /// Returns the mouse position relative to the panel it's over while comparing. Both images are
+    /// zoomed and panned in the coordinates of their own panel, which keeps them locked together.
+    pub fn compare_mouse_pos(&self) -> Vector2 {
+        let Some(ref compare) = self.compare else {
+            return self.mouse_pos;
+        };
+
+        let panels = compare.panels(self.compare_settings.layout, self.compare_screen());
+        let (layout, _) = panels
+            .into_iter()
+            .find(|(_, clip)| clip.check_collision_point_rec(self.mouse_pos))
+            .unwrap_or(panels[1]);
+        self.mouse_pos - rvec2(layout.x, layout.y)
+    }
+
+    /// Updates compare mode. Reads back the pixels of the current image once it changes, and drags
+    /// the slider of the split layout. The onion skin takes over from compare mode while it's shown.
+    pub fn update_compare(&mut self) {
+        if self.onion.is_some() {
+            return;
+        }
+
+        let Some(mut compare) = self.compare.take() else {
+            return;
+        };
+
+        if let Some(ref mut images) = self.images {
+            let path = images.img_path();
+            if compare
+                .current_pixels
+                .as_ref()
+                .is_none_or(|pixels| pixels.path != path)
+            {
+                let path = path.to_path_buf();
+                compare.current_pixels = images
+                    .img_get(&mut self.rl, &self.rl_thread)
+                    .and_then(|img| FoximgPixels::new(path, &img.borrow()));
+            }
+        }
+
+        if self.compare_settings.layout == FoximgCompareLayout::Difference {
+            self.update_compare_diff(&mut compare);
+        }
+
+        if self.compare_settings.layout == FoximgCompareLayout::Split {
+            let screen = self.compare_screen();
+            let x = screen.width * compare.split;
+            let on_slider = (self.mouse_pos.x - x).abs() <= FoximgCompare::SPLIT_GRAB
+                && screen.check_collision_point_rec(self.mouse_pos);
+
+            if on_slider
+                && self
+                    .rl
+                    .is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            {
+                compare.dragging = true;
+            } else if !self.rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+                compare.dragging = false;
+            }
+
+            if compare.dragging {
+                compare.split = (self.mouse_pos.x / screen.width).clamp(0., 1.);
+            }
+            if on_slider || compare.dragging {
+                self.rl
+                    .set_mouse_cursor(MouseCursor::MOUSE_CURSOR_RESIZE_EW);
+            }
+        } else {
+            compare.dragging = false;
+        }
+
+        self.compare = Some(compare);
+    }
+
+    /// Computes the difference between the pinned image and the current image once the current
+    /// image changes, and makes its heatmap again once the diff settings change.
+    fn update_compare_diff(&mut self, compare: &mut FoximgCompare) {
+        let (Some(pinned), Some(current)) = (&compare.pinned_pixels, &compare.current_pixels)
+        else {
+            compare.diff = None;
+            return;
+        };
+
+        let threshold = self.compare_settings.diff_threshold;
+        let amplification = self.compare_settings.diff_amplification;
+        let diff = match compare.diff {
+            Some(ref mut diff) if diff.path == current.path => {
+                if diff.diff.metrics.threshold == threshold && diff.amplification == amplification {
+                    return;
+                }
+
+                diff.diff.set_threshold(threshold);
+                diff
+            }
+            _ => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_DEBUG,
+                    &format!(
+                        "FOXIMG: Comparing {:?} against {:?}",
+                        current.path, pinned.path
+                    ),
+                );
+                compare.diff.insert(FoximgCompareDiff {
+                    path: current.path.clone(),
+                    diff: FoximgDiff::new(pinned.diff_input(), current.diff_input(), threshold),
+                    heatmap: None,
+                    amplification,
+                })
+            }
+        };
+
+        let metrics = &diff.diff.metrics;
+        let heatmap = FoximgImage::from_rgba(
+            &mut self.rl,
+            &self.rl_thread,
+            &diff.diff.heatmap(amplification),
+            metrics.width as i32,
+            metrics.height as i32,
+        );
+        diff.amplification = amplification;
+        diff.heatmap = match heatmap {
+            Ok(heatmap) => Some(heatmap),
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    "FOXIMG: Failed to make the difference heatmap:",
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                None
+            }
+        };
+    }
+
+    /// Changes the amplification of the difference heatmap with `DiffAmplificationUp` and
+    /// `DiffAmplificationDown`, or its threshold with `DiffThresholdUp` and `DiffThresholdDown`,
+    /// while it's shown. Returns true if so.
+    pub fn adjust_diff_img(&mut self) -> bool {
+        const MAX_AMPLIFICATION: f32 = 64.;
+
+        if self.compare.is_none() || self.compare_settings.layout != FoximgCompareLayout::Difference
+        {
+            return false;
+        }
+
+        const STEPS: [(FoximgAction, bool, i32); 4] = [
+            (FoximgAction::DiffAmplificationUp, false, 1),
+            (FoximgAction::DiffAmplificationDown, false, -1),
+            (FoximgAction::DiffThresholdUp, true, 1),
+            (FoximgAction::DiffThresholdDown, true, -1),
+        ];
+
+        let Some(&(_, threshold, step)) = STEPS
+            .iter()
+            .find(|&&(action, _, _)| self.is_action_pressed_or_repeat(action))
+        else {
+            return false;
+        };
+
+        self.set_compare_settings(|settings| {
+            if threshold {
+                settings.diff_threshold = settings.diff_threshold.saturating_add_signed(step as i8);
+            } else {
+                settings.diff_amplification =
+                    (settings.diff_amplification * 2f32.powi(step)).clamp(1., MAX_AMPLIFICATION);
+            }
+        });
+        true
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the pinned image and the current image according to the compare settings.
+    pub fn draw_compare(&mut self, images: &mut FoximgImages) {
+        let Some(compare) = self.compare else {
+            return;
+        };
+
+        let Some(current) = images.img_get(&mut self.d, self.rl_thread) else {
+            self.draw_large_centered_text(":(");
+            return;
+        };
+
+        current.borrow_mut().update_texture(&self.d);
+        let current = current.borrow();
+        let pinned = compare.pinned.borrow();
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let screen_height = self.d.get_screen_height().as_f32() - self.filmstrip_height();
+        let screen = rrect(0, 0, screen_width, screen_height);
+        let layout = self.compare_settings.layout;
+        // Differences are taken pixel for pixel, so the heatmap is always lined up that way.
+        let align_top_left = self.compare_settings.fit == FoximgCompareFit::TopLeft
+            || layout == FoximgCompareLayout::Difference;
+        let panels = compare.panels(layout, screen);
+        let mouse_pos = self.d.get_mouse_position();
+
+        if *self.mouse_wheel == 0. {
+            *self.camera = Camera2D {
+                zoom: 1.,
+                ..Default::default()
+            };
+        }
+
+        // The current image decides the scale when the images are lined up pixel for pixel.
+        let current_scale = self.img_scale(&current, panels[1].0.width, panels[1].0.height);
+        let mouse_panel = panels
+            .iter()
+            .find(|(_, clip)| clip.check_collision_point_rec(mouse_pos))
+            .map(|(rect, _)| *rect);
+        let mut pixels = [None, None];
+
+        for (i, (img, (rect, clip))) in [&*pinned, &*current].into_iter().zip(panels).enumerate() {
+            let scale = if align_top_left {
+                current_scale
+            } else {
+                self.img_scale(img, rect.width, rect.height)
+            };
+            let camera = Camera2D {
+                offset: self.camera.offset + rvec2(rect.x, rect.y),
+                ..*self.camera
+            };
+
+            if layout != FoximgCompareLayout::Difference {
+                let mut s = self.d.begin_scissor_mode(
+                    clip.x as i32,
+                    clip.y as i32,
+                    clip.width as i32,
+                    clip.height as i32,
+                );
+                let mut c = s.begin_mode2D(camera);
+                img.draw_like(
+                    &mut c,
+                    &current,
+                    rect.width,
+                    rect.height,
+                    scale,
+                    align_top_left && i == 0,
+                    Color::WHITE,
+                );
+            }
+
+            // The same point of both images is read, no matter which panel the mouse is over.
+            if let Some(mouse_panel) = mouse_panel {
+                let point = self.d.get_screen_to_world2D(
+                    mouse_pos - rvec2(mouse_panel.x, mouse_panel.y),
+                    *self.camera,
+                );
+                let pixel = img.pixel_at(
+                    &current,
+                    rect.width,
+                    rect.height,
+                    scale,
+                    align_top_left && i == 0,
+                    point,
+                );
+                let img_pixels = if i == 0 {
+                    &compare.pinned_pixels
+                } else {
+                    &compare.current_pixels
+                };
+                pixels[i] = pixel.zip(img_pixels.as_ref().and_then(|p| p.get(pixel?)));
+            }
+        }
+
+        if layout == FoximgCompareLayout::Difference
+            && let Some(heatmap) = compare.diff.as_ref().and_then(|diff| diff.heatmap.as_ref())
+        {
+            let mut c = self.d.begin_mode2D(*self.camera);
+            heatmap.draw_like(
+                &mut c,
+                &current,
+                screen_width,
+                screen_height,
+                current_scale,
+                true,
+                Color::WHITE,
+            );
+        }
+
+        if layout == FoximgCompareLayout::Split {
+            let x = screen_width * compare.split;
+            self.d
+                .draw_line_ex(rvec2(x, 0), rvec2(x, screen_height), 2., self.style.accent);
+        }
+
+        let names = [compare.path.as_path(), images.img_path()]
+            .map(|path| path.file_name().unwrap_or_default().to_string_lossy());
+        if layout == FoximgCompareLayout::Difference {
+            self.draw_compare_name(&format!("{} vs {}", names[0], names[1]), screen);
+            if let Some(ref diff) = compare.diff {
+                self.draw_diff_metrics(diff);
+            }
+        } else {
+            for ((_, clip), name) in panels.into_iter().zip(names) {
+                self.draw_compare_name(&name, clip);
+            }
+        }
+        self.draw_compare_pixels(pixels, screen_width);
+
+        current.draw_manipulation_info(
+            &mut self.d,
+            self.resources,
+            self.style,
+            screen_width,
+            screen_height,
+            None,
+        );
+        self.draw_fullscreen_title();
+        self.draw_skip_count(&current, screen_width, screen_height);
+    }
+
+    /// Draws the metrics of a difference in the top left corner.
+    fn draw_diff_metrics(&mut self, diff: &FoximgCompareDiff) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const PADDING: f32 = 6.;
+        const MARGIN: f32 = 10.;
+        const LINE: f32 = FONT_SIZE + PADDING;
+
+        let metrics = &diff.diff.metrics;
+        let total = u64::from(metrics.width) * u64::from(metrics.height);
+        let mut lines = vec![
+            format!("Max difference: {}", metrics.max_abs_diff),
+            format!("Mean difference: {:.3}", metrics.mean_abs_diff),
+            match metrics.psnr {
+                Some(psnr) => format!("PSNR: {psnr:.2} dB"),
+                None => "PSNR: identical".to_string(),
+            },
+            format!("SSIM: {:.4}", metrics.ssim),
+            format!(
+                "Differing pixels: {} of {total} ({:.2}%)",
+                metrics.differing_pixels,
+                metrics.differing_pixels as f64 / total.max(1) as f64 * 100.
+            ),
+            format!(
+                "Amplification: {}x, threshold: {}",
+                diff.amplification, metrics.threshold
+            ),
+        ];
+        if !metrics.sizes_match {
+            lines.push(format!(
+                "Sizes differ. Compared the top left {}x{}",
+                metrics.width, metrics.height
+            ));
+        }
+
+        let yudit = &self.resources.yudit;
+        let width = lines
+            .iter()
+            .map(|line| yudit.measure_text(line, FONT_SIZE, FONT_SPACING).x)
+            .fold(0., f32::max)
+            + PADDING * 2.;
+        let mut y = MARGIN;
+        // Make room for the title drawn in fullscreen.
+        if self.state.fullscreen {
+            y += FONT_SIZE + MARGIN;
+        }
+
+        let rect = rrect(MARGIN, y, width, LINE * lines.len() as f32 + PADDING);
+        self.d.draw_rectangle_rec(rect, self.style.bg.alpha(0.75));
+        y += PADDING;
+        for line in lines {
+            self.d.draw_text_ex(
+                yudit,
+                &line,
+                rvec2(rect.x + PADDING, y),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent,
+            );
+            y += LINE;
+        }
+    }
+
+    /// Draws the name of an image along the top of the rectangle it's drawn in.
+    fn draw_compare_name(&mut self, name: &str, clip: Rectangle) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const PADDING: f32 = 4.;
+        const MARGIN: f32 = 10.;
+
+        let yudit = &self.resources.yudit;
+        let text_width = yudit.measure_text(name, FONT_SIZE, FONT_SPACING).x;
+        let rect = rrect(
+            clip.x + clip.width / 2. - text_width / 2. - PADDING,
+            clip.y + MARGIN,
+            text_width + PADDING * 2.,
+            FONT_SIZE + PADDING * 2.,
+        );
+
+        let mut s = self.d.begin_scissor_mode(
+            clip.x as i32,
+            clip.y as i32,
+            clip.width as i32,
+            clip.height as i32,
+        );
+        s.draw_rectangle_rec(rect, self.style.bg.alpha(0.75));
+        s.draw_text_ex(
+            yudit,
+            name,
+            rvec2(rect.x + PADDING, rect.y + PADDING),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent,
+        );
+    }
+
+    /// Draws the position and colour of the pixels under the cursor in the top right corner, for
+    /// the pinned image and then the current one.
+    fn draw_compare_pixels(&mut self, pixels: [Option<((i32, i32), Color)>; 2], screen_width: f32) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const PADDING: f32 = 6.;
+        const MARGIN: f32 = 10.;
+        const LINE: f32 = FONT_SIZE + PADDING;
+
+        if pixels.iter().all(Option::is_none) {
+            return;
+        }
+
+        let lines: Vec<(String, Option<Color>)> = ["Pinned", "Current"]
+            .into_iter()
+            .zip(pixels)
+            .map(|(name, pixel)| match pixel {
+                Some(((x, y), c)) => (
+                    format!(
+                        "{name} {x}, {y}: {} {} {} {} #{:02X}{:02X}{:02X}{:02X}",
+                        c.r, c.g, c.b, c.a, c.r, c.g, c.b, c.a
+                    ),
+                    Some(c),
+                ),
+                None => (format!("{name}: -"), None),
+            })
+            .collect();
+
+        let yudit = &self.resources.yudit;
+        let width = lines
+            .iter()
+            .map(|(text, _)| yudit.measure_text(text, FONT_SIZE, FONT_SPACING).x)
+            .fold(0., f32::max)
+            + FONT_SIZE
+            + PADDING * 3.;
+        let rect = rrect(
+            screen_width - width - MARGIN,
+            MARGIN,
+            width,
+            LINE * lines.len() as f32 + PADDING,
+        );
+        self.d.draw_rectangle_rec(rect, self.style.bg.alpha(0.75));
+
+        let mut y = rect.y + PADDING;
+        for (text, color) in lines {
+            let swatch = rrect(rect.x + PADDING, y, FONT_SIZE, FONT_SIZE);
+            if let Some(color) = color {
+                self.d.draw_rectangle_rec(swatch, color);
+            }
+            self.d
+                .draw_rectangle_lines_ex(swatch, 1., self.style.accent.alpha(0.5));
+            self.d.draw_text_ex(
+                yudit,
+                &text,
+                rvec2(swatch.x + swatch.width + PADDING, y),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent,
+            );
+            y += LINE;
+        }
+    }
+}
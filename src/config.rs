@@ -1,17 +1,23 @@
 use std::{
     borrow::Cow,
+    cell::RefCell,
     fs::{self, File},
-    io::Write,
+    io::{self, Write},
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 use foximg_color::FoximgColor;
+pub use watch::FoximgConfigWatcher;
 use raylib::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{foximg_log, Foximg};
+use crate::{foximg_log, images, Foximg};
 
 mod foximg_color;
+mod recover;
+mod themes;
+mod watch;
 #[cfg(target_os = "windows")]
 mod win32;
 
@@ -85,14 +91,119 @@ fn get_config_path(
     }
 }
 
-fn config_try_new<C>(rl: &mut RaylibHandle) -> anyhow::Result<C>
+/// Reads the config file of `C`. Returns `None` if it doesn't exist yet.
+fn config_try_read<C>(rl: Option<&mut RaylibHandle>) -> anyhow::Result<Option<(PathBuf, String)>>
 where
     C: FoximgConfig,
 {
-    let file = fs::read_to_string(self::get_config_path(C::FILE, C::LOCAL, Some(rl))?)?;
+    let path = self::get_config_path(C::FILE, C::LOCAL, rl)?;
+    match fs::read_to_string(&path) {
+        Ok(file) => Ok(Some((path, file))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The key config files keep the version of their format in.
+const VERSION_KEY: &str = "version";
+
+/// Returns `file` with the line of its version key replaced by spaces, so that everything else in
+/// it stays at the same position.
+fn config_blank_version(file: &str) -> String {
+    let mut blanked = String::with_capacity(file.len());
+    let mut top_level = true;
+    for line in file.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        top_level &= !trimmed.starts_with('[');
+
+        let is_version = trimmed
+            .strip_prefix(VERSION_KEY)
+            .is_some_and(|rest| rest.trim_start().starts_with('='));
+        if top_level && is_version {
+            let content = line.trim_end_matches(['\r', '\n']);
+            blanked.extend(std::iter::repeat_n(' ', content.len()));
+            blanked.push_str(&line[content.len()..]);
+        } else {
+            blanked.push_str(line);
+        }
+    }
+    blanked
+}
+
+/// Takes the version out of `table`, and migrates `table` to the current version of `C` if it's
+/// older. Returns the version it was in. Files from before configs were versioned are version 0.
+fn config_migrate<C>(table: &mut toml::Table) -> anyhow::Result<u32>
+where
+    C: FoximgConfig,
+{
+    let version = match table.remove(VERSION_KEY) {
+        None => 0,
+        Some(toml::Value::Integer(version)) => u32::try_from(version)
+            .map_err(|_| anyhow::anyhow!("\"{VERSION_KEY}\" can't be {version}"))?,
+        Some(_) => anyhow::bail!("\"{VERSION_KEY}\" must be a whole number"),
+    };
+
+    if version < C::VERSION {
+        C::migrate(table, version)
+            .map_err(|e| anyhow::anyhow!("Couldn't migrate from version {version}: {e}"))?;
+    }
+    Ok(version)
+}
 
-    let settings: C = toml::from_str(&file)?;
-    Ok(settings)
+/// Deserializes `file` as `C`, migrating it first if it's in an older version of the format.
+/// Returns the config along with the version `file` was in.
+fn config_from_str<C>(file: &str) -> Result<(C, u32), toml::de::Error>
+where
+    C: FoximgConfig,
+{
+    let mut table: toml::Table = file.parse()?;
+    let version = self::config_migrate::<C>(&mut table)
+        .map_err(<toml::de::Error as serde::de::Error>::custom)?;
+    let config = if version >= C::VERSION {
+        // Deserializing the file rather than the table keeps where errors are in the file.
+        toml::from_str(&self::config_blank_version(file))?
+    } else {
+        toml::Value::Table(table).try_into()?
+    };
+    Ok((config, version))
+}
+
+/// Checks the config file of `C` the same way `FoximgConfig::new` reads it, without changing
+/// anything. Returns the path of the file along with the problems found in it, or `None` in place
+/// of the problems if the file doesn't exist yet.
+pub fn config_check<C>() -> anyhow::Result<(PathBuf, Option<Vec<String>>)>
+where
+    C: FoximgConfig,
+{
+    let path = self::get_config_path(C::FILE, C::LOCAL, None)?;
+    let Some((path, file)) = self::config_try_read::<C>(None)? else {
+        return Ok((path, None));
+    };
+
+    let mut problems = Vec::new();
+    match self::config_from_str::<C>(&file) {
+        Ok((config, version)) => {
+            if version < C::VERSION {
+                problems.push(format!(
+                    "In version {version} of the format. It will be migrated to version {} the \
+                     next time foximg starts",
+                    C::VERSION
+                ));
+            } else if version > C::VERSION {
+                problems.push(format!(
+                    "In version {version} of the format, which is newer than this foximg knows \
+                     about (version {}). Settings it doesn't know about are ignored",
+                    C::VERSION
+                ));
+            }
+            problems.extend(config.problems());
+        }
+        Err(e) => {
+            let message = e.to_string();
+            problems.push(message.trim_end().to_string());
+        }
+    }
+    Ok((path, Some(problems)))
 }
 
 fn config_try_to_file<C>(config: &C) -> anyhow::Result<()>
@@ -100,7 +211,14 @@ where
     C: FoximgConfig,
 {
     let settings = toml::to_string(config)?;
-    let mut file = File::create(self::get_config_path(C::FILE, C::LOCAL, None)?)?;
+    let path = self::get_config_path(C::FILE, C::LOCAL, None)?;
+
+    // The config is written next to the old one and then moved over it, so that the old one is
+    // never left half written if foximg gets closed in the middle of saving.
+    let mut tmp_path = path.clone().into_os_string();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut file = File::create(&tmp_path)?;
 
     write!(&mut file, "# This file was auto-generated by foximg. ")?;
     if C::LOCAL {
@@ -108,26 +226,42 @@ where
     } else {
         writeln!(
             &mut file,
-            "As of {} modifying the config files is unstable.",
-            env!("CARGO_PKG_VERSION")
-        )?;
-        writeln!(
-            &mut file,
-            "# Future versions of foximg will add functionality to modify the config files"
+            "Most of it can also be changed from the settings"
         )?;
         writeln!(
             &mut file,
-            "# through the app. Be careful messing with this file as an incorrect config"
-        )?;
-        writeln!(
-            &mut file,
-            "# will delete itself and create a default one again!\n"
+            "# in foximg. If this file has an error, it's backed up and left as it is, and"
         )?;
+        writeln!(&mut file, "# only the settings with errors are reset.\n")?;
     }
+    writeln!(&mut file, "{VERSION_KEY} = {}\n", C::VERSION)?;
     write!(&mut file, "{settings}")?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, &path)?;
+    WRITTEN.with_borrow_mut(|written| written.push((C::FILE, watch::modified(&path))));
     Ok(())
 }
 
+thread_local! {
+    /// Notices about config files that couldn't be deserialized, waiting to be shown in the app.
+    static NOTICES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    /// Config files foximg wrote itself, along with when they were modified after being written.
+    static WRITTEN: RefCell<Vec<(&'static str, Option<SystemTime>)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Takes the notices about config files that couldn't be deserialized since this was last called.
+fn take_notices() -> Vec<String> {
+    NOTICES.take()
+}
+
+/// Takes the config files foximg wrote itself since this was last called.
+fn take_written() -> Vec<(&'static str, Option<SystemTime>)> {
+    WRITTEN.take()
+}
+
 pub trait FoximgConfig
 where
     Self: Default + Serialize + for<'de> Deserialize<'de>,
@@ -140,29 +274,72 @@ where
     /// Represents whether the config file reflects the local state of foximg.
     const LOCAL: bool;
 
+    /// The version of the format of the config file, which gets written into it. Bump this whenever
+    /// the format changes in a way that needs `migrate` to read older files.
+    const VERSION: u32 = 1;
+
     /// Updates the config. This gets called in `new`.
     fn update(&self, rl: &mut RaylibHandle);
 
+    /// Migrates `table`, which is in version `from` of the format, to the current `VERSION`. This
+    /// gets called before `table` is deserialized, so fields can be renamed or converted here. Files
+    /// from before configs were versioned are version 0. Migrations are meant to be done one version
+    /// at a time, as in `if from < 2 { ... }`, so that files from any older version get migrated.
+    fn migrate(_table: &mut toml::Table, _from: u32) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Returns the problems with the config that don't stop it from being deserialized, such as
+    /// settings that contradict each other. These are printed by `--config-check`.
+    fn problems(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Loads the config from its file, creating the file with the defaults if it doesn't exist. If
+    /// the file can't be deserialized, it's backed up and whatever can be read from it is used.
+    /// What went wrong is then shown in the app through `take_notices`.
     fn new(rl: &mut RaylibHandle) -> Self {
         rl.trace_log(
             TraceLogLevel::LOG_INFO,
             &format!("FOXIMG: Loading \"{}\"", Self::FILE),
         );
 
-        let settings = match self::config_try_new(rl) {
-            Ok(settings) => settings,
-            Err(e) => {
-                rl.trace_log(
-                    TraceLogLevel::LOG_WARNING,
-                    &format!("FOXIMG: Couldn't deserialize \"{}\":", Self::FILE),
-                );
+        let settings = match self::config_try_read::<Self>(Some(&mut *rl)) {
+            Ok(Some((path, file))) => match self::config_from_str::<Self>(&file) {
+                Ok((settings, version)) if version < Self::VERSION => {
+                    rl.trace_log(
+                        TraceLogLevel::LOG_INFO,
+                        &format!(
+                            "FOXIMG: Migrating \"{}\" from version {version} to {}",
+                            Self::FILE,
+                            Self::VERSION
+                        ),
+                    );
 
-                // TOML parsing errors tend to be multiple lines long. We split them into mutliple
-                // messages so that they can fit and be neatly formatted.
-                for el in e.to_string().lines() {
-                    rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {el}"));
+                    settings.to_file(rl);
+                    settings
                 }
-
+                Ok((settings, version)) => {
+                    if version > Self::VERSION {
+                        rl.trace_log(
+                            TraceLogLevel::LOG_WARNING,
+                            &format!(
+                                "FOXIMG: \"{}\" is in version {version}, which is newer than this foximg knows about. Settings it doesn't know about are ignored",
+                                Self::FILE
+                            ),
+                        );
+                    }
+                    settings
+                }
+                Err(e) => {
+                    // An invalid config is never overwritten, so that a typo doesn't cost the user
+                    // the rest of it.
+                    let (settings, notice) = recover::recover(rl, &path, &file, e);
+                    NOTICES.with_borrow_mut(|notices| notices.push(notice));
+                    settings
+                }
+            },
+            Ok(None) => {
                 let settings = Self::default();
                 rl.trace_log(
                     TraceLogLevel::LOG_INFO,
@@ -172,12 +349,63 @@ where
                 settings.to_file(rl);
                 settings
             }
+            Err(e) => {
+                rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    &format!("FOXIMG: Couldn't read \"{}\". Using defaults:", Self::FILE),
+                );
+                rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                Self::default()
+            }
         };
 
         settings.update(rl);
         settings
     }
 
+    /// Loads the config from its file again after it changed, without updating it. Returns `None`
+    /// if the file is gone or can't be deserialized, in which case the config in use should be kept.
+    fn reload(rl: &mut RaylibHandle) -> Option<Self> {
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("FOXIMG: Reloading \"{}\"", Self::FILE),
+        );
+
+        match self::config_try_read::<Self>(Some(&mut *rl)) {
+            Ok(Some((_, file))) => match self::config_from_str::<Self>(&file) {
+                Ok((settings, _)) => Some(settings),
+                Err(e) => {
+                    rl.trace_log(
+                        TraceLogLevel::LOG_WARNING,
+                        &format!(
+                            "FOXIMG: Couldn't deserialize \"{}\". Keeping the previous one:",
+                            Self::FILE
+                        ),
+                    );
+                    for el in e.to_string().lines() {
+                        rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {el}"));
+                    }
+
+                    let notice = recover::notice::<Self>(&file, &e);
+                    NOTICES.with_borrow_mut(|notices| notices.push(notice));
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    &format!(
+                        "FOXIMG: Couldn't read \"{}\". Keeping the previous one:",
+                        Self::FILE
+                    ),
+                );
+                rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                None
+            }
+        }
+    }
+
     fn to_file(&self, rl: &RaylibHandle) -> bool {
         if let Err(e) = config_try_to_file(self) {
             rl.trace_log(
@@ -201,6 +429,7 @@ pub struct FoximgState {
 
     pub maximized: bool,
     pub fullscreen: bool,
+    pub filmstrip: bool,
 }
 
 impl Default for FoximgState {
@@ -211,6 +440,7 @@ impl Default for FoximgState {
             xy: None,
             maximized: false,
             fullscreen: false,
+            filmstrip: false,
         }
     }
 }
@@ -236,6 +466,7 @@ impl FoximgState {
 
         log_field!(rl, self.maximized);
         log_field!(rl, self.fullscreen);
+        log_field!(rl, self.filmstrip);
     }
 
     fn set_state(&self, rl: &mut RaylibHandle) {
@@ -262,27 +493,489 @@ impl FoximgConfig for FoximgState {
     }
 }
 
-// #[derive(Serialize, Deserialize)]
-// pub struct FoximgSettings {
-//     pub antialiasing: bool,
-// }
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoximgSlideshowSettings {
+    /// Seconds each image is shown for.
+    pub interval: f32,
+    /// Start over once the last image is reached instead of stopping.
+    pub repeat: bool,
+    pub shuffle: bool,
+    /// Don't advance until animated images finish playing at least once.
+    pub wait_for_animations: bool,
+    /// Don't advance while the current image is zoomed in.
+    pub pause_on_zoom: bool,
+    pub crossfade: bool,
+    /// Seconds the crossfade between two images lasts.
+    pub crossfade_duration: f32,
+}
+
+impl Default for FoximgSlideshowSettings {
+    fn default() -> Self {
+        Self {
+            interval: 5.,
+            repeat: true,
+            shuffle: false,
+            wait_for_animations: true,
+            pause_on_zoom: true,
+            crossfade: false,
+            crossfade_duration: 0.5,
+        }
+    }
+}
+
+impl FoximgSlideshowSettings {
+    fn log_settings(&self, rl: &RaylibHandle) {
+        macro_rules! log_field {
+            ($rl:ident, $self:ident.$field:ident) => {
+                $rl.trace_log(
+                    TraceLogLevel::LOG_INFO,
+                    &format!(concat!("    > ", stringify!($field), ": {}"), $self.$field),
+                )
+            };
+        }
+
+        log_field!(rl, self.interval);
+        log_field!(rl, self.repeat);
+        log_field!(rl, self.shuffle);
+        log_field!(rl, self.wait_for_animations);
+        log_field!(rl, self.pause_on_zoom);
+        log_field!(rl, self.crossfade);
+        log_field!(rl, self.crossfade_duration);
+    }
+}
+
+impl FoximgConfig for FoximgSlideshowSettings {
+    const FILE: &str = "foximg_slideshow.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        self.log_settings(rl);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoximgTrashSettings {
+    /// Ask before moving an image to the trash.
+    pub confirm: bool,
+}
+
+impl Default for FoximgTrashSettings {
+    fn default() -> Self {
+        Self { confirm: true }
+    }
+}
+
+impl FoximgConfig for FoximgTrashSettings {
+    const FILE: &str = "foximg_trash.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > confirm: {}", self.confirm),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FoximgCompareLayout {
+    SideBySide,
+    TopBottom,
+    /// Both images are drawn over each other, with a slider splitting them.
+    Split,
+    /// A heatmap of the difference between both images is drawn instead of either image.
+    Difference,
+}
+
+/// How two images of different sizes are lined up when comparing them.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FoximgCompareFit {
+    /// Both images are scaled to fit the window on their own.
+    Scale,
+    /// Both images are drawn at the same scale with their top left corners lined up, so that
+    /// their pixels line up.
+    TopLeft,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoximgCompareSettings {
+    pub layout: FoximgCompareLayout,
+    pub fit: FoximgCompareFit,
+    /// What the differences in the difference heatmap are multiplied by to make them visible.
+    pub diff_amplification: f32,
+    /// Pixels that differ by this much or less are masked out of the difference heatmap.
+    pub diff_threshold: u8,
+}
+
+impl Default for FoximgCompareSettings {
+    fn default() -> Self {
+        Self {
+            layout: FoximgCompareLayout::SideBySide,
+            fit: FoximgCompareFit::Scale,
+            diff_amplification: 8.,
+            diff_threshold: 0,
+        }
+    }
+}
+
+impl FoximgConfig for FoximgCompareSettings {
+    const FILE: &str = "foximg_compare.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > layout: {:?}", self.layout),
+        );
+        rl.trace_log(TraceLogLevel::LOG_INFO, &format!("    > fit: {:?}", self.fit));
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > diff_amplification: {}", self.diff_amplification),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > diff_threshold: {}", self.diff_threshold),
+        );
+    }
+}
+
+/// The image overlaid over the current one by the onion skin.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FoximgOnionSource {
+    /// The previous image in the gallery, or the previous frame of a paused animation.
+    Previous,
+    /// The next image in the gallery, or the next frame of a paused animation.
+    Next,
+    /// The image pinned for comparing.
+    Pinned,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoximgOnionSettings {
+    pub source: FoximgOnionSource,
+    /// How opaque the overlaid image is, from 0 to 1.
+    pub opacity: f32,
+    /// Tint the current image and the overlaid image so that they can be told apart.
+    pub tint: bool,
+    pub current_tint: FoximgColor,
+    pub overlay_tint: FoximgColor,
+}
+
+impl Default for FoximgOnionSettings {
+    fn default() -> Self {
+        Self {
+            source: FoximgOnionSource::Previous,
+            opacity: 0.5,
+            tint: false,
+            current_tint: FoximgColor(Color::new(255, 96, 96, 255)),
+            overlay_tint: FoximgColor(Color::new(96, 208, 255, 255)),
+        }
+    }
+}
+
+impl FoximgConfig for FoximgOnionSettings {
+    const FILE: &str = "foximg_onion.toml";
+    const LOCAL: bool = false;
 
-// impl Default for FoximgSettings {
-//     fn default() -> Self {
-//         Self {
-//             antialiasing: false,
-//         }
-//     }
-// }
+    fn update(&self, rl: &mut RaylibHandle) {
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > source: {:?}", self.source),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > opacity: {}", self.opacity),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > tint: {}", self.tint),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > current_tint: {}", self.current_tint),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > overlay_tint: {}", self.overlay_tint),
+        );
+    }
+}
 
-// impl FoximgConfig for FoximgSettings {
-//     const FILE: &str = "foximg_settings.toml";
-//     const LOCAL: bool = false;
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FoximgHistogramScale {
+    Linear,
+    /// Scales the counts logarithmically, so that small counts still show up next to large ones.
+    Log,
+}
 
-//     fn update(&self, _: &mut RaylibHandle) {}
-// }
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoximgHistogramSettings {
+    pub scale: FoximgHistogramScale,
+    /// Draw the luma curve along with the red, green and blue ones.
+    pub luma: bool,
+}
+
+impl Default for FoximgHistogramSettings {
+    fn default() -> Self {
+        Self {
+            scale: FoximgHistogramScale::Linear,
+            luma: true,
+        }
+    }
+}
+
+impl FoximgConfig for FoximgHistogramSettings {
+    const FILE: &str = "foximg_histogram.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > scale: {:?}", self.scale),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > luma: {}", self.luma),
+        );
+    }
+}
 
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoximgOverlaySettings {
+    /// Channels at or below this value, from 0 to 255, count as clipped shadows.
+    pub clipping_low: u8,
+    /// Channels at or above this value, from 0 to 255, count as clipped highlights.
+    pub clipping_high: u8,
+    pub shadow_color: FoximgColor,
+    pub highlight_color: FoximgColor,
+    /// How much the luma has to change across an edge for focus peaking to highlight it, from 0
+    /// to 4. Lower values highlight softer edges.
+    pub peaking_threshold: f32,
+    pub peaking_color: FoximgColor,
+}
+
+impl Default for FoximgOverlaySettings {
+    fn default() -> Self {
+        Self {
+            clipping_low: 0,
+            clipping_high: 255,
+            shadow_color: FoximgColor(Color::new(0, 96, 255, 255)),
+            highlight_color: FoximgColor(Color::new(255, 0, 0, 255)),
+            peaking_threshold: 0.5,
+            peaking_color: FoximgColor(Color::new(0, 255, 96, 255)),
+        }
+    }
+}
+
+impl FoximgConfig for FoximgOverlaySettings {
+    const FILE: &str = "foximg_overlays.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > clipping_low: {}", self.clipping_low),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > clipping_high: {}", self.clipping_high),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > shadow_color: {}", self.shadow_color),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > highlight_color: {}", self.highlight_color),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > peaking_threshold: {}", self.peaking_threshold),
+        );
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > peaking_color: {}", self.peaking_color),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FoximgCullAction {
+    Move,
+    Copy,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FoximgCullDestination {
+    pub name: String,
+    /// The folder images are sent to. Relative paths are relative to the folder of the image.
+    pub path: PathBuf,
+    pub action: FoximgCullAction,
+}
+
+impl FoximgCullDestination {
+    fn new(name: &str, action: FoximgCullAction) -> Self {
+        Self {
+            name: name.to_string(),
+            path: name.into(),
+            action,
+        }
+    }
+}
+
+/// The folders images can be sorted into while culling. The first destination is sent to with
+/// `Cull1`, the second with `Cull2` and so on up to `Cull9`.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoximgCullSettings {
+    pub destinations: Vec<FoximgCullDestination>,
+}
+
+impl Default for FoximgCullSettings {
+    fn default() -> Self {
+        Self {
+            destinations: vec![
+                FoximgCullDestination::new("keep", FoximgCullAction::Move),
+                FoximgCullDestination::new("reject", FoximgCullAction::Move),
+                FoximgCullDestination::new("edit", FoximgCullAction::Copy),
+            ],
+        }
+    }
+}
+
+impl FoximgConfig for FoximgCullSettings {
+    const FILE: &str = "foximg_culling.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        for (i, destination) in self.destinations.iter().enumerate() {
+            rl.trace_log(
+                TraceLogLevel::LOG_INFO,
+                &format!(
+                    "    > Alt+{}: {:?} {} ({:?})",
+                    i + 1,
+                    destination.action,
+                    destination.name,
+                    destination.path
+                ),
+            );
+        }
+    }
+}
+
+/// How images are sampled when they're drawn bigger or smaller than they are.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FoximgFiltering {
+    /// Draws every pixel as a sharp square, which suits pixel art.
+    Nearest,
+    /// Blends neighbouring pixels, which suits photos.
+    Bilinear,
+}
+
+impl FoximgFiltering {
+    pub fn texture_filter(self) -> TextureFilter {
+        match self {
+            Self::Nearest => TextureFilter::TEXTURE_FILTER_POINT,
+            Self::Bilinear => TextureFilter::TEXTURE_FILTER_BILINEAR,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FoximgSettings {
+    /// Restore the window and the filmstrip as they were the last time foximg was closed.
+    pub remember_window: bool,
+    pub always_on_top: bool,
+    /// How much each notch of the scroll wheel zooms in or out.
+    pub zoom_step: f32,
+    /// What the speed of panning with the keyboard is multiplied by.
+    pub pan_speed: f32,
+    /// How many of the most recently viewed images are kept loaded, from 1 to 64.
+    pub cache_size: usize,
+    pub filtering: FoximgFiltering,
+    /// The name of the theme from the theme library in use. The style file is used if it's unset.
+    pub theme: Option<String>,
+}
+
+impl Default for FoximgSettings {
+    fn default() -> Self {
+        Self {
+            remember_window: true,
+            always_on_top: false,
+            zoom_step: 0.4,
+            pan_speed: 1.,
+            cache_size: images::MAX_CACHE_SIZE,
+            filtering: FoximgFiltering::Nearest,
+            theme: None,
+        }
+    }
+}
+
+impl FoximgSettings {
+    fn log_settings(&self, rl: &RaylibHandle) {
+        macro_rules! log_field {
+            ($rl:ident, $self:ident.$field:ident) => {
+                $rl.trace_log(
+                    TraceLogLevel::LOG_INFO,
+                    &format!(
+                        concat!("    > ", stringify!($field), ": {:?}"),
+                        $self.$field
+                    ),
+                )
+            };
+        }
+
+        log_field!(rl, self.remember_window);
+        log_field!(rl, self.always_on_top);
+        log_field!(rl, self.zoom_step);
+        log_field!(rl, self.pan_speed);
+        log_field!(rl, self.cache_size);
+        log_field!(rl, self.filtering);
+        log_field!(rl, self.theme);
+    }
+}
+
+impl FoximgConfig for FoximgSettings {
+    const FILE: &str = "foximg_settings.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        self.log_settings(rl);
+        rl.set_window_state(rl.get_window_state().set_window_topmost(self.always_on_top));
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(1..=images::MAX_CACHE_SIZE).contains(&self.cache_size) {
+            problems.push(format!(
+                "\"cache_size\" must be from 1 to {}, so it's clamped",
+                images::MAX_CACHE_SIZE
+            ));
+        }
+        if self.zoom_step <= 0. {
+            problems.push("\"zoom_step\" must be above 0, or zooming goes backwards".into());
+        }
+        if self.pan_speed <= 0. {
+            problems.push("\"pan_speed\" must be above 0, or panning goes backwards".into());
+        }
+        problems
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 // I intentionally choose not to annotate this with #[serde(default)] because I actually do want the
 // effect of the name and author fields being None when the style is passed by command line arguments.
 pub struct FoximgStyleOptionals {
@@ -319,7 +1012,7 @@ impl Default for FoximgStyleOptionals {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(default)]
 pub struct FoximgStyle {
     #[serde(flatten)]
@@ -611,7 +1304,7 @@ impl Foximg {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct FoximgIcon {
     pub path: Option<PathBuf>,
 }
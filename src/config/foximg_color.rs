@@ -1,4 +1,7 @@
-use std::{fmt::Display, ops::Deref};
+use std::{
+    fmt::Display,
+    ops::{Deref, DerefMut},
+};
 
 use raylib::prelude::*;
 use serde::{Deserialize, Serialize, de::Visitor};
@@ -96,6 +99,18 @@ impl Deref for FoximgColor {
     }
 }
 
+impl DerefMut for FoximgColor {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl PartialEq for FoximgColor {
+    fn eq(&self, other: &Self) -> bool {
+        self.color_to_int() == other.color_to_int()
+    }
+}
+
 impl From<FoximgColor> for ffi::Color {
     fn from(value: FoximgColor) -> Self {
         value.0.into()
@@ -0,0 +1,207 @@
+//! Defines what happens to config files that can't be deserialized. They're backed up and left as
+//! they are, and whatever can still be read from them is used on top of the defaults.
+
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use raylib::prelude::*;
+
+use super::FoximgConfig;
+
+/// Returns the line and column `span` starts at in `file`, both starting from 1.
+fn position(file: &str, span: Option<Range<usize>>) -> Option<(usize, usize)> {
+    let start = span?.start.min(file.len());
+    let before = file.get(..start)?;
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    Some((line, column))
+}
+
+/// Copies the config at `path` next to it, with ".bak-" and the current time after its name. Nothing
+/// is copied if an identical backup already exists, so that the same broken config doesn't get
+/// backed up every time foximg starts. Returns the path of the backup.
+fn backup(path: &Path, file: &str) -> anyhow::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Config path has no file name"))?
+        .to_string_lossy();
+    let prefix = format!("{name}.bak-");
+    let folder = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Config path has no parent folder"))?;
+
+    for entry in fs::read_dir(folder)?.filter_map(Result::ok) {
+        let is_backup = entry.file_name().to_string_lossy().starts_with(&prefix);
+        if is_backup && fs::read_to_string(entry.path()).is_ok_and(|backup| backup == file) {
+            return Ok(entry.path());
+        }
+    }
+
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let backup = folder.join(format!("{prefix}{timestamp}"));
+    fs::write(&backup, file)?;
+    Ok(backup)
+}
+
+/// Parses `file` as a TOML table, leaving out every line that makes it invalid TOML. Returns the
+/// table along with the lines left out, starting from 1.
+fn parse_lines(file: &str) -> (toml::Table, Vec<usize>) {
+    let mut lines: Vec<&str> = file.lines().collect();
+    let mut ignored = Vec::new();
+
+    loop {
+        let joined = lines.join("\n");
+        let e = match joined.parse::<toml::Table>() {
+            Ok(table) => return (table, ignored),
+            Err(e) => e,
+        };
+
+        match self::position(&joined, e.span()) {
+            Some((line, _)) if line <= lines.len() && !lines[line - 1].is_empty() => {
+                lines[line - 1] = "";
+                ignored.push(line);
+            }
+            // The error can't be narrowed down to a line, so nothing can be salvaged.
+            _ => return (toml::Table::new(), (1..=lines.len()).collect()),
+        }
+    }
+}
+
+/// Deserializes `table` one top-level key at a time on top of the defaults of `C`, so that an
+/// invalid value only resets itself. Returns the config along with the keys that were left out.
+fn salvage<C>(table: toml::Table) -> anyhow::Result<(C, Vec<String>)>
+where
+    C: FoximgConfig,
+{
+    let toml::Value::Table(mut salvaged) = toml::Value::try_from(C::default())? else {
+        anyhow::bail!("The defaults aren't a table");
+    };
+    let mut invalid = Vec::new();
+
+    for (key, value) in table {
+        let previous = salvaged.insert(key.clone(), value);
+        if toml::Value::Table(salvaged.clone())
+            .try_into::<C>()
+            .is_err()
+        {
+            match previous {
+                Some(previous) => salvaged.insert(key.clone(), previous),
+                None => salvaged.remove(&key),
+            };
+            invalid.push(key);
+        }
+    }
+
+    Ok((toml::Value::Table(salvaged).try_into()?, invalid))
+}
+
+/// Recovers from the config at `path` failing to deserialize with `e`. The config gets backed up
+/// and isn't written to, and as much of it as possible is used. Returns the recovered config along
+/// with a notice about what went wrong, meant to be shown in the app.
+pub(super) fn recover<C>(
+    rl: &mut RaylibHandle,
+    path: &Path,
+    file: &str,
+    e: toml::de::Error,
+) -> (C, String)
+where
+    C: FoximgConfig,
+{
+    let position = self::position(file, e.span());
+    rl.trace_log(
+        TraceLogLevel::LOG_WARNING,
+        &match position {
+            Some((line, column)) => format!(
+                "FOXIMG: Couldn't deserialize \"{}\" at line {line}, column {column}:",
+                C::FILE
+            ),
+            None => format!("FOXIMG: Couldn't deserialize \"{}\":", C::FILE),
+        },
+    );
+
+    // TOML parsing errors tend to be multiple lines long. We split them into mutliple messages so
+    // that they can fit and be neatly formatted.
+    for el in e.to_string().lines() {
+        rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {el}"));
+    }
+
+    match self::backup(path, file) {
+        Ok(backup) => rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("FOXIMG: Backed up \"{}\" to {backup:?}", C::FILE),
+        ),
+        Err(e) => {
+            rl.trace_log(
+                TraceLogLevel::LOG_WARNING,
+                &format!("FOXIMG: Couldn't back up \"{}\":", C::FILE),
+            );
+            rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+        }
+    }
+
+    let (mut table, ignored) = self::parse_lines(file);
+    if let Err(e) = super::config_migrate::<C>(&mut table) {
+        rl.trace_log(
+            TraceLogLevel::LOG_WARNING,
+            &format!("FOXIMG: Couldn't migrate \"{}\":", C::FILE),
+        );
+        rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+    }
+
+    let config = match self::salvage::<C>(table) {
+        Ok((config, invalid)) => {
+            rl.trace_log(
+                TraceLogLevel::LOG_INFO,
+                &format!("FOXIMG: Using what could be read from \"{}\"", C::FILE),
+            );
+            for line in ignored {
+                rl.trace_log(
+                    TraceLogLevel::LOG_INFO,
+                    &format!("    > Ignored line {line}"),
+                );
+            }
+            for key in invalid {
+                rl.trace_log(
+                    TraceLogLevel::LOG_INFO,
+                    &format!("    > Reset \"{key}\" to its default"),
+                );
+            }
+            config
+        }
+        Err(e) => {
+            rl.trace_log(
+                TraceLogLevel::LOG_WARNING,
+                &format!("FOXIMG: Couldn't salvage \"{}\". Using defaults:", C::FILE),
+            );
+            rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+            C::default()
+        }
+    };
+
+    (config, self::notice::<C>(file, &e))
+}
+
+/// Returns a notice about `file`, the contents of the config file of `C`, failing to deserialize
+/// with `e`, meant to be shown in the app.
+pub(super) fn notice<C>(file: &str, e: &toml::de::Error) -> String
+where
+    C: FoximgConfig,
+{
+    match self::position(file, e.span()) {
+        Some((line, column)) => format!(
+            "\"{}\" has an error at line {line}, column {column}: {}",
+            C::FILE,
+            e.message()
+        ),
+        None => format!("\"{}\" has an error: {}", C::FILE, e.message()),
+    }
+}
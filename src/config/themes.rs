@@ -0,0 +1,193 @@
+//! Defines the theme library. It's made up of the themes built into foximg, followed by the style
+//! files found in the themes folder next to the config files. The theme picked from it is
+//! remembered by its name in the settings, which leaves the style file as it is.
+
+use std::{borrow::Cow, fs, io, path::Path};
+
+use raylib::prelude::*;
+
+use crate::Foximg;
+
+use super::{FoximgConfig, FoximgSettings, FoximgStyle};
+
+/// The folder next to the config files that user themes are read from.
+pub(super) const THEMES_FOLDER: &str = "themes";
+
+/// The themes built into foximg besides the default one.
+static BUILT_IN_THEMES: [&str; 4] = [
+    include_str!("../resources/themes/light.toml"),
+    include_str!("../resources/themes/midnight.toml"),
+    include_str!("../resources/themes/high_contrast.toml"),
+    include_str!("../resources/themes/oled_black.toml"),
+];
+
+/// Reads the theme at `path`. A theme without a name is named after its file.
+fn load_theme(path: &Path) -> anyhow::Result<FoximgStyle> {
+    let mut theme: FoximgStyle = toml::from_str(&fs::read_to_string(path)?)?;
+    if theme.optionals.name.is_none() {
+        theme.optionals.name = path
+            .file_stem()
+            .map(|stem| Cow::Owned(stem.to_string_lossy().into_owned()));
+    }
+    Ok(theme)
+}
+
+impl FoximgStyle {
+    /// Returns the themes built into foximg, starting with the default one.
+    pub fn themes() -> Vec<Self> {
+        let mut themes = vec![Self::default()];
+        themes.extend(
+            BUILT_IN_THEMES
+                .iter()
+                .map(|theme| toml::from_str(theme).expect("built-in themes should parse")),
+        );
+        themes
+    }
+
+    /// Returns the built-in themes followed by the ones in the themes folder, sorted by their file
+    /// names. The folder is created if it doesn't exist yet. Themes that can't be read are left out.
+    pub fn library(rl: &mut RaylibHandle) -> Vec<Self> {
+        let mut themes = Self::themes();
+
+        let folder = match super::get_config_path(THEMES_FOLDER, false, Some(&mut *rl)) {
+            Ok(folder) => folder,
+            Err(e) => {
+                rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    "FOXIMG: Couldn't find the themes folder:",
+                );
+                rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                return themes;
+            }
+        };
+
+        let entries = match fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(e) => {
+                if e.kind() == io::ErrorKind::NotFound {
+                    if let Err(e) = fs::create_dir(&folder) {
+                        rl.trace_log(
+                            TraceLogLevel::LOG_WARNING,
+                            &format!("FOXIMG: Couldn't create the themes folder {folder:?}:"),
+                        );
+                        rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                    }
+                } else {
+                    rl.trace_log(
+                        TraceLogLevel::LOG_WARNING,
+                        &format!("FOXIMG: Couldn't read the themes folder {folder:?}:"),
+                    );
+                    rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                }
+                return themes;
+            }
+        };
+
+        let mut paths: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match self::load_theme(&path) {
+                Ok(theme) => themes.push(theme),
+                Err(e) => {
+                    rl.trace_log(
+                        TraceLogLevel::LOG_WARNING,
+                        &format!("FOXIMG: Couldn't load theme {path:?}:"),
+                    );
+                    // TOML parsing errors tend to be multiple lines long.
+                    for el in e.to_string().lines() {
+                        rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {el}"));
+                    }
+                }
+            }
+        }
+
+        themes
+    }
+
+    /// Returns the theme in `themes` that's named `name`.
+    fn find_theme<'a>(themes: &'a [Self], name: &str) -> Option<&'a Self> {
+        themes
+            .iter()
+            .find(|theme| theme.optionals.name.as_deref() == Some(name))
+    }
+
+    /// Returns the name of the theme in `themes` that `self` is, if it's one of them.
+    pub fn theme_name(&self, themes: &[Self]) -> Option<String> {
+        themes
+            .iter()
+            .find(|&theme| theme == self)
+            .and_then(|theme| theme.optionals.name.as_deref())
+            .map(str::to_owned)
+    }
+
+    /// Returns the theme selected in `settings`, or reads the style file if there isn't one or it's
+    /// not in `themes`.
+    pub fn selected(rl: &mut RaylibHandle, themes: &[Self], settings: &FoximgSettings) -> Self {
+        let Some(ref name) = settings.theme else {
+            return Self::new(rl);
+        };
+
+        match Self::find_theme(themes, name) {
+            Some(theme) => {
+                rl.trace_log(
+                    TraceLogLevel::LOG_INFO,
+                    &format!("FOXIMG: Loaded theme \"{name}\""),
+                );
+                theme.update(rl);
+                theme.clone()
+            }
+            None => {
+                rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    &format!("FOXIMG: Couldn't find theme \"{name}\". Using the style file"),
+                );
+                Self::new(rl)
+            }
+        }
+    }
+}
+
+impl Foximg {
+    /// Returns true if the style file is in use, which it is unless a theme from the library is
+    /// selected.
+    pub fn uses_style_file(&self) -> bool {
+        self.settings
+            .theme
+            .as_deref()
+            .and_then(|name| FoximgStyle::find_theme(&self.themes, name))
+            .is_none()
+    }
+
+    /// Applies the theme selected in the settings again, or the style file if there isn't one.
+    pub(super) fn apply_selected_theme(&mut self) {
+        let theme = self
+            .settings
+            .theme
+            .as_deref()
+            .and_then(|name| FoximgStyle::find_theme(&self.themes, name))
+            .cloned();
+        if let Some(theme) = theme.or_else(|| FoximgStyle::reload(&mut self.rl)) {
+            self.apply_style(theme);
+        }
+    }
+
+    /// Switches to the `i`th theme of the library and remembers it in the settings, so that it's
+    /// still used the next time foximg starts.
+    pub fn switch_theme(&mut self, i: usize) {
+        let theme = self.themes[i].clone();
+        let name = theme.optionals.name.clone().unwrap_or_default();
+        self.apply_style(theme);
+        self.settings.theme = Some(name.to_string());
+
+        if self.settings.to_file(&self.rl) {
+            self.toast(format!("Switched to the \"{name}\" theme"));
+        } else {
+            self.toast("Couldn't save theme");
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Defines hot-reloading of config files. They're watched by checking when they were last
+//! modified, and applied again as soon as they change, so that themes can be tweaked without
+//! restarting foximg. The themes folder is watched the same way, and the theme library is read
+//! again whenever a theme in it changes.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{Foximg, images, keybinds::FoximgKeybinds};
+
+use super::{
+    FoximgCompareSettings, FoximgConfig, FoximgCullSettings, FoximgHistogramSettings,
+    FoximgOnionSettings, FoximgOverlaySettings, FoximgSettings, FoximgSlideshowSettings,
+    FoximgStyle, FoximgTrashSettings, themes::THEMES_FOLDER,
+};
+
+/// A config file being watched, along with when it was last modified.
+struct FoximgWatchedConfig {
+    file: &'static str,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+/// Watches config files for changes.
+pub struct FoximgConfigWatcher {
+    configs: Vec<FoximgWatchedConfig>,
+    last_poll: f64,
+}
+
+impl FoximgConfigWatcher {
+    /// Seconds between checking whether the config files were modified.
+    const POLL_INTERVAL: f64 = 1.;
+
+    /// Watches every config file that can be changed while foximg is running. The style and the
+    /// themes folder are only watched if `style` is set, so that a style passed by command line
+    /// arguments stays.
+    pub fn new(style: bool) -> Self {
+        let mut watcher = Self {
+            configs: Vec::new(),
+            last_poll: 0.,
+        };
+
+        if style {
+            watcher.watch::<FoximgStyle>();
+            watcher.watch_path(THEMES_FOLDER, false);
+        }
+        watcher.watch::<FoximgSettings>();
+        watcher.watch::<FoximgKeybinds>();
+        watcher.watch::<FoximgSlideshowSettings>();
+        watcher.watch::<FoximgTrashSettings>();
+        watcher.watch::<FoximgCompareSettings>();
+        watcher.watch::<FoximgOnionSettings>();
+        watcher.watch::<FoximgHistogramSettings>();
+        watcher.watch::<FoximgOverlaySettings>();
+        watcher.watch::<FoximgCullSettings>();
+        watcher
+    }
+
+    fn watch<C>(&mut self)
+    where
+        C: FoximgConfig,
+    {
+        self.watch_path(C::FILE, C::LOCAL);
+    }
+
+    fn watch_path(&mut self, file: &'static str, local: bool) {
+        if let Ok(path) = super::get_config_path(file, local, None) {
+            self.configs.push(FoximgWatchedConfig {
+                file,
+                modified: self::modified(&path),
+                path,
+            });
+        }
+    }
+
+    /// Returns the names of the config files modified since they were last checked. They're only
+    /// checked every `POLL_INTERVAL` seconds, with `time` being the current time in seconds.
+    fn poll(&mut self, time: f64) -> Vec<&'static str> {
+        if time - self.last_poll < Self::POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = time;
+
+        // Writes of foximg's own aren't changes to reload.
+        for (file, written) in super::take_written() {
+            if let Some(config) = self.configs.iter_mut().find(|config| config.file == file) {
+                config.modified = written;
+            }
+        }
+
+        let mut modified = Vec::new();
+        for config in &mut self.configs {
+            let now = self::modified(&config.path);
+            // A config that got deleted is kept as it is until it's created again.
+            if now.is_some() && now != config.modified {
+                modified.push(config.file);
+            }
+            config.modified = now;
+        }
+        modified
+    }
+}
+
+/// Returns when `path` was last modified. A folder counts as modified whenever any of the files in
+/// it are.
+pub(super) fn modified(path: &Path) -> Option<SystemTime> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .chain(modified)
+            .max(),
+        Err(_) => modified,
+    }
+}
+
+impl Foximg {
+    /// Applies `style` to raygui and the window icon. The raygui style is reset first, so that
+    /// colours the previous style set don't stick around when the new one leaves them unchanged.
+    pub fn apply_style(&mut self, style: FoximgStyle) {
+        self.rl.gui_load_style_default();
+        self.resources.set_gui_font(&mut self.rl);
+        style.update(&mut self.rl);
+        self.style = style;
+        images::set_window_icon(&mut self.rl, &self.style, self.icon.clone());
+    }
+
+    /// Applies `settings`, including to the images that are already loaded.
+    pub fn apply_settings(&mut self, settings: FoximgSettings) {
+        settings.update(&mut self.rl);
+        if let Some(ref mut images) = self.images {
+            images.apply_settings(&settings, &self.rl_thread);
+        }
+        self.settings = settings;
+    }
+
+    /// Reloads and applies the config files that changed since they were last checked. Config
+    /// files that fail to deserialize are left out, keeping the configs in use.
+    pub fn reload_configs(&mut self) {
+        fn reload<C>(f: &mut Foximg) -> Option<C>
+        where
+            C: FoximgConfig,
+        {
+            let config = C::reload(&mut f.rl)?;
+            config.update(&mut f.rl);
+            Some(config)
+        }
+
+        let time = self.rl.get_time();
+        for file in self.config_watcher.poll(time) {
+            match file {
+                // The style file is left alone while a theme from the library is in use.
+                FoximgStyle::FILE if self.uses_style_file() => {
+                    if let Some(style) = FoximgStyle::reload(&mut self.rl) {
+                        self.apply_style(style);
+                    }
+                }
+                FoximgSettings::FILE => {
+                    if let Some(settings) = FoximgSettings::reload(&mut self.rl) {
+                        let theme_changed = settings.theme != self.settings.theme;
+                        self.apply_settings(settings);
+                        if theme_changed {
+                            self.apply_selected_theme();
+                        }
+                    }
+                }
+                THEMES_FOLDER => {
+                    self.themes = FoximgStyle::library(&mut self.rl);
+                    if !self.uses_style_file() {
+                        self.apply_selected_theme();
+                    }
+                }
+                FoximgKeybinds::FILE => {
+                    if let Some(keybinds) = reload(self) {
+                        self.keybinds = keybinds;
+                    }
+                }
+                FoximgSlideshowSettings::FILE => {
+                    if let Some(slideshow_settings) = reload(self) {
+                        self.slideshow_settings = slideshow_settings;
+                    }
+                }
+                FoximgTrashSettings::FILE => {
+                    if let Some(trash_settings) = reload(self) {
+                        self.trash_settings = trash_settings;
+                    }
+                }
+                FoximgCompareSettings::FILE => {
+                    if let Some(compare_settings) = reload(self) {
+                        self.compare_settings = compare_settings;
+                    }
+                }
+                FoximgOnionSettings::FILE => {
+                    if let Some(onion_settings) = reload(self) {
+                        self.onion_settings = onion_settings;
+                    }
+                }
+                FoximgHistogramSettings::FILE => {
+                    if let Some(histogram_settings) = reload(self) {
+                        self.histogram_settings = histogram_settings;
+                    }
+                }
+                FoximgOverlaySettings::FILE => {
+                    if let Some(overlay_settings) = reload(self) {
+                        self.overlay_settings = overlay_settings;
+                        self.update_overlay_shaders();
+                    }
+                }
+                FoximgCullSettings::FILE => {
+                    if let Some(cull_settings) = reload(self) {
+                        self.cull_settings = cull_settings;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for notice in super::take_notices() {
+            self.notice(notice);
+        }
+    }
+}
@@ -1,28 +1,25 @@
 //! Defines the basic controls for manipulating the current image or zooming in and out.
 
-use crate::Foximg;
+use crate::{Foximg, keybinds::FoximgAction};
 use raylib::prelude::*;
 
 const MOUSE_WHEEL_MIN: f32 = 0.;
 const MOUSE_WHEEL_MAX: f32 = 25.;
 
 impl Foximg {
-    /// Returns true if either left or right Shift is held down.
-    fn is_shift_down(&self) -> bool {
-        self.rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
-            || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT)
-    }
-
     /// Returns true if either left or right Ctrl is held down.
-    fn is_control_down(&self) -> bool {
+    pub(crate) fn is_control_down(&self) -> bool {
         self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
             || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL)
     }
 
-    /// Zooms in the image by `current_mouse_wheel` * `ZOOM_MULTIPLIER`.
-    pub fn zoom_img(&mut self, current_mouse_wheel: f32) {
-        const ZOOM_MULTIPLIER: f32 = 0.4;
+    /// Returns true if `key` was just pressed or is being held down.
+    pub(crate) fn is_key_pressed_or_repeat(&self, key: KeyboardKey) -> bool {
+        self.rl.is_key_pressed(key) || self.rl.is_key_pressed_repeat(key)
+    }
 
+    /// Zooms in the image by `current_mouse_wheel` * the zoom step in the settings.
+    pub fn zoom_img(&mut self, current_mouse_wheel: f32) {
         if let Some(ref images) = self.images {
             if images.img_failed() {
                 return;
@@ -31,10 +28,11 @@ impl Foximg {
             if !((current_mouse_wheel < 0. && self.mouse_wheel <= self::MOUSE_WHEEL_MIN)
                 || (current_mouse_wheel > 0. && self.mouse_wheel >= self::MOUSE_WHEEL_MAX))
             {
-                let mouse_world_pos = self.rl.get_screen_to_world2D(self.mouse_pos, self.camera);
-                self.camera.offset = self.mouse_pos;
+                let mouse_pos = self.compare_mouse_pos();
+                let mouse_world_pos = self.rl.get_screen_to_world2D(mouse_pos, self.camera);
+                self.camera.offset = mouse_pos;
                 self.camera.target = mouse_world_pos;
-                self.camera.zoom += current_mouse_wheel * ZOOM_MULTIPLIER;
+                self.camera.zoom += current_mouse_wheel * self.settings.zoom_step;
 
                 if self.camera.zoom < 1. {
                     self.camera.zoom = 1.;
@@ -49,9 +47,9 @@ impl Foximg {
         }
     }
 
-    /// Zooms in the image by 0.1 when Ctrl+W is held down. Returns `true` if so
+    /// Zooms in the image by 0.1 when `ZoomInSlow` is held down. Returns `true` if so
     pub fn zoom_in1_img(&mut self) -> bool {
-        if self.is_control_down() && self.rl.is_key_down(KeyboardKey::KEY_W) {
+        if self.is_action_down(FoximgAction::ZoomInSlow, false) {
             self.zoom_img(0.1);
             true
         } else {
@@ -59,9 +57,9 @@ impl Foximg {
         }
     }
 
-    /// Zooms out the image by 0.1 when Ctrl+S is held down. Returns `true` if so.
+    /// Zooms out the image by 0.1 when `ZoomOutSlow` is held down. Returns `true` if so.
     pub fn zoom_out1_img(&mut self) -> bool {
-        if self.is_control_down() && self.rl.is_key_down(KeyboardKey::KEY_S) {
+        if self.is_action_down(FoximgAction::ZoomOutSlow, false) {
             self.zoom_img(-0.1);
             true
         } else {
@@ -69,9 +67,9 @@ impl Foximg {
         }
     }
 
-    /// Zooms in the image by 0.5 when W is held down. Returns `true` if so.
+    /// Zooms in the image by 0.5 when `ZoomIn` is held down. Returns `true` if so.
     pub fn zoom_in5_img(&mut self) -> bool {
-        if self.rl.is_key_down(KeyboardKey::KEY_W) {
+        if self.is_action_down(FoximgAction::ZoomIn, false) {
             self.zoom_img(0.5);
             true
         } else {
@@ -79,9 +77,9 @@ impl Foximg {
         }
     }
 
-    /// Zooms out the image by 0.5 when S is held down. Returns `true` if so.
+    /// Zooms out the image by 0.5 when `ZoomOut` is held down. Returns `true` if so.
     pub fn zoom_out5_img(&mut self) -> bool {
-        if self.rl.is_key_down(KeyboardKey::KEY_S) {
+        if self.is_action_down(FoximgAction::ZoomOut, false) {
             self.zoom_img(-0.5);
             true
         } else {
@@ -89,72 +87,62 @@ impl Foximg {
         }
     }
 
-    /// Flips the image horizontally if Shift+Q is pressed. Returns true if so.
+    /// Flips the image horizontally if `FlipHorizontal` is pressed. Returns true if so.
     pub fn flip_horizontal_img(&mut self) -> bool {
-        let is_shift_down = self.is_shift_down();
-        if let Some(ref mut images) = self.images {
-            if is_shift_down && self.rl.is_key_pressed(KeyboardKey::KEY_Q) {
-                images.flip_horizontal(&mut self.rl, &self.rl_thread);
-                return true;
-            }
+        let pressed = self.is_action_pressed(FoximgAction::FlipHorizontal);
+        if pressed && let Some(ref mut images) = self.images {
+            images.flip_horizontal(&mut self.rl, &self.rl_thread);
+            return true;
         }
         false
     }
 
-    /// Flips the image vertically if Shift+E is pressed. Returns true if so.
+    /// Flips the image vertically if `FlipVertical` is pressed. Returns true if so.
     pub fn flip_vertical_img(&mut self) -> bool {
-        let is_shift_down = self.is_shift_down();
-        if let Some(ref mut images) = self.images {
-            if is_shift_down && self.rl.is_key_pressed(KeyboardKey::KEY_E) {
-                images.flip_vertical(&mut self.rl, &self.rl_thread);
-                return true;
-            }
+        let pressed = self.is_action_pressed(FoximgAction::FlipVertical);
+        if pressed && let Some(ref mut images) = self.images {
+            images.flip_vertical(&mut self.rl, &self.rl_thread);
+            return true;
         }
         false
     }
 
-    /// Rotates the image -1 deg if Ctrl+Q. Returns true if so.
+    /// Rotates the image -1 deg while `RotateN1` is held down. Returns true if so.
     pub fn rotate_n1_img(&mut self) -> bool {
-        let is_control_down = self.is_control_down();
-        if let Some(ref mut images) = self.images {
-            if is_control_down && self.rl.is_key_down(KeyboardKey::KEY_Q) {
-                images.rotate_n1(&mut self.rl, &self.rl_thread);
-                return true;
-            }
+        let down = self.is_action_down(FoximgAction::RotateN1, false);
+        if down && let Some(ref mut images) = self.images {
+            images.rotate_n1(&mut self.rl, &self.rl_thread);
+            return true;
         }
         false
     }
 
-    /// Rotates the image 1 deg if Ctrl+E. Returns true if so.
+    /// Rotates the image 1 deg while `Rotate1` is held down. Returns true if so.
     pub fn rotate_1_img(&mut self) -> bool {
-        let is_control_down = self.is_control_down();
-        if let Some(ref mut images) = self.images {
-            if is_control_down && self.rl.is_key_down(KeyboardKey::KEY_E) {
-                images.rotate_1(&mut self.rl, &self.rl_thread);
-                return true;
-            }
+        let down = self.is_action_down(FoximgAction::Rotate1, false);
+        if down && let Some(ref mut images) = self.images {
+            images.rotate_1(&mut self.rl, &self.rl_thread);
+            return true;
         }
         false
     }
 
-    /// Rotates the image -90 deg if Q. Returns true if so.
+    /// Rotates the image -90 deg if `RotateN90` is pressed. Returns true if so.
     pub fn rotate_n90_img(&mut self) -> bool {
-        if let Some(ref mut images) = self.images {
-            if self.rl.is_key_pressed(KeyboardKey::KEY_Q) {
-                images.rotate_n90(&mut self.rl, &self.rl_thread);
-                return true;
-            }
+        let pressed = self.is_action_pressed(FoximgAction::RotateN90);
+        if pressed && let Some(ref mut images) = self.images {
+            images.rotate_n90(&mut self.rl, &self.rl_thread);
+            return true;
         }
         false
     }
 
-    /// Rotates the image 90 deg if E. Returns true if so.
+    /// Rotates the image 90 deg if `Rotate90` is pressed. Returns true if so.
     pub fn rotate_90_img(&mut self) -> bool {
-        if let Some(ref mut images) = self.images {
-            if self.rl.is_key_pressed(KeyboardKey::KEY_E) {
-                images.rotate_90(&mut self.rl, &self.rl_thread);
-                return true;
-            }
+        let pressed = self.is_action_pressed(FoximgAction::Rotate90);
+        if pressed && let Some(ref mut images) = self.images {
+            images.rotate_90(&mut self.rl, &self.rl_thread);
+            return true;
         }
         false
     }
@@ -166,14 +154,14 @@ impl Foximg {
             .unwrap()
     }
 
-    /// Updates the current image on the gallery. Goes to the next one if D is pressed, and goes to
-    /// the previous one if A is pressed. Returns true if so.
+    /// Updates the current image on the gallery. Goes to the next one if `NextImage` is pressed,
+    /// and goes to the previous one if `PreviousImage` is pressed. Returns true if so.
     pub fn update_gallery(&mut self) -> bool {
         let mut res = false;
         self.images_with(|f, images| {
-            let pressed_a = f.rl.is_key_pressed(KeyboardKey::KEY_A);
-            let pressed_d = f.rl.is_key_pressed(KeyboardKey::KEY_D);
-            let amount = if !f.skip_count.is_empty() && (pressed_a || pressed_d) {
+            let pressed_prev = f.is_action_pressed(FoximgAction::PreviousImage);
+            let pressed_next = f.is_action_pressed(FoximgAction::NextImage);
+            let amount = if !f.skip_count.is_empty() && (pressed_prev || pressed_next) {
                 f.skip_count_to_usize()
             } else {
                 1
@@ -182,14 +170,14 @@ impl Foximg {
             if images.can_dec()
                 && (f.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
                     && f.btn_bounds.mouse_on_left_btn())
-                || pressed_a
+                || pressed_prev
             {
                 images.dec(f, amount);
                 res = true;
             } else if images.can_inc()
                 && (f.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
                     && f.btn_bounds.mouse_on_right_btn())
-                || pressed_d
+                || pressed_next
             {
                 images.inc(f, amount);
                 res = true;
@@ -219,48 +207,100 @@ impl Foximg {
         }
     }
 
-    fn pan_img_direction<F>(&mut self, vim: KeyboardKey, arrow: KeyboardKey, f: F)
+    fn pan_img_direction<F>(&mut self, action: FoximgAction, f: F)
     where
         F: FnOnce(&mut Self, f32),
     {
         const PAN_MIN: f32 = self::MOUSE_WHEEL_MAX / 3.;
         const PAN_MAX: f32 = self::MOUSE_WHEEL_MAX - PAN_MIN;
 
-        if self.mouse_wheel > 0. && (self.rl.is_key_down(vim) || self.rl.is_key_down(arrow)) {
-            let d = self.mouse_wheel.clamp(PAN_MIN, PAN_MAX);
+        if self.mouse_wheel > 0. && self.is_action_down(action, true) {
+            let d = self.mouse_wheel.clamp(PAN_MIN, PAN_MAX) * self.settings.pan_speed;
             let ctrl = self.is_control_down();
             f(self, if ctrl { d / 2. } else { d });
         }
     }
 
     pub fn pan_img_up(&mut self) {
-        self.pan_img_direction(KeyboardKey::KEY_K, KeyboardKey::KEY_UP, |f, d| {
-            f.camera.target.y -= d
-        });
+        self.pan_img_direction(FoximgAction::PanUp, |f, d| f.camera.target.y -= d);
     }
 
     pub fn pan_img_down(&mut self) {
-        self.pan_img_direction(KeyboardKey::KEY_J, KeyboardKey::KEY_DOWN, |f, d| {
-            f.camera.target.y += d
-        });
+        self.pan_img_direction(FoximgAction::PanDown, |f, d| f.camera.target.y += d);
     }
 
     pub fn pan_img_left(&mut self) {
-        self.pan_img_direction(KeyboardKey::KEY_H, KeyboardKey::KEY_LEFT, |f, d| {
-            f.camera.target.x -= d
-        });
+        self.pan_img_direction(FoximgAction::PanLeft, |f, d| f.camera.target.x -= d);
     }
 
     pub fn pan_img_right(&mut self) {
-        self.pan_img_direction(KeyboardKey::KEY_L, KeyboardKey::KEY_RIGHT, |f, d| {
-            f.camera.target.x += d
-        });
+        self.pan_img_direction(FoximgAction::PanRight, |f, d| f.camera.target.x += d);
+    }
+
+    /// Marks the current image, or unmarks it if it's already marked.
+    pub fn toggle_mark_current(&mut self) {
+        if let Some(ref mut images) = self.images {
+            images.toggle_marked(images.img_current() - 1);
+        }
+    }
+
+    /// Marks or unmarks the current image if `Mark` is pressed. Returns true if so.
+    pub fn mark_img(&mut self) -> bool {
+        if self.images.is_some() && self.is_action_pressed(FoximgAction::Mark) {
+            self.toggle_mark_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Pauses or resumes the current image if `Pause` is pressed and it's animated. Returns true if
+    /// so.
+    pub fn pause_img(&mut self) -> bool {
+        if !self.is_action_pressed(FoximgAction::Pause) {
+            return false;
+        }
+
+        let mut paused = None;
+        if let Some(ref mut images) = self.images {
+            images.img_with(&mut self.rl, &self.rl_thread, |mut img| {
+                paused = img.toggle_paused();
+            });
+        }
+
+        match paused {
+            Some(true) => self.toast("Paused animation"),
+            Some(false) => self.toast("Resumed animation"),
+            None => return false,
+        }
+        true
+    }
+
+    /// Steps the current image back or forward a frame if `PreviousFrame` or `NextFrame` is pressed
+    /// while it's a paused animation. Returns true if so.
+    pub fn step_frame_img(&mut self) -> bool {
+        let by = if self.is_action_pressed_or_repeat(FoximgAction::PreviousFrame) {
+            -1
+        } else if self.is_action_pressed_or_repeat(FoximgAction::NextFrame) {
+            1
+        } else {
+            return false;
+        };
+
+        let mut stepped = false;
+        if let Some(ref mut images) = self.images {
+            images.img_with(&mut self.rl, &self.rl_thread, |mut img| {
+                stepped = img.paused();
+                img.step_frame(by);
+            });
+        }
+        stepped
     }
 
     pub fn jump_to(&mut self) -> bool {
         let mut res = false;
         self.images_with(|f, images| {
-            if !f.skip_count.is_empty() && f.rl.is_key_pressed(KeyboardKey::KEY_G) {
+            if !f.skip_count.is_empty() && f.is_action_pressed(FoximgAction::GoTo) {
                 let goto = f.skip_count_to_usize().clamp(1, images.len()) - 1;
 
                 images.set_current(goto);
@@ -275,7 +315,7 @@ impl Foximg {
     pub fn jump_to_end(&mut self) -> bool {
         let mut res = false;
         self.images_with(|f, images| {
-            if f.is_shift_down() && f.rl.is_key_pressed(KeyboardKey::KEY_FOUR) {
+            if f.is_action_pressed(FoximgAction::LastImage) {
                 images.set_current(images.len() - 1);
                 images.update_window(f);
                 res = true;
@@ -326,7 +366,7 @@ impl Foximg {
     pub fn jump_to_start(&mut self) -> bool {
         let mut res = false;
         self.images_with(|f, images| {
-            if f.rl.is_key_pressed(KeyboardKey::KEY_ZERO) {
+            if f.is_action_pressed(FoximgAction::FirstImage) {
                 images.set_current(0);
                 images.update_window(f);
                 res = true;
@@ -0,0 +1,290 @@
+//! Defines culling: sending the current image to one of the folders in `FoximgCullSettings` with a
+//! single key, and undoing any of the file operations done to the gallery.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg,
+    config::FoximgCullAction,
+    images::FoximgImageLoader,
+    keybinds::FoximgAction,
+    trash::{self, FoximgTrashedImage},
+    xmp,
+};
+
+/// A file operation done to the gallery that can be undone.
+pub enum FoximgFileOp {
+    Trash(FoximgTrashedImage),
+    /// `loader` is `None` if the image stayed in the gallery under its new path.
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+        index: usize,
+        loader: Option<FoximgImageLoader>,
+    },
+    Copy {
+        to: PathBuf,
+    },
+    /// Pairs of the old and new paths of every renamed image.
+    Rename(Vec<(PathBuf, PathBuf)>),
+}
+
+/// Returns a path in `dir` for a file named `name` that doesn't exist yet. Names that are taken
+/// get suffixed with a number: `name (2).ext`, `name (3).ext` and so on.
+pub fn unique_path(dir: &Path, name: &Path) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = name
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (2..)
+        .map(|n| dir.join(format!("{stem} ({n}){ext}")))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Returns the name of the file at `path` for showing in toasts.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+impl Foximg {
+    /// Sends the current image to the `i`th destination of `cull_settings`, either moving or copying
+    /// it there. Does nothing if the UI is locked.
+    pub fn cull_current(&mut self, i: usize) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        let Some(destination) = self.cull_settings.destinations.get(i) else {
+            return;
+        };
+
+        let action = destination.action;
+        let destination_name = destination.name.clone();
+        let destination_path = destination.path.clone();
+
+        self.images_with(|f, images| {
+            let index = images.img_current() - 1;
+            let from = images.img_path().to_path_buf();
+            let folder = from.parent().unwrap_or(Path::new(""));
+            let name = self::file_name(&from);
+
+            let to = (|| -> anyhow::Result<PathBuf> {
+                let dir = folder.join(&destination_path);
+                fs::create_dir_all(&dir)?;
+
+                let to = self::unique_path(&dir.canonicalize()?, Path::new(&name));
+                match action {
+                    FoximgCullAction::Move => trash::move_file(&from, &to)?,
+                    FoximgCullAction::Copy => {
+                        fs::copy(&from, &to)?;
+                    }
+                }
+                Ok(to)
+            })();
+
+            let to = match to {
+                Ok(to) => to,
+                Err(e) => {
+                    f.rl.trace_log(
+                        TraceLogLevel::LOG_ERROR,
+                        &format!("FOXIMG: Failed to send {from:?} to {destination_path:?}:"),
+                    );
+                    f.rl.trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                    f.toast(format!("Couldn't send \"{name}\" to {destination_name}"));
+                    return;
+                }
+            };
+
+            match action {
+                FoximgCullAction::Move => f.move_sidecar(&from, &to),
+                FoximgCullAction::Copy => f.copy_sidecar(&from, &to),
+            }
+
+            // Files that land in the same folder show up in the gallery as if they were renamed or
+            // created. Otherwise, moving a file out of the folder removes it from the gallery.
+            let same_folder = to.parent() == Some(folder);
+            let op = match action {
+                FoximgCullAction::Move if same_folder => {
+                    images.set_path(index, to.clone());
+                    f.toast(format!("Renamed \"{name}\" ({destination_name})"));
+                    FoximgFileOp::Move {
+                        from,
+                        to,
+                        index,
+                        loader: None,
+                    }
+                }
+                FoximgCullAction::Move => {
+                    let (_, loader) = images.remove(index);
+                    f.toast(format!("Moved \"{name}\" to {destination_name}"));
+                    FoximgFileOp::Move {
+                        from,
+                        to,
+                        index,
+                        loader: Some(loader),
+                    }
+                }
+                FoximgCullAction::Copy => {
+                    if same_folder {
+                        images.insert(index + 1, to.clone(), images.loader(index));
+                    }
+                    f.toast(format!("Copied \"{name}\" to {destination_name}"));
+                    FoximgFileOp::Copy { to }
+                }
+            };
+
+            f.file_ops.push(op);
+            if images.len() > 0 {
+                images.update_window(f);
+            }
+        });
+
+        self.close_gallery_if_empty();
+    }
+
+    /// Sends the current image to a destination if one of `Cull1` to `Cull9` is pressed. Returns
+    /// true if so.
+    pub fn cull_img(&mut self) -> bool {
+        const ACTIONS: [FoximgAction; 9] = [
+            FoximgAction::Cull1,
+            FoximgAction::Cull2,
+            FoximgAction::Cull3,
+            FoximgAction::Cull4,
+            FoximgAction::Cull5,
+            FoximgAction::Cull6,
+            FoximgAction::Cull7,
+            FoximgAction::Cull8,
+            FoximgAction::Cull9,
+        ];
+
+        let Some(i) = self.which_action_pressed(&ACTIONS) else {
+            return false;
+        };
+
+        self.cull_current(i);
+        true
+    }
+
+    /// Moves an image that was sent to a destination back where it was.
+    fn undo_move(
+        &mut self,
+        from: &Path,
+        to: &Path,
+        index: usize,
+        loader: Option<FoximgImageLoader>,
+    ) {
+        let name = self::file_name(from);
+        let res = if from.exists() {
+            Err(anyhow::anyhow!("{from:?} already exists"))
+        } else {
+            trash::move_file(to, from).map_err(anyhow::Error::from)
+        };
+
+        if let Err(e) = res {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_ERROR,
+                &format!("FOXIMG: Failed to move {to:?} back to {from:?}:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+            self.toast(format!("Couldn't move \"{name}\" back"));
+            return;
+        }
+
+        self.move_sidecar(to, from);
+        self.toast(format!("Moved \"{name}\" back"));
+        match loader {
+            Some(loader) => self.reinsert_img(from, index, loader),
+            None => self.images_with(|f, images| {
+                if let Some(i) = images.paths().iter().position(|path| path == to) {
+                    images.set_path(i, from.to_path_buf());
+                    images.update_window(f);
+                }
+            }),
+        }
+    }
+
+    /// Deletes a copy made by sending an image to a destination.
+    fn undo_copy(&mut self, to: &Path) {
+        let name = self::file_name(to);
+        if let Err(e) = fs::remove_file(to) {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_ERROR,
+                &format!("FOXIMG: Failed to delete copy {to:?}:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+            self.toast(format!("Couldn't delete copy \"{name}\""));
+            return;
+        }
+
+        let sidecar = xmp::sidecar_path(to);
+        if sidecar.exists()
+            && let Err(e) = fs::remove_file(&sidecar)
+        {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_WARNING,
+                &format!("FOXIMG: Failed to delete the sidecar of copy {to:?}:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+        }
+
+        self.toast(format!("Deleted copy \"{name}\""));
+        self.images_with(|f, images| {
+            if let Some(i) = images.paths().iter().position(|path| path == to) {
+                images.remove(i);
+                if images.len() > 0 {
+                    images.update_window(f);
+                }
+            }
+        });
+        self.close_gallery_if_empty();
+    }
+
+    /// Undoes the last file operation done to the gallery. Does nothing if the UI is locked.
+    pub fn undo_file_op(&mut self) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        match self.file_ops.pop() {
+            Some(FoximgFileOp::Trash(trashed)) => self.restore_trashed(trashed),
+            Some(FoximgFileOp::Move {
+                from,
+                to,
+                index,
+                loader,
+            }) => self.undo_move(&from, &to, index, loader),
+            Some(FoximgFileOp::Copy { to }) => self.undo_copy(&to),
+            Some(FoximgFileOp::Rename(renamed)) => self.undo_rename(renamed),
+            None => self.toast("Nothing to undo"),
+        }
+    }
+
+    /// Undoes the last file operation if `UndoFileOp` is pressed. Returns true if so.
+    pub fn undo_file_op_img(&mut self) -> bool {
+        if self.is_action_pressed(FoximgAction::UndoFileOp) {
+            self.undo_file_op();
+            true
+        } else {
+            false
+        }
+    }
+}
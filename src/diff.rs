@@ -0,0 +1,174 @@
+//! Defines comparing the pixels of two images: the metrics that sum up how different they are, and
+//! the heatmap of where they differ. Images of different sizes are compared over the area they
+//! share with their top left corners lined up.
+
+use serde::Serialize;
+
+/// The constants that keep SSIM stable over flat areas, for 8-bit channels.
+const SSIM_C1: f64 = (0.01 * 255.) * (0.01 * 255.);
+const SSIM_C2: f64 = (0.03 * 255.) * (0.03 * 255.);
+/// The side of the windows SSIM is measured over.
+const SSIM_WINDOW: u32 = 8;
+
+/// An image as RGBA bytes, row by row.
+#[derive(Clone, Copy)]
+pub struct FoximgDiffInput<'a> {
+    pub rgba: &'a [u8],
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FoximgDiffInput<'_> {
+    fn pixel(&self, x: u32, y: u32) -> &[u8] {
+        let i = ((y * self.width + x) * 4) as usize;
+        &self.rgba[i..i + 4]
+    }
+
+    fn luma(&self, x: u32, y: u32) -> f64 {
+        let p = self.pixel(x, y);
+        0.299 * f64::from(p[0]) + 0.587 * f64::from(p[1]) + 0.114 * f64::from(p[2])
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct FoximgDiffMetrics {
+    /// The width of the area that got compared.
+    pub width: u32,
+    /// The height of the area that got compared.
+    pub height: u32,
+    pub sizes_match: bool,
+    /// The largest difference between two channels, from 0 to 255.
+    pub max_abs_diff: u8,
+    /// The mean difference between two channels, from 0 to 255.
+    pub mean_abs_diff: f64,
+    /// Peak signal-to-noise ratio in dB. Left out if the images are identical.
+    pub psnr: Option<f64>,
+    /// Structural similarity of the luma of both images, from -1 to 1.
+    pub ssim: f64,
+    /// How many pixels have a channel that differs by more than `threshold`.
+    pub differing_pixels: u64,
+    pub threshold: u8,
+}
+
+pub struct FoximgDiff {
+    pub metrics: FoximgDiffMetrics,
+    /// The largest difference between the channels of each pixel, row by row.
+    diffs: Vec<u8>,
+}
+
+impl FoximgDiff {
+    pub fn new(a: FoximgDiffInput, b: FoximgDiffInput, threshold: u8) -> Self {
+        let width = a.width.min(b.width);
+        let height = a.height.min(b.height);
+        let mut diffs = Vec::with_capacity((width * height) as usize);
+        let mut max_abs_diff = 0;
+        let mut sum_abs = 0u64;
+        let mut sum_sq = 0u64;
+
+        for y in 0..height {
+            for x in 0..width {
+                let diff = a
+                    .pixel(x, y)
+                    .iter()
+                    .zip(b.pixel(x, y))
+                    .map(|(a, b)| a.abs_diff(*b))
+                    .inspect(|&d| {
+                        sum_abs += u64::from(d);
+                        sum_sq += u64::from(d) * u64::from(d);
+                    })
+                    .fold(0, u8::max);
+
+                max_abs_diff = max_abs_diff.max(diff);
+                diffs.push(diff);
+            }
+        }
+
+        let channels = (diffs.len() * 4).max(1) as f64;
+        let mse = sum_sq as f64 / channels;
+        let mut diff = Self {
+            metrics: FoximgDiffMetrics {
+                width,
+                height,
+                sizes_match: a.width == b.width && a.height == b.height,
+                max_abs_diff,
+                mean_abs_diff: sum_abs as f64 / channels,
+                psnr: (mse > 0.).then(|| 10. * (255. * 255. / mse).log10()),
+                ssim: self::ssim(a, b, width, height),
+                differing_pixels: 0,
+                threshold,
+            },
+            diffs,
+        };
+        diff.set_threshold(threshold);
+        diff
+    }
+
+    /// Counts the differing pixels again with a new threshold.
+    pub fn set_threshold(&mut self, threshold: u8) {
+        self.metrics.threshold = threshold;
+        self.metrics.differing_pixels =
+            self.diffs.iter().filter(|&&diff| diff > threshold).count() as u64;
+    }
+
+    /// Returns a heatmap of the differences as RGBA bytes, as big as the compared area. Differences
+    /// get multiplied by `amplification` and go from black through red and yellow to white.
+    /// Pixels that don't differ by more than the threshold are masked out in black.
+    pub fn heatmap(&self, amplification: f32) -> Vec<u8> {
+        let threshold = self.metrics.threshold;
+        let mut rgba = Vec::with_capacity(self.diffs.len() * 4);
+
+        for &diff in &self.diffs {
+            if diff <= threshold {
+                rgba.extend_from_slice(&[0, 0, 0, 255]);
+                continue;
+            }
+
+            let t = (f32::from(diff) * amplification / 255.).clamp(0., 1.) * 3.;
+            let channel = |from: f32| ((t - from).clamp(0., 1.) * 255.) as u8;
+            rgba.extend_from_slice(&[channel(0.), channel(1.), channel(2.), 255]);
+        }
+
+        rgba
+    }
+}
+
+/// Returns the mean SSIM of the luma of `a` and `b` over windows of `SSIM_WINDOW` pixels.
+fn ssim(a: FoximgDiffInput, b: FoximgDiffInput, width: u32, height: u32) -> f64 {
+    let window_width = SSIM_WINDOW.min(width);
+    let window_height = SSIM_WINDOW.min(height);
+    if window_width == 0 || window_height == 0 {
+        return 1.;
+    }
+
+    let n = f64::from(window_width * window_height);
+    let mut total = 0.;
+    let mut windows = 0;
+
+    for wy in (0..=height - window_height).step_by(window_height as usize) {
+        for wx in (0..=width - window_width).step_by(window_width as usize) {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0., 0., 0., 0., 0.);
+            for y in wy..wy + window_height {
+                for x in wx..wx + window_width {
+                    let (la, lb) = (a.luma(x, y), b.luma(x, y));
+                    sum_a += la;
+                    sum_b += lb;
+                    sum_aa += la * la;
+                    sum_bb += lb * lb;
+                    sum_ab += la * lb;
+                }
+            }
+
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let cov = sum_ab / n - mean_a * mean_b;
+
+            total += ((2. * mean_a * mean_b + SSIM_C1) * (2. * cov + SSIM_C2))
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2));
+            windows += 1;
+        }
+    }
+
+    total / f64::from(windows)
+}
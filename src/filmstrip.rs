@@ -0,0 +1,242 @@
+//! Defines the filmstrip, a bar of thumbnails of the images around the current one, drawn along the
+//! bottom edge of the window.
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw,
+    images::FoximgImages,
+    keybinds::FoximgAction,
+    thumbnail::{FoximgThumbnailSize, FoximgThumbnails},
+};
+
+pub struct FoximgFilmstrip {
+    thumbnails: FoximgThumbnails,
+    /// The index of the image the filmstrip was last centered on.
+    current: usize,
+    /// How many tiles the filmstrip has been scrolled away from the current image.
+    scroll: isize,
+    hovered: Option<usize>,
+}
+
+impl FoximgFilmstrip {
+    const TILE_SIDE: f32 = 64.;
+    const TILE_PADDING: f32 = 6.;
+    const TILE_STRIDE: f32 = Self::TILE_SIDE + Self::TILE_PADDING;
+    /// The height of the filmstrip, which is taken away from the area the current image is drawn in.
+    pub const HEIGHT: f32 = Self::TILE_SIDE + Self::TILE_PADDING * 2.;
+
+    pub fn new(rl: &RaylibHandle) -> Self {
+        Self {
+            thumbnails: FoximgThumbnails::new(rl, FoximgThumbnailSize::Normal),
+            current: 0,
+            scroll: 0,
+            hovered: None,
+        }
+    }
+
+    /// Returns the index of the image shown in the middle of the filmstrip.
+    fn center(&self) -> isize {
+        self.current as isize + self.scroll
+    }
+
+    fn tile_rect(&self, i: usize, screen_width: f32, screen_height: f32) -> Rectangle {
+        let offset = (i as isize - self.center()) as f32;
+
+        rrect(
+            screen_width / 2. - Self::TILE_SIDE / 2. + offset * Self::TILE_STRIDE,
+            screen_height - Self::HEIGHT + Self::TILE_PADDING,
+            Self::TILE_SIDE,
+            Self::TILE_SIDE,
+        )
+    }
+
+    /// Returns the range of images whose tiles are at least partially on screen.
+    fn visible(&self, len: usize, screen_width: f32) -> (usize, usize) {
+        let half = (screen_width / 2. / Self::TILE_STRIDE).ceil() as isize + 1;
+        let start = (self.center() - half).clamp(0, len as isize) as usize;
+        let end = (self.center() + half + 1).clamp(0, len as isize) as usize;
+        (start, end)
+    }
+
+    /// Requests the thumbnails of the visible tiles and unloads every other thumbnail.
+    fn update_thumbnails(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rl_thread: &RaylibThread,
+        images: &FoximgImages,
+    ) {
+        let (start, end) = self.visible(images.len(), rl.get_screen_width().as_f32());
+        let paths = &images.paths()[start..end];
+
+        self.thumbnails
+            .retain(|path| paths.iter().any(|p| p == path));
+        for (i, path) in paths.iter().enumerate() {
+            if !images.failed(start + i) {
+                self.thumbnails.request(path);
+            }
+        }
+        self.thumbnails.update(rl, rl_thread);
+    }
+}
+
+impl Foximg {
+    /// Shows or hides the filmstrip. Does nothing if the UI is locked.
+    pub fn toggle_filmstrip(&mut self) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        if self.filmstrip.take().is_some() {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Hid filmstrip");
+        } else {
+            self.filmstrip = Some(FoximgFilmstrip::new(&self.rl));
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Showed filmstrip");
+        }
+
+        self.state.filmstrip = self.filmstrip.is_some();
+    }
+
+    /// Toggles the filmstrip if `Filmstrip` is pressed. Returns true if so.
+    pub fn filmstrip_view(&mut self) -> bool {
+        if self.lock.is_none() && self.is_action_pressed(FoximgAction::Filmstrip) {
+            self.toggle_filmstrip();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns whether the filmstrip is currently drawn. It's hidden in fullscreen, when the UI is
+    /// locked and while the grid view is open.
+    pub fn filmstrip_shown(&self) -> bool {
+        self.filmstrip.is_some()
+            && self.images.is_some()
+            && self.grid.is_none()
+            && self.lock.is_none()
+            && !self.state.fullscreen
+    }
+
+    /// Returns the height of the filmstrip, or 0 if it's not shown.
+    pub fn filmstrip_height(&self) -> f32 {
+        if self.filmstrip_shown() {
+            FoximgFilmstrip::HEIGHT
+        } else {
+            0.
+        }
+    }
+
+    /// Returns whether the mouse is hovering over the filmstrip.
+    pub fn mouse_on_filmstrip(&self) -> bool {
+        self.filmstrip_shown()
+            && self.mouse_pos.y >= self.rl.get_screen_height().as_f32() - FoximgFilmstrip::HEIGHT
+    }
+
+    /// Updates the filmstrip. Scrolls it with the mouse wheel while it's hovered and opens the image
+    /// whose tile gets clicked.
+    pub fn update_filmstrip(&mut self) {
+        if !self.filmstrip_shown() {
+            return;
+        }
+
+        let mouse_on_filmstrip = self.mouse_on_filmstrip();
+        let Some(mut filmstrip) = self.filmstrip.take() else {
+            return;
+        };
+
+        self.images_with(|f, images| {
+            let screen_width = f.rl.get_screen_width().as_f32();
+            let screen_height = f.rl.get_screen_height().as_f32();
+            let len = images.len();
+            let current = images.img_current() - 1;
+
+            // Changing the image recenters the filmstrip on it.
+            if filmstrip.current != current {
+                filmstrip.current = current;
+                filmstrip.scroll = 0;
+            }
+
+            if mouse_on_filmstrip {
+                let wheel = f.rl.get_mouse_wheel_move();
+                if wheel != 0. {
+                    filmstrip.scroll -= wheel.signum() as isize;
+                    filmstrip.scroll = filmstrip
+                        .scroll
+                        .clamp(-(current as isize), (len - 1 - current) as isize);
+                }
+            }
+
+            let (start, end) = filmstrip.visible(len, screen_width);
+            filmstrip.hovered = (start..end).find(|&i| {
+                mouse_on_filmstrip
+                    && filmstrip
+                        .tile_rect(i, screen_width, screen_height)
+                        .check_collision_point_rec(f.mouse_pos)
+            });
+            filmstrip.update_thumbnails(&mut f.rl, &f.rl_thread, images);
+
+            if let Some(hovered) = filmstrip.hovered {
+                f.rl.set_mouse_cursor(MouseCursor::MOUSE_CURSOR_POINTING_HAND);
+                if hovered != current
+                    && f.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+                {
+                    images.set_current(hovered);
+                    images.update_window(f);
+                }
+            }
+        });
+
+        self.filmstrip = Some(filmstrip);
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Returns the height of the filmstrip, or 0 if it's not shown.
+    pub fn filmstrip_height(&self) -> f32 {
+        if self.filmstrip.is_some() {
+            FoximgFilmstrip::HEIGHT
+        } else {
+            0.
+        }
+    }
+
+    pub fn draw_filmstrip(&mut self, images: &FoximgImages) {
+        let Some(filmstrip) = self.filmstrip else {
+            return;
+        };
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let screen_height = self.d.get_screen_height().as_f32();
+        let current = images.img_current() - 1;
+
+        // The background covers the current image when it's zoomed in past the filmstrip.
+        self.d.draw_rectangle_rec(
+            rrect(
+                0,
+                screen_height - FoximgFilmstrip::HEIGHT,
+                screen_width,
+                FoximgFilmstrip::HEIGHT,
+            ),
+            *self.style.bg,
+        );
+
+        let (start, end) = filmstrip.visible(images.len(), screen_width);
+        for i in start..end {
+            let rect = filmstrip.tile_rect(i, screen_width, screen_height);
+
+            if filmstrip.hovered == Some(i) || current == i {
+                self.d
+                    .draw_rectangle_rec(rect, self.style.accent.alpha(0.2));
+            }
+
+            self.draw_thumbnail(&filmstrip.thumbnails, images, i, rect);
+
+            if current == i {
+                self.d
+                    .draw_rectangle_lines_ex(rect, 2., self.style.accent.alpha(1.));
+            }
+        }
+    }
+}
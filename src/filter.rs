@@ -0,0 +1,580 @@
+//! Defines the gallery filter, which narrows the gallery down to the images that match it. Filters
+//! are written as queries, such as `name:IMG_* format:png,webp width:1920.. rating:4`, both in the
+//! filter bar and with `--filter`.
+
+use std::{
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use chrono::NaiveDate;
+use image::{ImageFormat, ImageReader};
+use raylib::prelude::*;
+use regex::Regex;
+
+use crate::{
+    Foximg,
+    images::{self, FoximgImageLoader},
+    keybinds::FoximgAction,
+    prompt::FoximgTextInput,
+    rename,
+    xmp::{FoximgLabel, FoximgXmp},
+};
+
+/// An inclusive range, written as `min..max`. Either side can be left out, and a single value
+/// matches only itself.
+#[derive(Clone, Copy, PartialEq)]
+pub struct FoximgRange<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T> Default for FoximgRange<T> {
+    fn default() -> Self {
+        Self {
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl<T: PartialOrd + Copy> FoximgRange<T> {
+    fn is_any(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    fn contains(&self, value: T) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    fn parse(range: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Self> {
+        let bound = |bound: &str| {
+            if bound.is_empty() {
+                Some(None)
+            } else {
+                parse(bound).map(Some)
+            }
+        };
+
+        match range.split_once("..") {
+            Some((min, max)) => Some(Self {
+                min: bound(min)?,
+                max: bound(max)?,
+            }),
+            None => {
+                let value = parse(range)?;
+                Some(Self {
+                    min: Some(value),
+                    max: Some(value),
+                })
+            }
+        }
+    }
+
+    /// Returns the range as a query term, or `None` if it matches everything.
+    fn term(&self, key: &str, show: impl Fn(T) -> String) -> Option<String> {
+        match (self.min, self.max) {
+            (None, None) => None,
+            (Some(min), Some(max)) if min == max => Some(format!("{key}:{}", show(min))),
+            (min, max) => Some(format!(
+                "{key}:{}..{}",
+                min.map(&show).unwrap_or_default(),
+                max.map(&show).unwrap_or_default()
+            )),
+        }
+    }
+}
+
+/// Matches the names of images, including their extension.
+#[derive(Clone)]
+pub enum FoximgNamePattern {
+    /// `*` matches any number of characters, and `?` matches a single one. Case is ignored.
+    Glob(String),
+    Regex(Regex),
+}
+
+impl PartialEq for FoximgNamePattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Glob(a), Self::Glob(b)) => a == b,
+            (Self::Regex(a), Self::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl FoximgNamePattern {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Glob(glob) => self::glob_match(glob, name),
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Returns whether `name` matches `glob` as a whole.
+fn glob_match(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut g, mut n) = (0, 0);
+    // Where the last `*` was, and where in `name` it started matching from.
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some(&'*') => {
+                star = Some((g, n));
+                g += 1;
+            }
+            Some(&'?') => {
+                g += 1;
+                n += 1;
+            }
+            Some(&c) if c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            // Backtrack by making the last `*` match one more character.
+            _ => match star {
+                Some((star_g, star_n)) => {
+                    g = star_g + 1;
+                    n = star_n + 1;
+                    star = Some((star_g, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// Parses a file size in bytes, optionally followed by a `K`, `M` or `G` multiple of 1024.
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.to_ascii_uppercase();
+    let size = size.trim_end_matches("IB").trim_end_matches('B');
+    let (number, multiplier) = match size.char_indices().last()? {
+        (i, 'K') => (&size[..i], 1 << 10),
+        (i, 'M') => (&size[..i], 1 << 20),
+        (i, 'G') => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+
+    let number: f64 = number.parse().ok()?;
+    (number >= 0.).then_some((number * multiplier as f64) as u64)
+}
+
+fn show_size(size: u64) -> String {
+    match size {
+        0 => "0".to_string(),
+        size if size % (1 << 30) == 0 => format!("{}G", size >> 30),
+        size if size % (1 << 20) == 0 => format!("{}M", size >> 20),
+        size if size % (1 << 10) == 0 => format!("{}K", size >> 10),
+        size => size.to_string(),
+    }
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Narrows the gallery down to the images that match every one of these.
+#[derive(Clone, Default, PartialEq)]
+pub struct FoximgFilter {
+    pub name: Option<FoximgNamePattern>,
+    pub formats: Vec<ImageFormat>,
+    pub width: FoximgRange<u32>,
+    pub height: FoximgRange<u32>,
+    /// In bytes.
+    pub size: FoximgRange<u64>,
+    /// When the image was taken according to its EXIF metadata, or when it was last modified.
+    pub date: FoximgRange<NaiveDate>,
+    pub min_rating: u8,
+    pub label: Option<FoximgLabel>,
+    pub tag: Option<String>,
+}
+
+impl FoximgFilter {
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Returns whether the image at `path` matches the filter. The cheapest checks go first, so
+    /// that images only get opened if the filter asks for their dimensions.
+    pub fn matches(&self, path: &Path) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if self
+            .name
+            .as_ref()
+            .is_some_and(|pattern| !pattern.matches(&name))
+        {
+            return false;
+        }
+
+        if !self.formats.is_empty()
+            && !ImageFormat::from_path(path).is_ok_and(|format| self.formats.contains(&format))
+        {
+            return false;
+        }
+
+        if !self.size.is_any()
+            && !fs::metadata(path).is_ok_and(|metadata| self.size.contains(metadata.len()))
+        {
+            return false;
+        }
+
+        if !self.date.is_any()
+            && !rename::date_taken(path).is_some_and(|date| self.date.contains(date.date()))
+        {
+            return false;
+        }
+
+        if !self.width.is_any() || !self.height.is_any() {
+            let dimensions = ImageReader::open(path)
+                .and_then(ImageReader::with_guessed_format)
+                .ok()
+                .and_then(|reader| reader.into_dimensions().ok());
+            let Some((width, height)) = dimensions else {
+                return false;
+            };
+
+            if !self.width.contains(width) || !self.height.contains(height) {
+                return false;
+            }
+        }
+
+        if self.min_rating > 0 || self.label.is_some() || self.tag.is_some() {
+            let xmp = FoximgXmp::read(path).unwrap_or_default();
+            let tag_matches = self.tag.as_ref().is_none_or(|tag| {
+                xmp.tags
+                    .iter()
+                    .any(|other| other.to_lowercase() == tag.to_lowercase())
+            });
+
+            if xmp.rating < self.min_rating as i8
+                || self.label.is_some_and(|label| xmp.label != Some(label))
+                || !tag_matches
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn parse_term(&mut self, term: &str) -> Result<(), String> {
+        let Some((key, value)) = term.split_once(':') else {
+            // Bare words are matched against the name.
+            self.name = Some(FoximgNamePattern::Glob(term.to_string()));
+            return Ok(());
+        };
+
+        let invalid = || format!("Invalid {key} \"{value}\"");
+        match key {
+            "name" => self.name = Some(FoximgNamePattern::Glob(value.to_string())),
+            "regex" => {
+                let regex = Regex::new(value).map_err(|e| format!("Invalid regex: {e}"))?;
+                self.name = Some(FoximgNamePattern::Regex(regex));
+            }
+            "format" => {
+                self.formats = value
+                    .split(',')
+                    .map(|ext| ImageFormat::from_extension(ext).ok_or_else(invalid))
+                    .collect::<Result<_, _>>()?;
+            }
+            "width" => {
+                self.width = FoximgRange::parse(value, |n| n.parse().ok()).ok_or_else(invalid)?
+            }
+            "height" => {
+                self.height = FoximgRange::parse(value, |n| n.parse().ok()).ok_or_else(invalid)?
+            }
+            "size" => {
+                self.size = FoximgRange::parse(value, self::parse_size).ok_or_else(invalid)?
+            }
+            "date" => {
+                self.date = FoximgRange::parse(value, self::parse_date).ok_or_else(invalid)?
+            }
+            "rating" => {
+                self.min_rating = value
+                    .parse()
+                    .ok()
+                    .filter(|&rating| rating <= 5)
+                    .ok_or_else(invalid)?
+            }
+            "label" => {
+                self.label = Some(
+                    FoximgLabel::ALL
+                        .into_iter()
+                        .find(|label| label.name().eq_ignore_ascii_case(value))
+                        .ok_or_else(invalid)?,
+                )
+            }
+            "tag" => self.tag = Some(value.to_string()),
+            _ => return Err(format!("Unknown filter \"{key}\"")),
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for FoximgFilter {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::default();
+        for term in query.split_whitespace() {
+            filter.parse_term(term)?;
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for FoximgFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut terms = Vec::new();
+        match self.name {
+            Some(FoximgNamePattern::Glob(ref glob)) => terms.push(format!("name:{glob}")),
+            Some(FoximgNamePattern::Regex(ref regex)) => terms.push(format!("regex:{regex}")),
+            None => (),
+        }
+
+        if !self.formats.is_empty() {
+            let formats: Vec<_> = self
+                .formats
+                .iter()
+                .map(|format| format.extensions_str()[0])
+                .collect();
+            terms.push(format!("format:{}", formats.join(",")));
+        }
+
+        terms.extend(self.width.term("width", |n| n.to_string()));
+        terms.extend(self.height.term("height", |n| n.to_string()));
+        terms.extend(self.size.term("size", self::show_size));
+        terms.extend(
+            self.date
+                .term("date", |date| date.format("%Y-%m-%d").to_string()),
+        );
+
+        if self.min_rating > 0 {
+            terms.push(format!("rating:{}", self.min_rating));
+        }
+        if let Some(label) = self.label {
+            terms.push(format!("label:{}", label.name().to_lowercase()));
+        }
+        if let Some(ref tag) = self.tag {
+            terms.push(format!("tag:{tag}"));
+        }
+
+        f.write_str(&terms.join(" "))
+    }
+}
+
+/// The images that matched a filter in a folder, sorted by their paths.
+type FoximgScanResult = io::Result<Vec<(PathBuf, FoximgImageLoader)>>;
+
+/// A folder being scanned for the images that match the filter on a background thread, since
+/// matching reads the metadata of every image.
+pub struct FoximgFilterScan {
+    folder: PathBuf,
+    /// The filter to go back to if no images match.
+    previous: FoximgFilter,
+    results: Receiver<FoximgScanResult>,
+}
+
+/// Returns the images in `folder` that match `filter`, sorted by their paths.
+fn scan(folder: &Path, filter: &FoximgFilter) -> FoximgScanResult {
+    let mut matching = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        match images::image_loader(&path) {
+            Some(loader) if filter.matches(&path) => matching.push((path, loader)),
+            _ => (),
+        }
+    }
+
+    matching.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(matching)
+}
+
+impl Foximg {
+    /// Changes the filter with `f` and starts narrowing the gallery down with it. The current image
+    /// stays selected if it still matches. The filter goes back to how it was if no images match it,
+    /// or if the user cancels when asked about the unsaved edits of images that don't match it.
+    pub fn set_filter(&mut self, f: impl FnOnce(&mut FoximgFilter)) {
+        if self.images.is_none() || self.lock.is_some() {
+            return;
+        }
+
+        // If a scan is still running, going back should skip the filter it was started for too.
+        let previous = match self.filter_scan.take() {
+            Some(scan) => scan.previous,
+            None => self.filter.clone(),
+        };
+        f(&mut self.filter);
+        self.scan_folder(previous);
+    }
+
+    /// Starts scanning the gallery's folder for the images that match the filter, going back to
+    /// `previous` if none do. A scan that's still running gets replaced.
+    pub fn scan_folder(&mut self, previous: FoximgFilter) {
+        let Some(folder) = self
+            .images
+            .as_ref()
+            .and_then(|images| images.img_path().parent())
+            .map(Path::to_path_buf)
+        else {
+            return;
+        };
+
+        let (sender, results) = mpsc::channel();
+        let filter = self.filter.clone();
+        let scanned = folder.clone();
+        let spawned = thread::Builder::new()
+            .name("foximg-filter".to_string())
+            .spawn(move || {
+                // The scan might've been replaced in the meantime.
+                let _ = sender.send(self::scan(&scanned, &filter));
+            });
+
+        match spawned {
+            Ok(_) => {
+                self.filter_scan = Some(FoximgFilterScan {
+                    folder,
+                    previous,
+                    results,
+                });
+            }
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    "FOXIMG: Failed to spawn filter thread:",
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                self.filter = previous;
+                self.toast("Couldn't filter the gallery");
+            }
+        }
+    }
+
+    /// Narrows the gallery down to the images found by the filter scan once it's done. Images that
+    /// don't match are only dropped after asking about their unsaved edits.
+    pub fn update_filter_scan(&mut self) {
+        let Some(scan) = self.filter_scan.take() else {
+            return;
+        };
+
+        let images = match scan.results.try_recv() {
+            Ok(images) => images,
+            Err(TryRecvError::Empty) => {
+                self.filter_scan = Some(scan);
+                return;
+            }
+            Err(TryRecvError::Disconnected) => Err(io::Error::other("The filter thread stopped")),
+        };
+
+        // The gallery might've moved on to another folder in the meantime.
+        let Some(ref gallery) = self.images else {
+            return;
+        };
+        if gallery.img_path().parent() != Some(&scan.folder) {
+            return;
+        }
+
+        let images = match images {
+            Ok(images) if !images.is_empty() => images,
+            Ok(_) => {
+                self.filter = scan.previous;
+                self.toast("No images match the filter");
+                return;
+            }
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    &format!("FOXIMG: Failed to filter {:?}:", scan.folder),
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                self.filter = scan.previous;
+                self.toast("Couldn't filter the gallery");
+                return;
+            }
+        };
+
+        let matching: HashSet<_> = images.iter().map(|(path, _)| path.as_path()).collect();
+        let dropped = gallery
+            .edited_indices()
+            .into_iter()
+            .filter(|&i| !matching.contains(gallery.paths()[i].as_path()))
+            .collect();
+        if !self.check_edits(dropped, false) {
+            self.filter = scan.previous;
+            return;
+        }
+
+        self.images_with(|f, gallery| {
+            gallery.refilter(images);
+            gallery.update_window(f);
+        });
+
+        if self.filter.is_active() {
+            let len = self.images.as_ref().map_or(0, |images| images.len());
+            self.toast(format!("Showing {len} matching images"));
+        } else {
+            self.toast("Showing every image");
+        }
+    }
+
+    /// Shows the filter bar, a prompt for writing the filter as a query. Leaving it empty shows
+    /// every image.
+    pub fn edit_filter(&mut self) {
+        if self.images.is_none() || self.lock.is_some() {
+            return;
+        }
+
+        let query = self.filter.to_string();
+        let input = FoximgTextInput::new(
+            "Filter",
+            "name: regex: format: width: height: size: date: rating: label: tag:",
+            query,
+        );
+
+        if let Some(filter) = self.prompt_text(input, str::parse::<FoximgFilter>) {
+            self.set_filter(|previous| *previous = filter);
+        }
+    }
+
+    /// Shows the filter bar if `Filter` is pressed. Returns true if so.
+    pub fn filter_img(&mut self) -> bool {
+        if self.is_action_pressed(FoximgAction::Filter) {
+            self.edit_filter();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Shows a prompt for the tag to filter the gallery by. Leaving it empty stops filtering by tag.
+    pub fn filter_by_tag(&mut self) {
+        let tag = self.filter.tag.clone().unwrap_or_default();
+        let input = FoximgTextInput::new("Filter by Tag", "Enter to filter, Esc to cancel", tag);
+
+        let Some(tag) = self.prompt_text(input, |tag| {
+            let tag = tag.trim();
+            Ok::<_, String>((!tag.is_empty()).then(|| tag.to_string()))
+        }) else {
+            return;
+        };
+
+        self.set_filter(|filter| filter.tag = tag);
+    }
+}
@@ -0,0 +1,317 @@
+//! Defines the fuzzy finder for going to an image in the gallery by typing part of its name. The
+//! highlighted match is shown behind the finder while it's open.
+
+use std::{cmp::Reverse, path::PathBuf};
+
+use aho_corasick::AhoCorasick;
+use raylib::prelude::*;
+
+use crate::{Foximg, FoximgDraw, keybinds::FoximgAction, resources};
+
+/// How many matches the finder shows at once.
+const VISIBLE_MATCHES: usize = 10;
+
+/// Scores how well `query` fuzzy matches `name`, or returns `None` if the characters of `query`
+/// don't appear in `name` in order. Matches that run on from the last one or start a word score
+/// higher, and so do matches closer to the start of `name`.
+fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut prev_matched = false;
+    let mut prev = None;
+
+    for (pos, c) in name.chars().enumerate() {
+        let Some(&q) = query.peek() else {
+            break;
+        };
+
+        let word_start = prev.is_none_or(|prev: char| {
+            !prev.is_alphanumeric() || (prev.is_lowercase() && c.is_uppercase())
+        });
+        prev = Some(c);
+
+        if !c.to_lowercase().eq(q.to_lowercase()) {
+            prev_matched = false;
+            continue;
+        }
+
+        query.next();
+        score += 1;
+        if prev_matched {
+            score += 5;
+        }
+        if word_start {
+            score += 8;
+        }
+        if pos == 0 {
+            score += 4;
+        }
+        prev_matched = true;
+    }
+
+    query.peek().is_none().then_some(score)
+}
+
+/// The state of an open finder.
+struct FoximgFinder {
+    query: String,
+    /// Indices into the gallery's paths of every image that matches `query`, best match first.
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl FoximgFinder {
+    fn new(paths: &[PathBuf]) -> Self {
+        let mut finder = Self {
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+        };
+        finder.update_matches(paths);
+        finder
+    }
+
+    /// Matches every path against the query again and highlights the best match.
+    fn update_matches(&mut self, paths: &[PathBuf]) {
+        // Exact substrings of the name always rank above scattered characters.
+        const SUBSTRING_BONUS: i64 = 1000;
+
+        self.selected = 0;
+        if self.query.is_empty() {
+            self.matches = (0..paths.len()).collect();
+            return;
+        }
+
+        let substring = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .build([&self.query])
+            .ok();
+        let mut scored: Vec<(i64, usize)> = paths
+            .iter()
+            .enumerate()
+            .filter_map(|(i, path)| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let mut score = self::fuzzy_score(&self.query, &name)?;
+                if substring.as_ref().is_some_and(|ac| ac.is_match(&*name)) {
+                    score += SUBSTRING_BONUS;
+                }
+                Some((score, i))
+            })
+            .collect();
+
+        scored.sort_by_key(|&(score, i)| (Reverse(score), i));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+    }
+
+    /// Returns the index of the highlighted image, if anything matches.
+    fn selected_img(&self) -> Option<usize> {
+        self.matches.get(self.selected).copied()
+    }
+
+    /// Moves the highlight by `by` matches, stopping at either end.
+    fn move_selected(&mut self, by: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+
+        self.selected = self
+            .selected
+            .saturating_add_signed(by)
+            .min(self.matches.len() - 1);
+    }
+}
+
+impl Foximg {
+    /// Shows the fuzzy finder. This runs its own loop until an image is picked with Enter or the
+    /// finder is closed with Escape, which goes back to the image that was open before. Does
+    /// nothing if the UI is locked.
+    pub fn find_img(&mut self) {
+        let Some(ref images) = self.images else {
+            return;
+        };
+
+        if self.lock.is_some() {
+            return;
+        }
+
+        let original = images.img_current() - 1;
+        let mut finder = FoximgFinder::new(images.paths());
+        let mut picked = false;
+
+        // Throw away anything typed before the finder showed up.
+        while self.rl.get_char_pressed().is_some() {}
+
+        while !self.rl.window_should_close() {
+            self.update();
+
+            let mut changed = false;
+            while let Some(c) = self.rl.get_char_pressed() {
+                finder.query.push(c);
+                changed = true;
+            }
+
+            if self.is_key_pressed_or_repeat(KeyboardKey::KEY_BACKSPACE) {
+                changed |= finder.query.pop().is_some();
+            }
+
+            if changed && let Some(ref images) = self.images {
+                finder.update_matches(images.paths());
+            }
+
+            if self.is_key_pressed_or_repeat(KeyboardKey::KEY_DOWN) {
+                finder.move_selected(1);
+            } else if self.is_key_pressed_or_repeat(KeyboardKey::KEY_UP) {
+                finder.move_selected(-1);
+            } else if self.is_key_pressed_or_repeat(KeyboardKey::KEY_PAGE_DOWN) {
+                finder.move_selected(VISIBLE_MATCHES as isize);
+            } else if self.is_key_pressed_or_repeat(KeyboardKey::KEY_PAGE_UP) {
+                finder.move_selected(-(VISIBLE_MATCHES as isize));
+            }
+
+            if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                break;
+            } else if (self.rl.is_key_pressed(KeyboardKey::KEY_ENTER)
+                || self.rl.is_key_pressed(KeyboardKey::KEY_KP_ENTER))
+                && finder.selected_img().is_some()
+            {
+                picked = true;
+                break;
+            }
+
+            let current = finder.selected_img().unwrap_or(original);
+            FoximgDraw::begin(self, |mut d, images| {
+                if let Some(images) = images {
+                    images.set_current(current);
+                    d.draw_current_img(images);
+                    d.draw_finder(&finder, images.paths());
+                }
+            });
+        }
+
+        let current = finder.selected_img().filter(|_| picked).unwrap_or(original);
+        self.images_with(|f, images| {
+            images.set_current(current);
+            if picked {
+                images.update_window(f);
+            }
+        });
+    }
+
+    /// Opens the fuzzy finder if `Find` is pressed. Returns true if so.
+    pub fn find_img_key(&mut self) -> bool {
+        if self.images.is_some() && self.is_action_pressed(FoximgAction::Find) {
+            self.find_img();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the query and matches of a `FoximgFinder` along the top of the window, leaving the
+    /// highlighted image in view.
+    fn draw_finder(&mut self, finder: &FoximgFinder, paths: &[PathBuf]) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const WIDTH: f32 = 480.;
+        const PADDING: f32 = 10.;
+        const LINE: f32 = FONT_SIZE + PADDING;
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let rows = finder.matches.len().min(VISIBLE_MATCHES);
+        let height = PADDING * 2. + LINE * (rows + 2) as f32;
+        let rect = rrect(screen_width / 2. - WIDTH / 2., PADDING, WIDTH, height);
+        let yudit = &self.resources.yudit;
+        let x = rect.x + PADDING;
+        let mut y = rect.y + PADDING;
+
+        self.d.draw_rectangle_rec(rect, self.style.bg.alpha(0.9));
+        self.d
+            .draw_rectangle_lines_ex(rect, 1., self.style.accent.alpha(1.));
+
+        let field = rrect(x, y - PADDING / 2., WIDTH - PADDING * 2., LINE);
+        self.d
+            .draw_rectangle_lines_ex(field, 1., self.style.accent.alpha(0.5));
+
+        // Long queries are scrolled so that the end of the text stays in view.
+        let text_width = yudit.measure_text(&finder.query, FONT_SIZE, FONT_SPACING).x;
+        let text_x = x + PADDING / 2. - (text_width - (field.width - PADDING)).max(0.);
+        {
+            let mut s = self.d.begin_scissor_mode(
+                field.x as i32,
+                field.y as i32,
+                field.width as i32,
+                field.height as i32,
+            );
+            s.draw_text_ex(
+                yudit,
+                &finder.query,
+                rvec2(text_x, y),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent,
+            );
+            if s.get_time() % 1. < 0.5 {
+                s.draw_rectangle_rec(
+                    rrect(text_x + text_width, y, 1, FONT_SIZE),
+                    self.style.accent,
+                );
+            }
+        }
+        y += LINE;
+
+        // Scroll the list so that the highlighted match stays in view.
+        let first = finder.selected.saturating_sub(VISIBLE_MATCHES - 1);
+        {
+            let mut s = self.d.begin_scissor_mode(
+                rect.x as i32,
+                y as i32,
+                rect.width as i32,
+                (LINE * rows as f32) as i32,
+            );
+            for (row, &i) in finder.matches.iter().enumerate().skip(first).take(rows) {
+                if row == finder.selected {
+                    s.draw_rectangle_rec(
+                        rrect(rect.x, y - PADDING / 2., rect.width, LINE),
+                        self.style.accent.alpha(0.2),
+                    );
+                }
+
+                let number = format!("{}", i + 1);
+                let number_width = yudit.measure_text(&number, FONT_SIZE, FONT_SPACING).x;
+                s.draw_text_ex(
+                    yudit,
+                    &paths[i].file_name().unwrap_or_default().to_string_lossy(),
+                    rvec2(x, y),
+                    FONT_SIZE,
+                    FONT_SPACING,
+                    self.style.accent,
+                );
+                s.draw_text_ex(
+                    yudit,
+                    &number,
+                    rvec2(rect.x + rect.width - PADDING - number_width, y),
+                    FONT_SIZE,
+                    FONT_SPACING,
+                    self.style.accent.alpha(0.5),
+                );
+                y += LINE;
+            }
+        }
+
+        let hint = match finder.matches.len() {
+            0 => "No matches".to_string(),
+            1 => "1 match. Enter to go to it, Esc to cancel".to_string(),
+            n => format!("{n} matches. Enter to go to one, Esc to cancel"),
+        };
+        self.d.draw_text_ex(
+            yudit,
+            &hint,
+            rvec2(x, y),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent.alpha(0.5),
+        );
+    }
+}
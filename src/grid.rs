@@ -0,0 +1,323 @@
+//! Defines the grid view, an overview of the whole gallery made of thumbnails.
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw,
+    images::FoximgImages,
+    keybinds::FoximgAction,
+    resources,
+    thumbnail::{FoximgThumbnail, FoximgThumbnailSize, FoximgThumbnails},
+};
+
+pub struct FoximgGrid {
+    thumbnails: FoximgThumbnails,
+    selected: usize,
+    hovered: Option<usize>,
+    scroll: f32,
+}
+
+impl FoximgGrid {
+    const TILE_SIDE: f32 = 160.;
+    const TILE_PADDING: f32 = 12.;
+    const TILE_STRIDE: f32 = Self::TILE_SIDE + Self::TILE_PADDING;
+    /// Extra rows above and below the visible ones whose thumbnails get loaded ahead of time.
+    const PRELOAD_ROWS: usize = 2;
+    const SCROLL_SPEED: f32 = Self::TILE_STRIDE / 2.;
+
+    pub fn new(rl: &RaylibHandle, selected: usize) -> Self {
+        Self {
+            thumbnails: FoximgThumbnails::new(rl, FoximgThumbnailSize::Normal),
+            selected,
+            hovered: None,
+            scroll: 0.,
+        }
+    }
+
+    fn columns(screen_width: f32) -> usize {
+        (((screen_width - Self::TILE_PADDING) / Self::TILE_STRIDE) as usize).max(1)
+    }
+
+    /// Returns the x position where the grid starts so that it's centered horizontally.
+    fn left(screen_width: f32, columns: usize) -> f32 {
+        (screen_width - columns as f32 * Self::TILE_STRIDE + Self::TILE_PADDING) / 2.
+    }
+
+    fn tile_rect(&self, i: usize, screen_width: f32) -> Rectangle {
+        let columns = Self::columns(screen_width);
+        let col = i % columns;
+        let row = i / columns;
+
+        rrect(
+            Self::left(screen_width, columns) + col as f32 * Self::TILE_STRIDE,
+            Self::TILE_PADDING + row as f32 * Self::TILE_STRIDE - self.scroll,
+            Self::TILE_SIDE,
+            Self::TILE_SIDE,
+        )
+    }
+
+    /// Returns the range of images whose tiles are at least partially on screen.
+    fn visible(&self, len: usize, screen_width: f32, screen_height: f32) -> (usize, usize) {
+        let columns = Self::columns(screen_width);
+        let first_row = (self.scroll / Self::TILE_STRIDE).max(0.) as usize;
+        let rows = (screen_height / Self::TILE_STRIDE).ceil() as usize + 1;
+
+        let start = (first_row * columns).min(len);
+        let end = ((first_row + rows) * columns).min(len);
+        (start, end)
+    }
+
+    fn max_scroll(len: usize, screen_width: f32, screen_height: f32) -> f32 {
+        let rows = len.div_ceil(Self::columns(screen_width));
+        let height = rows as f32 * Self::TILE_STRIDE + Self::TILE_PADDING;
+        (height - screen_height).max(0.)
+    }
+
+    /// Scrolls the grid just enough for the selected tile to be fully on screen.
+    fn scroll_to_selected(&mut self, screen_width: f32, screen_height: f32) {
+        let rect = self.tile_rect(self.selected, screen_width);
+        if rect.y < Self::TILE_PADDING {
+            self.scroll += rect.y - Self::TILE_PADDING;
+        } else if rect.y + rect.height > screen_height - Self::TILE_PADDING {
+            self.scroll += rect.y + rect.height - screen_height + Self::TILE_PADDING;
+        }
+    }
+
+    /// Requests the thumbnails around the visible tiles and unloads every other thumbnail.
+    fn update_thumbnails(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rl_thread: &RaylibThread,
+        images: &FoximgImages,
+    ) {
+        let screen_width = rl.get_screen_width().as_f32();
+        let screen_height = rl.get_screen_height().as_f32();
+        let columns = Self::columns(screen_width);
+        let (start, end) = self.visible(images.len(), screen_width, screen_height);
+        let start = start.saturating_sub(Self::PRELOAD_ROWS * columns);
+        let end = (end + Self::PRELOAD_ROWS * columns).min(images.len());
+        let paths = &images.paths()[start..end];
+
+        self.thumbnails.retain(|path| paths.iter().any(|p| p == path));
+        for (i, path) in paths.iter().enumerate() {
+            if !images.failed(start + i) {
+                self.thumbnails.request(path);
+            }
+        }
+        self.thumbnails.update(rl, rl_thread);
+    }
+}
+
+impl Foximg {
+    /// Shows or hides the grid view. The grid opens with the current image selected.
+    pub fn toggle_grid(&mut self) {
+        if self.grid.take().is_some() {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Closed grid view");
+            return;
+        }
+
+        if let Some(ref images) = self.images {
+            let mut grid = FoximgGrid::new(&self.rl, images.img_current() - 1);
+            let screen_width = self.rl.get_screen_width().as_f32();
+            let screen_height = self.rl.get_screen_height().as_f32();
+
+            grid.scroll_to_selected(screen_width, screen_height);
+            self.grid = Some(grid);
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Opened grid view");
+        }
+    }
+
+    /// Opens the grid view if `GridView` is pressed. Returns true if so.
+    pub fn grid_view(&mut self) -> bool {
+        if self.lock.is_none() && self.is_action_pressed(FoximgAction::GridView) {
+            self.toggle_grid();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves the selection of the grid with the same keybinds that pan the image.
+    fn grid_move_selection(&self, grid: &mut FoximgGrid, len: usize) -> bool {
+        let columns = FoximgGrid::columns(self.rl.get_screen_width().as_f32());
+        let selected = grid.selected;
+
+        if self.is_action_pressed_or_repeat(FoximgAction::PanLeft) {
+            grid.selected = grid.selected.saturating_sub(1);
+        } else if self.is_action_pressed_or_repeat(FoximgAction::PanRight) {
+            grid.selected = (grid.selected + 1).min(len - 1);
+        } else if self.is_action_pressed_or_repeat(FoximgAction::PanUp) {
+            grid.selected = grid.selected.saturating_sub(columns);
+        } else if self.is_action_pressed_or_repeat(FoximgAction::PanDown) {
+            grid.selected = (grid.selected + columns).min(len - 1);
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_HOME) {
+            grid.selected = 0;
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_END) {
+            grid.selected = len - 1;
+        }
+
+        selected != grid.selected
+    }
+
+    /// Closes the grid view and opens the selected image.
+    fn grid_open_selected(&mut self, images: &mut FoximgImages, selected: usize) {
+        images.set_current(selected);
+        images.update_window(self);
+        self.grid = None;
+        self.rl
+            .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Closed grid view");
+    }
+
+    /// Updates the grid view. This replaces the regular image controls while the grid is shown.
+    pub fn update_grid(&mut self) {
+        let Some(mut grid) = self.grid.take() else {
+            return;
+        };
+
+        self.images_with(|f, images| {
+            let screen_width = f.rl.get_screen_width().as_f32();
+            let screen_height = f.rl.get_screen_height().as_f32();
+            let len = images.len();
+            let (start, end) = grid.visible(len, screen_width, screen_height);
+
+            grid.selected = grid.selected.min(len - 1);
+            grid.hovered = (start..end).find(|&i| {
+                grid.tile_rect(i, screen_width)
+                    .check_collision_point_rec(f.mouse_pos)
+            });
+
+            let wheel = f.rl.get_mouse_wheel_move();
+            if wheel != 0. {
+                grid.scroll -= wheel * FoximgGrid::SCROLL_SPEED;
+            }
+
+            if f.grid_move_selection(&mut grid, len) {
+                grid.scroll_to_selected(screen_width, screen_height);
+            }
+
+            grid.scroll = grid
+                .scroll
+                .clamp(0., FoximgGrid::max_scroll(len, screen_width, screen_height));
+            grid.update_thumbnails(&mut f.rl, &f.rl_thread, images);
+            f.rl.set_mouse_cursor(if grid.hovered.is_some() {
+                MouseCursor::MOUSE_CURSOR_POINTING_HAND
+            } else {
+                MouseCursor::MOUSE_CURSOR_DEFAULT
+            });
+
+            let selected = grid.selected;
+            let hovered = grid.hovered;
+            f.grid = Some(grid);
+
+            if f.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                f.grid_open_selected(images, selected);
+            } else if f.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                // The first click selects an image, and clicking it again opens it.
+                match hovered {
+                    Some(hovered) if hovered == selected => f.grid_open_selected(images, hovered),
+                    Some(hovered) => f.grid.as_mut().unwrap().selected = hovered,
+                    None => (),
+                }
+            } else if f.is_action_pressed(FoximgAction::GridView)
+                || f.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE)
+            {
+                f.toggle_grid();
+            }
+        });
+    }
+}
+
+impl FoximgDraw<'_> {
+    fn draw_error_tile(&mut self, rect: Rectangle) {
+        let font_size = rect.height / 5.;
+        let font_spacing = resources::yudit_spacing(font_size);
+
+        let yudit = &self.resources.yudit;
+        let text_size = yudit.measure_text(":(", font_size, font_spacing);
+
+        self.d.draw_text_ex(
+            yudit,
+            ":(",
+            rvec2(
+                rect.x + rect.width / 2. - text_size.x / 2.,
+                rect.y + rect.height / 2. - text_size.y / 2.,
+            ),
+            font_size,
+            font_spacing,
+            self.style.accent,
+        );
+    }
+
+    /// Draws the thumbnail of the image at index `i` fitted inside `rect`, or an error tile if either
+    /// the image or its thumbnail failed to load. Nothing is drawn while the thumbnail is loading.
+    pub fn draw_thumbnail(
+        &mut self,
+        thumbnails: &FoximgThumbnails,
+        images: &FoximgImages,
+        i: usize,
+        rect: Rectangle,
+    ) {
+        match thumbnails.get(&images.paths()[i]) {
+            _ if images.failed(i) => self.draw_error_tile(rect),
+            Some(FoximgThumbnail::Failed) => self.draw_error_tile(rect),
+            Some(FoximgThumbnail::Loaded(texture)) => {
+                let width = texture.width().as_f32();
+                let height = texture.height().as_f32();
+                let scale = (rect.width / width).min(rect.height / height).min(1.);
+
+                self.d.draw_texture_pro(
+                    texture,
+                    rrect(0, 0, width, height),
+                    rrect(
+                        rect.x + (rect.width - width * scale) / 2.,
+                        rect.y + (rect.height - height * scale) / 2.,
+                        width * scale,
+                        height * scale,
+                    ),
+                    rvec2(0, 0),
+                    0.,
+                    Color::WHITE,
+                );
+            }
+            Some(FoximgThumbnail::Loading) | None => (),
+        }
+
+        if images.marked(i) {
+            self.draw_mark(rect);
+        }
+    }
+
+    fn draw_grid_tile(&mut self, grid: &FoximgGrid, images: &FoximgImages, i: usize) {
+        let screen_width = self.d.get_screen_width().as_f32();
+        let rect = grid.tile_rect(i, screen_width);
+
+        if grid.hovered == Some(i) || grid.selected == i {
+            self.d.draw_rectangle_rec(rect, self.style.accent.alpha(0.2));
+        }
+
+        self.draw_thumbnail(&grid.thumbnails, images, i, rect);
+
+        if grid.selected == i {
+            self.d
+                .draw_rectangle_lines_ex(rect, 2., self.style.accent.alpha(1.));
+        }
+    }
+
+    pub fn draw_grid(&mut self, images: &FoximgImages) {
+        let Some(grid) = self.grid else {
+            return;
+        };
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let screen_height = self.d.get_screen_height().as_f32();
+        let (start, end) = grid.visible(images.len(), screen_width, screen_height);
+
+        for i in start..end {
+            self.draw_grid_tile(grid, images, i);
+        }
+
+        self.draw_fullscreen_title();
+    }
+}
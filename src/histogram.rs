@@ -0,0 +1,441 @@
+//! Defines the histogram of the red, green, blue and luma values of an image. Histograms are made
+//! on a background thread from the image decoded at its own bit depth, or from the frame that's
+//! shown for animations.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use image::{DynamicImage, ImageReader, RgbaImage};
+use raylib::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    Foximg, FoximgDraw,
+    config::{FoximgConfig, FoximgHistogramScale, FoximgHistogramSettings},
+    keybinds::FoximgAction,
+    resources,
+};
+
+/// How many bins each channel is counted into.
+pub const HISTOGRAM_BINS: usize = 256;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum FoximgHistogramDepth {
+    #[serde(rename = "8-bit")]
+    Bits8,
+    #[serde(rename = "16-bit")]
+    Bits16,
+    #[serde(rename = "float")]
+    Float,
+}
+
+impl FoximgHistogramDepth {
+    /// Returns the value of a channel that's fully lit.
+    fn white(self) -> f64 {
+        match self {
+            FoximgHistogramDepth::Bits8 => 255.,
+            FoximgHistogramDepth::Bits16 => 65535.,
+            FoximgHistogramDepth::Float => 1.,
+        }
+    }
+
+    /// Formats `value` with as many decimals as make sense for the depth, plus `extra`.
+    fn format(self, value: f64, extra: usize) -> String {
+        let decimals = match self {
+            FoximgHistogramDepth::Float => 4,
+            _ => 0,
+        } + extra;
+        format!("{value:.decimals$}")
+    }
+}
+
+impl fmt::Display for FoximgHistogramDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FoximgHistogramDepth::Bits8 => "8-bit",
+            FoximgHistogramDepth::Bits16 => "16-bit",
+            FoximgHistogramDepth::Float => "float",
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct FoximgHistogramChannel {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// How many pixels fall into each bin, from the low to the high end of the histogram.
+    pub bins: Vec<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct FoximgHistogram {
+    pub depth: FoximgHistogramDepth,
+    /// The value the first bin starts at. Only below 0 for float images with negative values.
+    pub low: f64,
+    /// The value the last bin ends at. Only above white for float images brighter than white.
+    pub high: f64,
+    pub red: FoximgHistogramChannel,
+    pub green: FoximgHistogramChannel,
+    pub blue: FoximgHistogramChannel,
+    pub luma: FoximgHistogramChannel,
+}
+
+impl FoximgHistogram {
+    pub fn new(image: &DynamicImage) -> Self {
+        let color = image.color();
+        match color.bytes_per_pixel() / color.channel_count() {
+            1 => Self::from_samples(image.to_rgb8().as_raw(), FoximgHistogramDepth::Bits8),
+            2 => Self::from_samples(image.to_rgb16().as_raw(), FoximgHistogramDepth::Bits16),
+            _ => Self::from_samples(image.to_rgb32f().as_raw(), FoximgHistogramDepth::Float),
+        }
+    }
+
+    /// Decodes the image at `path` and makes a histogram of it. Animations use their first frame.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+        Ok(Self::new(&image))
+    }
+
+    /// Makes a histogram out of RGB samples. Pixels with a channel that isn't finite are skipped.
+    fn from_samples<T: Copy + Into<f64>>(samples: &[T], depth: FoximgHistogramDepth) -> Self {
+        let pixels = || {
+            samples
+                .chunks_exact(3)
+                .map(|p| {
+                    let [r, g, b]: [f64; 3] = [p[0].into(), p[1].into(), p[2].into()];
+                    [r, g, b, 0.299 * r + 0.587 * g + 0.114 * b]
+                })
+                .filter(|p| p.iter().all(|v| v.is_finite()))
+        };
+
+        let mut min = [f64::INFINITY; 4];
+        let mut max = [f64::NEG_INFINITY; 4];
+        let mut sum = [0.; 4];
+        let mut count = 0u64;
+        for p in pixels() {
+            for (c, v) in p.into_iter().enumerate() {
+                min[c] = min[c].min(v);
+                max[c] = max[c].max(v);
+                sum[c] += v;
+            }
+            count += 1;
+        }
+
+        let low = min.iter().copied().fold(0., f64::min);
+        let high = max.iter().copied().fold(depth.white(), f64::max);
+        let bin_width = (high - low) / HISTOGRAM_BINS as f64;
+        let mut bins = [[0u64; HISTOGRAM_BINS]; 4];
+        for p in pixels() {
+            for (c, v) in p.into_iter().enumerate() {
+                let i = ((v - low) / bin_width) as usize;
+                bins[c][i.min(HISTOGRAM_BINS - 1)] += 1;
+            }
+        }
+
+        let channel = |c: usize| FoximgHistogramChannel {
+            min: if count == 0 { 0. } else { min[c] },
+            max: if count == 0 { 0. } else { max[c] },
+            mean: sum[c] / count.max(1) as f64,
+            bins: bins[c].to_vec(),
+        };
+
+        Self {
+            depth,
+            low,
+            high,
+            red: channel(0),
+            green: channel(1),
+            blue: channel(2),
+            luma: channel(3),
+        }
+    }
+}
+
+/// What a histogram is made from: an image, or the frame of an animation that's shown.
+#[derive(PartialEq)]
+struct FoximgHistogramKey {
+    path: PathBuf,
+    frame: Option<usize>,
+}
+
+enum FoximgHistogramSource {
+    Path(PathBuf),
+    Frame(RgbaImage),
+}
+
+type FoximgHistogramResult = anyhow::Result<FoximgHistogram>;
+
+/// The histogram panel. Keeps the histogram of the current image, or `None` if making it failed,
+/// while the next one is being made.
+#[derive(Default)]
+pub struct FoximgHistogramPanel {
+    shown: Option<(FoximgHistogramKey, Option<FoximgHistogram>)>,
+    pending: Option<(FoximgHistogramKey, Receiver<FoximgHistogramResult>)>,
+}
+
+impl FoximgHistogramPanel {
+    /// Makes a histogram out of `source` on a new thread. Returns where to receive it from.
+    fn spawn(
+        rl: &RaylibHandle,
+        source: FoximgHistogramSource,
+    ) -> Option<Receiver<FoximgHistogramResult>> {
+        let (sender, results) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name("foximg-histogram".to_string())
+            .spawn(move || {
+                let histogram = match source {
+                    FoximgHistogramSource::Path(path) => FoximgHistogram::open(&path),
+                    FoximgHistogramSource::Frame(frame) => {
+                        Ok(FoximgHistogram::new(&DynamicImage::ImageRgba8(frame)))
+                    }
+                };
+                // The panel might've been hidden in the meantime.
+                let _ = sender.send(histogram);
+            });
+
+        match spawned {
+            Ok(_) => Some(results),
+            Err(e) => {
+                rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    "FOXIMG: Failed to spawn histogram thread:",
+                );
+                rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                None
+            }
+        }
+    }
+}
+
+impl Foximg {
+    /// Shows or hides the histogram panel. Does nothing if the UI is locked.
+    pub fn toggle_histogram(&mut self) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        if self.histogram.take().is_some() {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Hid histogram");
+        } else {
+            self.histogram = Some(FoximgHistogramPanel::default());
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Showed histogram");
+        }
+    }
+
+    /// Changes the histogram settings and saves them.
+    pub fn set_histogram_settings(&mut self, f: impl FnOnce(&mut FoximgHistogramSettings)) {
+        f(&mut self.histogram_settings);
+        self.histogram_settings.to_file(&self.rl);
+    }
+
+    /// Toggles the histogram panel if `Histogram` is pressed, or switches between a linear and log
+    /// scale if `HistogramScale` is pressed while it's shown. Returns true if so.
+    pub fn histogram_img(&mut self) -> bool {
+        let scale = self.is_action_pressed(FoximgAction::HistogramScale);
+        if self.images.is_none() || !(scale || self.is_action_pressed(FoximgAction::Histogram)) {
+            return false;
+        }
+
+        if self.histogram.is_some() && scale {
+            self.set_histogram_settings(|settings| {
+                settings.scale = match settings.scale {
+                    FoximgHistogramScale::Linear => FoximgHistogramScale::Log,
+                    FoximgHistogramScale::Log => FoximgHistogramScale::Linear,
+                }
+            });
+            self.toast(match self.histogram_settings.scale {
+                FoximgHistogramScale::Linear => "Linear histogram",
+                FoximgHistogramScale::Log => "Log histogram",
+            });
+        } else {
+            self.toggle_histogram();
+        }
+        true
+    }
+
+    /// Updates the histogram panel. Picks up the histogram once it's made, and starts making a new
+    /// one once the current image or the frame of an animation changes.
+    pub fn update_histogram(&mut self) {
+        let Some(mut panel) = self.histogram.take() else {
+            return;
+        };
+
+        if let Some((key, results)) = panel.pending.take() {
+            match results.try_recv() {
+                Ok(Ok(histogram)) => panel.shown = Some((key, Some(histogram))),
+                Ok(Err(e)) => {
+                    self.rl.trace_log(
+                        TraceLogLevel::LOG_ERROR,
+                        &format!(
+                            "FOXIMG: Failed to make histogram of {}:",
+                            key.path.display()
+                        ),
+                    );
+                    self.rl
+                        .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                    panel.shown = Some((key, None));
+                }
+                Err(TryRecvError::Empty) => panel.pending = Some((key, results)),
+                Err(TryRecvError::Disconnected) => panel.shown = Some((key, None)),
+            }
+        }
+
+        if panel.pending.is_none() {
+            self.request_histogram(&mut panel);
+        }
+        self.histogram = Some(panel);
+    }
+
+    /// Starts making a histogram of the current image if `panel` doesn't have one of it yet.
+    fn request_histogram(&mut self, panel: &mut FoximgHistogramPanel) {
+        let Some(ref mut images) = self.images else {
+            return;
+        };
+
+        let path = images.img_path().to_path_buf();
+        let Some(img) = images.img_get(&mut self.rl, &self.rl_thread) else {
+            return;
+        };
+
+        let img = img.borrow();
+        let frame = img.current_frame();
+        let key = FoximgHistogramKey {
+            path,
+            frame: frame.map(|(i, _)| i),
+        };
+        if panel.shown.as_ref().is_some_and(|(shown, _)| *shown == key) {
+            return;
+        }
+
+        let source = match frame {
+            Some((_, buffer)) => FoximgHistogramSource::Frame(buffer.clone()),
+            None => FoximgHistogramSource::Path(key.path.clone()),
+        };
+        match FoximgHistogramPanel::spawn(&self.rl, source) {
+            Some(results) => panel.pending = Some((key, results)),
+            None => panel.shown = Some((key, None)),
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the histogram panel in the bottom right corner of the window, if it's shown.
+    pub fn draw_histogram(&mut self) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const PADDING: f32 = 6.;
+        const MARGIN: f32 = 10.;
+        const LINE: f32 = FONT_SIZE + PADDING;
+        const GRAPH_WIDTH: f32 = HISTOGRAM_BINS as f32;
+        const GRAPH_HEIGHT: f32 = 100.;
+
+        let Some(panel) = self.histogram else {
+            return;
+        };
+
+        let histogram = match panel.shown {
+            Some((_, Some(ref histogram))) => Some(histogram),
+            Some((_, None)) => None,
+            None if panel.pending.is_some() => None,
+            None => return,
+        };
+        let settings = self.histogram_settings;
+        let mut channels = Vec::with_capacity(4);
+        let mut lines = Vec::with_capacity(5);
+        match histogram {
+            Some(histogram) => {
+                channels.extend([
+                    ("R", &histogram.red, Color::RED),
+                    ("G", &histogram.green, Color::GREEN),
+                    ("B", &histogram.blue, Color::BLUE),
+                ]);
+                if settings.luma {
+                    channels.push(("L", &histogram.luma, *self.style.accent));
+                }
+
+                let depth = histogram.depth;
+                lines.push(match settings.scale {
+                    FoximgHistogramScale::Linear => format!("{depth}, linear"),
+                    FoximgHistogramScale::Log => format!("{depth}, log"),
+                });
+                for &(name, channel, _) in &channels {
+                    lines.push(format!(
+                        "{name}: min {}, max {}, mean {}",
+                        depth.format(channel.min, 0),
+                        depth.format(channel.max, 0),
+                        depth.format(channel.mean, 1),
+                    ));
+                }
+            }
+            None if panel.pending.is_some() => lines.push("Making histogram...".to_string()),
+            None => lines.push("Couldn't make a histogram".to_string()),
+        }
+
+        let yudit = &self.resources.yudit;
+        let width = lines
+            .iter()
+            .map(|line| yudit.measure_text(line, FONT_SIZE, FONT_SPACING).x)
+            .fold(GRAPH_WIDTH, f32::max)
+            + PADDING * 2.;
+        let height = GRAPH_HEIGHT + LINE * lines.len() as f32 + PADDING * 2.;
+        let rect = rrect(
+            self.d.get_screen_width().as_f32() - width - MARGIN,
+            self.d.get_screen_height().as_f32()
+                - self.filmstrip_height()
+                - resources::SYMBOL_SIDE
+                - resources::SYMBOL_PADDING * 2.
+                - height,
+            width,
+            height,
+        );
+        self.d.draw_rectangle_rec(rect, self.style.bg.alpha(0.75));
+
+        // Every curve shares the same scale so that they can be compared with each other.
+        let peak = channels
+            .iter()
+            .flat_map(|(_, channel, _)| channel.bins.iter().copied())
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+        let scale = |count: u64| match settings.scale {
+            FoximgHistogramScale::Linear => count as f32 / peak,
+            FoximgHistogramScale::Log => (count as f32).ln_1p() / peak.ln_1p(),
+        };
+        let graph_x = rect.x + PADDING;
+        let graph_bottom = rect.y + PADDING + GRAPH_HEIGHT;
+        for &(_, channel, color) in &channels {
+            let point = |i: usize| {
+                rvec2(
+                    graph_x + i as f32 * GRAPH_WIDTH / (HISTOGRAM_BINS - 1) as f32,
+                    graph_bottom - scale(channel.bins[i]) * GRAPH_HEIGHT,
+                )
+            };
+            for i in 1..HISTOGRAM_BINS {
+                self.d
+                    .draw_line_ex(point(i - 1), point(i), 1., color.alpha(0.8));
+            }
+        }
+
+        let mut y = graph_bottom + PADDING;
+        for line in lines {
+            self.d.draw_text_ex(
+                yudit,
+                &line,
+                rvec2(rect.x + PADDING, y),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent,
+            );
+            y += LINE;
+        }
+    }
+}
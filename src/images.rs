@@ -1,5 +1,6 @@
 use std::{
     cell::{RefCell, RefMut},
+    collections::HashMap,
     ffi::c_void,
     fs::ReadDir,
     mem::ManuallyDrop,
@@ -9,25 +10,36 @@ use std::{
 };
 
 use circular_buffer::CircularBuffer;
-use foximg_image_loader::FoximgImageLoader;
-use image::{EncodableLayout, Frame, Frames, ImageResult, foximg::AnimationLoops};
+use image::{EncodableLayout, Frame, Frames, ImageResult, RgbaImage, foximg::AnimationLoops};
 use raylib::prelude::*;
 
 use crate::{
     Foximg,
-    config::FoximgStyle,
+    adjust::FoximgAdjustments,
+    config::{FoximgSettings, FoximgStyle},
+    filter::FoximgFilter,
     resources::{self, FoximgResources},
+    xmp::FoximgXmp,
 };
 
 mod foximg_image_loader;
+mod history;
+mod metadata;
 
-pub use foximg_image_loader::{new_resource, set_window_icon};
+pub use foximg_image_loader::{FoximgImageLoader, new_resource, set_window_icon};
+pub use history::{FoximgEdit, FoximgHistory};
+
+/// The most images that can be kept loaded at once.
+pub const MAX_CACHE_SIZE: usize = 64;
 
 struct FoximgImageAnimated {
     frames: Vec<Frame>,
     current: usize,
     current_delay: f32,
     loops: Option<AnimationLoops>,
+    /// Whether the animation played through all of its frames at least once.
+    looped: bool,
+    paused: bool,
 }
 
 impl FoximgImageAnimated {
@@ -37,6 +49,8 @@ impl FoximgImageAnimated {
             loops: Some(loops),
             current: 0,
             current_delay: 0.,
+            looped: false,
+            paused: false,
         })
     }
 
@@ -54,6 +68,10 @@ impl FoximgImageAnimated {
     /// has finished and there's no more frames to update. The `FoximgImageAnimated` object can be
     /// dropped after this.
     pub fn update_frame(&mut self, rl: &RaylibHandle) -> Option<bool> {
+        if self.paused {
+            return Some(false);
+        }
+
         let loops = &mut self.loops?;
         self.current_delay += rl.get_frame_time() * 1000.;
 
@@ -78,6 +96,8 @@ impl FoximgImageAnimated {
             return Some(true);
         }
 
+        self.looped = true;
+
         if let AnimationLoops::Finite(i) = loops {
             let new_i = NonZeroU32::new(i.get() - 1);
             match new_i {
@@ -97,6 +117,11 @@ impl FoximgImageAnimated {
         }
     }
 
+    /// Returns the index of the frame `by` frames away from the current one, wrapping around.
+    fn frame_index(&self, by: isize) -> usize {
+        (self.current as isize + by).rem_euclid(self.frames.len() as isize) as usize
+    }
+
     /// Returns a non-owning [`Image`] shallow copy of the current frame's image buffer.
     pub fn get_frame(&self) -> ManuallyDrop<Image> {
         let texture = self.frames[self.current].buffer();
@@ -121,22 +146,23 @@ pub struct FoximgImage {
     rotation: f32,
     width_mult: i32,
     height_mult: i32,
+    adjustments: FoximgAdjustments,
+    history: FoximgHistory,
 }
 
 impl FoximgImage {
+    /// Sets how the image is sampled when it's drawn bigger or smaller than it is.
+    pub fn set_filter(&self, rl_thread: &RaylibThread, filter: TextureFilter) {
+        self.texture.set_texture_filter(rl_thread, filter);
+    }
+
     /// Update the image. This will do nothing for static images, but update the frames of an animated
     /// image when appropriate.
     pub fn update_texture(&mut self, rl: &RaylibHandle) {
         if let Some(ref mut animation) = self.animation {
             let new_state = animation.update_frame(rl);
             if new_state == Some(true) {
-                let new_image = animation.get_frame();
-                // I don't want to bother with turning new_image.data into a validated u8 slice, only
-                // for Texture::update_texture to validate it once again. So I just use the unsafe
-                // FFI.
-                unsafe {
-                    ffi::UpdateTexture(*self.texture, new_image.data);
-                }
+                Self::upload_frame(&self.texture, animation);
             } else if new_state.is_none() {
                 self.animation.take();
                 rl.trace_log(TraceLogLevel::LOG_INFO, "FOXIMG: Animation stopped");
@@ -144,6 +170,76 @@ impl FoximgImage {
         }
     }
 
+    fn upload_frame(texture: &Texture2D, animation: &FoximgImageAnimated) {
+        let new_image = animation.get_frame();
+        // I don't want to bother with turning new_image.data into a validated u8 slice, only for
+        // Texture::update_texture to validate it once again. So I just use the unsafe FFI.
+        unsafe {
+            ffi::UpdateTexture(**texture, new_image.data);
+        }
+    }
+
+    /// Pauses or resumes the animation. Returns whether it's paused now, or `None` if the image
+    /// isn't animated.
+    pub fn toggle_paused(&mut self) -> Option<bool> {
+        let animation = self.animation.as_mut()?;
+        animation.paused = !animation.paused;
+        animation.current_delay = 0.;
+        Some(animation.paused)
+    }
+
+    /// Returns whether the image is an animation that's paused.
+    pub fn paused(&self) -> bool {
+        self.animation
+            .as_ref()
+            .is_some_and(|animation| animation.paused)
+    }
+
+    /// Shows the frame `by` frames away from the current one while the animation is paused.
+    pub fn step_frame(&mut self, by: isize) {
+        let Some(ref mut animation) = self.animation else {
+            return;
+        };
+
+        if animation.paused {
+            animation.current = animation.frame_index(by);
+            Self::upload_frame(&self.texture, animation);
+        }
+    }
+
+    /// Returns the index and the RGBA bytes of the frame `by` frames away from the current one
+    /// while the animation is paused.
+    pub fn paused_frame(&self, by: isize) -> Option<(usize, &[u8])> {
+        let animation = self
+            .animation
+            .as_ref()
+            .filter(|animation| animation.paused)?;
+        let i = animation.frame_index(by);
+        Some((i, animation.frames[i].buffer().as_bytes()))
+    }
+
+    /// Returns the index and the buffer of the frame that's shown, or `None` if the image isn't
+    /// animated.
+    pub fn current_frame(&self) -> Option<(usize, &RgbaImage)> {
+        let animation = self.animation.as_ref()?;
+        Some((animation.current, animation.frames[animation.current].buffer()))
+    }
+
+    /// Returns whether the image is done animating for the first time since `restart_looped` was
+    /// called. Always true for static images.
+    pub fn looped(&self) -> bool {
+        self.animation
+            .as_ref()
+            .is_none_or(|animation| animation.looped)
+    }
+
+    /// Makes `looped` return false again until the animation plays through all of its frames.
+    pub fn restart_looped(&mut self) {
+        if let Some(ref mut animation) = self.animation {
+            animation.looped = false;
+        }
+    }
+
     pub fn width(&self) -> i32 {
         self.texture.width()
     }
@@ -156,13 +252,17 @@ impl FoximgImage {
         self.rotation
     }
 
-    pub fn draw_center_scaled(
+    /// Returns where the image is drawn and the point it's rotated around, relative to its top left
+    /// corner. The image is centered on the screen, unless `align_top_left` is set. Then its top left
+    /// corner is lined up with where the top left corner of `like` would be.
+    fn placement(
         &self,
-        d: &mut RaylibDrawHandle,
+        like: &FoximgImage,
         screen_width: f32,
         screen_height: f32,
         scale: f32,
-    ) {
+        align_top_left: bool,
+    ) -> (Vector2, Vector2) {
         let pos_offset = if let Some(ref animation) = self.animation {
             rvec2(
                 animation.frames[animation.current].left(),
@@ -172,26 +272,153 @@ impl FoximgImage {
             rvec2(0, 0)
         };
 
+        let pos = rvec2(screen_width / 2., screen_height / 2.) + pos_offset;
+        let origin = if align_top_left {
+            rvec2(like.width() / 2, like.height() / 2) * scale
+        } else {
+            rvec2(self.width() / 2, self.height() / 2) * scale
+        };
+
+        (pos, origin)
+    }
+
+    pub fn draw_center_scaled(
+        &self,
+        d: &mut RaylibDrawHandle,
+        screen_width: f32,
+        screen_height: f32,
+        scale: f32,
+        tint: Color,
+    ) {
+        self.draw_like(d, self, screen_width, screen_height, scale, false, tint);
+    }
+
+    /// Draws the image like `draw_center_scaled`, through `shader` if there's one.
+    pub fn draw_center_shaded(
+        &self,
+        d: &mut RaylibDrawHandle,
+        shader: Option<&RefCell<Shader>>,
+        screen_width: f32,
+        screen_height: f32,
+        scale: f32,
+        tint: Color,
+    ) {
+        match shader {
+            Some(shader) => {
+                let mut shader = shader.borrow_mut();
+                let mut s = d.begin_shader_mode(&mut shader);
+                self.draw_center_scaled(&mut s, screen_width, screen_height, scale, tint);
+            }
+            None => self.draw_center_scaled(d, screen_width, screen_height, scale, tint),
+        }
+    }
+
+    /// Draws the image like `draw_center_scaled`, but rotated and flipped like `like` is. See
+    /// `placement` for `align_top_left`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_like(
+        &self,
+        d: &mut RaylibDrawHandle,
+        like: &FoximgImage,
+        screen_width: f32,
+        screen_height: f32,
+        scale: f32,
+        align_top_left: bool,
+        tint: Color,
+    ) {
+        let (pos, origin) =
+            self.placement(like, screen_width, screen_height, scale, align_top_left);
+
         d.draw_texture_pro(
             &self.texture,
             rrect(
                 0,
                 0,
-                self.width() * self.width_mult,
-                self.height() * self.height_mult,
+                self.width() * like.width_mult,
+                self.height() * like.height_mult,
             ),
             rrect(
-                screen_width / 2. + pos_offset.x,
-                screen_height / 2. + pos_offset.y,
+                pos.x,
+                pos.y,
                 self.width().as_f32() * scale,
                 self.height().as_f32() * scale,
             ),
-            rvec2(self.width() / 2, self.height() / 2) * scale,
-            self.rotation,
-            Color::WHITE,
+            origin,
+            like.rotation,
+            tint,
         );
     }
 
+    /// Returns the pixel of the image under `point` when it's drawn with `draw_like`, or `None` if
+    /// `point` is outside of the image.
+    pub fn pixel_at(
+        &self,
+        like: &FoximgImage,
+        screen_width: f32,
+        screen_height: f32,
+        scale: f32,
+        align_top_left: bool,
+        point: Vector2,
+    ) -> Option<(i32, i32)> {
+        let (pos, origin) =
+            self.placement(like, screen_width, screen_height, scale, align_top_left);
+
+        // Undo the rotation around `pos`, then the scale.
+        let v = point - pos;
+        let (sin, cos) = (-like.rotation).to_radians().sin_cos();
+        let unrotated = rvec2(v.x * cos - v.y * sin, v.x * sin + v.y * cos) + origin;
+        let mut x = (unrotated.x / scale).floor() as i32;
+        let mut y = (unrotated.y / scale).floor() as i32;
+
+        if x < 0 || y < 0 || x >= self.width() || y >= self.height() {
+            return None;
+        }
+        if like.width_mult == -1 {
+            x = self.width() - 1 - x;
+        }
+        if like.height_mult == -1 {
+            y = self.height() - 1 - y;
+        }
+
+        Some((x, y))
+    }
+
+    /// Creates a static image out of RGBA bytes, such as a heatmap made by foximg itself.
+    pub fn from_rgba(
+        rl: &mut RaylibHandle,
+        rl_thread: &RaylibThread,
+        rgba: &[u8],
+        width: i32,
+        height: i32,
+    ) -> anyhow::Result<Self> {
+        // Like `FoximgImageAnimated::get_frame`, the image only borrows `rgba`.
+        let image = ManuallyDrop::new(unsafe {
+            Image::from_raw(ffi::Image {
+                data: rgba.as_ptr() as *mut c_void,
+                width,
+                height,
+                mipmaps: 1,
+                format: ffi::PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+            })
+        });
+
+        Ok(Self {
+            texture: rl.load_texture_from_image(rl_thread, &image)?,
+            animation: None,
+            rotation: 0.,
+            width_mult: 1,
+            height_mult: 1,
+            adjustments: FoximgAdjustments::default(),
+            history: FoximgHistory::default(),
+        })
+    }
+
+    /// Reads the pixels of the current frame back from the GPU, row by row.
+    pub fn load_colors(&self) -> Option<Vec<Color>> {
+        let image = self.texture.load_image().ok()?;
+        Some(image.get_image_data().to_vec())
+    }
+
     pub fn draw_manipulation_info(
         &self,
         d: &mut impl RaylibDraw,
@@ -199,6 +426,7 @@ impl FoximgImage {
         style: &FoximgStyle,
         screen_width: f32,
         screen_height: f32,
+        channel_label: Option<&str>,
     ) {
         let flipped_horizontal = self.width_mult == -1;
         let flipped_vertical = self.height_mult == -1;
@@ -230,8 +458,31 @@ impl FoximgImage {
             );
         }
 
-        if self.rotation != 0. {
-            let text = self.rotation.to_string();
+        if let Some(label) = channel_label {
+            let flips = usize::from(flipped_horizontal) + usize::from(flipped_vertical);
+            d.draw_text_ex(
+                &resources.yudit,
+                label,
+                rvec2(
+                    resources::SYMBOL_PADDING
+                        + (resources::SYMBOL_SIDE + resources::SYMBOL_PADDING) * flips as f32,
+                    screen_height - resources::SYMBOL_SIDE - resources::FLIP_OFFSET,
+                ),
+                resources::SYMBOL_SIDE,
+                1.,
+                accent,
+            );
+        }
+
+        // Unsaved edits are marked with an asterisk before the rotation.
+        let rotation = (self.rotation != 0.).then(|| self.rotation.to_string());
+        let text = match (self.has_unsaved_edits(), rotation) {
+            (true, Some(rotation)) => Some(format!("* {rotation}")),
+            (true, None) => Some("*".to_string()),
+            (false, rotation) => rotation,
+        };
+
+        if let Some(text) = text {
             let yudit = &resources.yudit;
             let text_width = yudit.measure_text(&text, resources::SYMBOL_SIDE, 1.).x;
 
@@ -256,8 +507,15 @@ pub struct FoximgImages {
     paths: Vec<PathBuf>,
     images_loader: Vec<FoximgImageLoader>,
     images_failed: Vec<bool>,
+    images_marked: Vec<bool>,
+    images_xmp: Vec<Option<FoximgXmp>>,
     current: usize,
-    current_images: CircularBuffer<64, Rc<RefCell<FoximgImage>>>,
+    current_images: CircularBuffer<MAX_CACHE_SIZE, Rc<RefCell<FoximgImage>>>,
+    /// Images with unsaved edits that fell out of `current_images`. They're kept loaded until
+    /// their edits are saved or discarded, so that they aren't lost.
+    edited_images: Vec<Rc<RefCell<FoximgImage>>>,
+    cache_size: usize,
+    filter: TextureFilter,
 }
 
 impl FoximgImages {
@@ -265,6 +523,7 @@ impl FoximgImages {
         paths: Vec<PathBuf>,
         images_loader: Vec<FoximgImageLoader>,
         current: usize,
+        settings: &FoximgSettings,
     ) -> Self {
         let mut images = Vec::with_capacity(paths.len());
         (0..paths.len()).for_each(|_| images.push(Weak::new()));
@@ -273,12 +532,44 @@ impl FoximgImages {
             images,
             images_loader,
             images_failed: vec![false; paths.len()],
+            images_marked: vec![false; paths.len()],
+            images_xmp: vec![None; paths.len()],
             current_images: CircularBuffer::new(),
+            edited_images: Vec::new(),
+            cache_size: settings.cache_size.clamp(1, MAX_CACHE_SIZE),
+            filter: settings.filtering.texture_filter(),
             paths,
             current,
         }
     }
 
+    /// Applies the cache size and filtering from `settings`. Images past the new cache size are
+    /// unloaded unless they're still used somewhere else.
+    pub fn apply_settings(&mut self, settings: &FoximgSettings, rl_thread: &RaylibThread) {
+        self.cache_size = settings.cache_size.clamp(1, MAX_CACHE_SIZE);
+        self.filter = settings.filtering.texture_filter();
+
+        self.shrink_cache(self.cache_size);
+        for image in self.images.iter().filter_map(Weak::upgrade) {
+            image.borrow().set_filter(rl_thread, self.filter);
+        }
+    }
+
+    /// Drops the oldest images from the cache until at most `len` are left. Images with unsaved
+    /// edits are kept loaded in `edited_images` instead.
+    fn shrink_cache(&mut self, len: usize) {
+        self.edited_images
+            .retain(|image| image.borrow().has_unsaved_edits());
+        while self.current_images.len() > len {
+            let Some(image) = self.current_images.pop_front() else {
+                break;
+            };
+            if image.borrow().has_unsaved_edits() {
+                self.edited_images.push(image);
+            }
+        }
+    }
+
     pub fn img_path(&self) -> &Path {
         &self.paths[self.current]
     }
@@ -288,27 +579,170 @@ impl FoximgImages {
         self.images_failed[self.current]
     }
 
+    /// Returns the paths of every image in the gallery.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Returns whether the image at index `i` failed to load.
+    pub fn failed(&self, i: usize) -> bool {
+        self.images_failed[i]
+    }
+
+    /// Returns whether the image at index `i` is marked.
+    pub fn marked(&self, i: usize) -> bool {
+        self.images_marked[i]
+    }
+
+    /// Marks the image at index `i`, or unmarks it if it's already marked.
+    pub fn toggle_marked(&mut self, i: usize) {
+        self.images_marked[i] = !self.images_marked[i];
+    }
+
+    /// Returns the indices of every marked image in order.
+    pub fn marked_indices(&self) -> Vec<usize> {
+        (0..self.len()).filter(|&i| self.images_marked[i]).collect()
+    }
+
+    /// Returns the rating, label and tags of the image at index `i`, reading its sidecar the first
+    /// time. Images whose sidecar can't be read have none.
+    pub fn xmp(&mut self, i: usize) -> &FoximgXmp {
+        self.images_xmp[i].get_or_insert_with(|| FoximgXmp::read(&self.paths[i]).unwrap_or_default())
+    }
+
+    pub fn set_xmp(&mut self, i: usize, xmp: FoximgXmp) {
+        self.images_xmp[i] = Some(xmp);
+    }
+
+    /// Removes the image at index `i` from the gallery. Returns its path and loader, so that it can
+    /// be put back with `insert`. The current image stays the same unless it's the one removed, in
+    /// which case the image after it becomes the current one. The gallery can end up empty, and it
+    /// must not be used if so.
+    pub fn remove(&mut self, i: usize) -> (PathBuf, FoximgImageLoader) {
+        self.images.remove(i);
+        self.images_failed.remove(i);
+        self.images_marked.remove(i);
+        self.images_xmp.remove(i);
+        let path = self.paths.remove(i);
+        let loader = self.images_loader.remove(i);
+
+        if self.current > i || self.current == self.paths.len() {
+            self.current = self.current.saturating_sub(1);
+        }
+
+        (path, loader)
+    }
+
+    /// Changes the path of the image at index `i`, for when it gets renamed. The image keeps its
+    /// place in the gallery.
+    pub fn set_path(&mut self, i: usize, path: PathBuf) {
+        self.paths[i] = path;
+        self.images_xmp[i] = None;
+    }
+
+    /// Returns the loader of the image at index `i`.
+    pub fn loader(&self, i: usize) -> FoximgImageLoader {
+        self.images_loader[i]
+    }
+
+    /// Inserts an image into the gallery at index `i`, or at the end if `i` is out of bounds.
+    /// Returns the index it was inserted at. The current image stays the same.
+    pub fn insert(&mut self, i: usize, path: PathBuf, loader: FoximgImageLoader) -> usize {
+        let i = i.min(self.paths.len());
+        self.images.insert(i, Weak::new());
+        self.images_failed.insert(i, false);
+        self.images_marked.insert(i, false);
+        self.images_xmp.insert(i, None);
+        self.paths.insert(i, path);
+        self.images_loader.insert(i, loader);
+
+        if self.current >= i && self.paths.len() > 1 {
+            self.current += 1;
+        }
+
+        i
+    }
+
+    /// Narrows or widens the gallery down to the images in `images`, which must be sorted by their
+    /// paths. Images that stay in the gallery keep everything about them, such as being marked or
+    /// their unsaved edits. The current image stays the same if it's still in the gallery, otherwise
+    /// the closest one to it alphabetically becomes the current one. `images` must not be empty.
+    pub fn refilter(&mut self, images: Vec<(PathBuf, FoximgImageLoader)>) {
+        let previous: HashMap<_, _> = self
+            .paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (path.clone(), i))
+            .collect();
+        let current = self.paths[self.current].clone();
+
+        let len = images.len();
+        let mut refiltered = Vec::with_capacity(len);
+        let mut images_failed = Vec::with_capacity(len);
+        let mut images_marked = Vec::with_capacity(len);
+        let mut images_xmp = Vec::with_capacity(len);
+        for (path, _) in &images {
+            match previous.get(path) {
+                Some(&i) => {
+                    refiltered.push(self.images[i].clone());
+                    images_failed.push(self.images_failed[i]);
+                    images_marked.push(self.images_marked[i]);
+                    images_xmp.push(self.images_xmp[i].take());
+                }
+                None => {
+                    refiltered.push(Weak::new());
+                    images_failed.push(false);
+                    images_marked.push(false);
+                    images_xmp.push(None);
+                }
+            }
+        }
+
+        self.images = refiltered;
+        self.images_failed = images_failed;
+        self.images_marked = images_marked;
+        self.images_xmp = images_xmp;
+        (self.paths, self.images_loader) = images.into_iter().unzip();
+        self.current = self
+            .paths
+            .binary_search(&current)
+            .unwrap_or_else(|i| i.min(len.saturating_sub(1)));
+    }
+
     pub fn img_get(
         &mut self,
         rl: &mut RaylibHandle,
         rl_thread: &RaylibThread,
     ) -> Option<Rc<RefCell<FoximgImage>>> {
-        if self.img_failed() {
+        self.get(self.current, rl, rl_thread)
+    }
+
+    /// Returns the image at index `i`, loading it if it's not loaded yet. Returns `None` if it
+    /// failed to load.
+    pub fn get(
+        &mut self,
+        i: usize,
+        rl: &mut RaylibHandle,
+        rl_thread: &RaylibThread,
+    ) -> Option<Rc<RefCell<FoximgImage>>> {
+        if self.images_failed[i] {
             return None;
         }
 
-        match self.images[self.current].upgrade() {
+        match self.images[i].upgrade() {
             Some(texture) => Some(texture),
             None => {
-                match self.images_loader[self.current](rl, rl_thread, &self.paths[self.current]) {
+                match self.images_loader[i](rl, rl_thread, &self.paths[i]) {
                     Ok(texture) => {
-                        self.images[self.current] = Rc::downgrade(&texture);
+                        texture.borrow().set_filter(rl_thread, self.filter);
+                        self.images[i] = Rc::downgrade(&texture);
+                        self.shrink_cache(self.cache_size - 1);
                         self.current_images.push_back(texture.clone());
 
                         Some(texture)
                     }
                     Err(e) => {
-                        self.images_failed[self.current] = true;
+                        self.images_failed[i] = true;
                         rl.trace_log(
                             TraceLogLevel::LOG_ERROR,
                             &format!("FOXIMG: Failed to load image: {e}"),
@@ -396,51 +830,73 @@ impl FoximgImages {
     }
 
     pub fn flip_horizontal(&mut self, rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
-        self.img_with(rl, rl_thread, |mut img| img.width_mult = -img.width_mult);
+        self.img_with(rl, rl_thread, |mut img| img.edit(FoximgEdit::FlipHorizontal));
     }
 
     pub fn flip_vertical(&mut self, rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
-        self.img_with(rl, rl_thread, |mut img| img.height_mult = -img.height_mult);
+        self.img_with(rl, rl_thread, |mut img| img.edit(FoximgEdit::FlipVertical));
     }
 
     pub fn rotate_n1(&mut self, rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
         self.img_with(rl, rl_thread, |mut img| {
-            img.rotation -= 1.;
-            if img.rotation == -1. {
-                img.rotation = 359.;
+            let from = img.rotation;
+            let mut to = from - 1.;
+            if to == -1. {
+                to = 359.;
             }
+            img.edit_merging(FoximgEdit::Rotate { from, to });
         });
     }
 
     pub fn rotate_1(&mut self, rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
         self.img_with(rl, rl_thread, |mut img| {
-            img.rotation += 1.;
-            if img.rotation == 360. {
-                img.rotation = 0.;
+            let from = img.rotation;
+            let mut to = from + 1.;
+            if to == 360. {
+                to = 0.;
             }
+            img.edit_merging(FoximgEdit::Rotate { from, to });
         });
     }
 
     pub fn rotate_n90(&mut self, rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
         self.img_with(rl, rl_thread, |mut img| {
-            let rot_mod90 = img.rotation % 90.;
-            img.rotation -= if rot_mod90 == 0. { 90. } else { rot_mod90 };
-            if img.rotation == -90. {
-                img.rotation = 270.;
+            let from = img.rotation;
+            let rot_mod90 = from % 90.;
+            let mut to = from - if rot_mod90 == 0. { 90. } else { rot_mod90 };
+            if to == -90. {
+                to = 270.;
             }
+            img.edit(FoximgEdit::Rotate { from, to });
         });
     }
 
     pub fn rotate_90(&mut self, rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
         self.img_with(rl, rl_thread, |mut img| {
-            img.rotation += 90. - img.rotation % 90.;
-            if img.rotation == 360. {
-                img.rotation = 0.;
+            let from = img.rotation;
+            let mut to = from + 90. - from % 90.;
+            if to == 360. {
+                to = 0.;
             }
+            img.edit(FoximgEdit::Rotate { from, to });
         });
     }
 }
 
+/// Returns the loader for the image at `path` going by its extension, or `None` if foximg can't
+/// open it.
+pub fn image_loader(path: &Path) -> Option<FoximgImageLoader> {
+    let ext = path.extension()?.to_ascii_lowercase();
+    match ext.to_str()? {
+        "bmp" | "jpg" | "jpeg" | "jpe" | "jif" | "jfif" | "jfi" | "dds" | "hdr" | "ico" | "qoi"
+        | "tiff" | "pgm" | "pbm" | "ppm" | "pnm" | "exr" => Some(FoximgImage::new_dynamic),
+        "apng" | "png" => Some(FoximgImage::new_png),
+        "webp" => Some(FoximgImage::new_webp),
+        "gif" => Some(FoximgImage::new_gif),
+        _ => None,
+    }
+}
+
 type FoximgFolderIter = Box<dyn Iterator<Item = Result<PathBuf, Option<std::io::Error>>>>;
 
 /// Intermediate struct that helps with loading folders into Foximg galleries.
@@ -468,33 +924,32 @@ impl<'a> FoximgFolder<'a> {
     }
 
     fn skip_reread(&mut self) -> Option<Box<FoximgImages>> {
-        if let Some(ref mut images) = self.f.images {
-            if self.folder.is_some()
-                && images.paths.first().and_then(|path| path.parent()) == self.folder
+        if let Some(ref mut images) = self.f.images
+            && self.folder.is_some()
+            && images.paths.first().and_then(|path| path.parent()) == self.folder
+        {
+            self.f.rl.trace_log(
+                TraceLogLevel::LOG_INFO,
+                &format!(
+                    "FOXIMG: Searching through already loaded gallery for {:?}",
+                    self.path
+                ),
+            );
+
+            if let Some(current) = images
+                .paths
+                .iter()
+                .enumerate()
+                .find(|(_, path)| *path == self.path)
+                .map(|(i, _)| i)
             {
-                self.f.rl.trace_log(
-                    TraceLogLevel::LOG_INFO,
-                    &format!(
-                        "FOXIMG: Searching through already loaded gallery for {:?}",
-                        self.path
-                    ),
-                );
-
-                if let Some(current) = images
-                    .paths
-                    .iter()
-                    .enumerate()
-                    .find(|(_, path)| *path == self.path)
-                    .map(|(i, _)| i)
-                {
-                    images.current = current;
-                    return self.f.images.take();
-                }
-                self.f.rl.trace_log(
-                    TraceLogLevel::LOG_INFO,
-                    &format!("FOXIMG: Failed to find {:?}. Re-reading folder", self.path),
-                );
+                images.current = current;
+                return self.f.images.take();
             }
+            self.f.rl.trace_log(
+                TraceLogLevel::LOG_INFO,
+                &format!("FOXIMG: Failed to find {:?}. Re-reading folder", self.path),
+            );
         }
 
         None
@@ -502,10 +957,6 @@ impl<'a> FoximgFolder<'a> {
 
     /// Push a valid image and increment `i`.
     fn push_img(&mut self, i: &mut usize, current_path: PathBuf, loader: FoximgImageLoader) {
-        if current_path == self.path {
-            self.current = Some(*i);
-        }
-
         *i += 1;
         self.paths.push(current_path);
         self.images_loader.push(loader);
@@ -528,35 +979,29 @@ impl<'a> FoximgFolder<'a> {
                 }
             };
 
-            let Some(ext) = current_path.extension() else {
-                continue;
-            };
-
-            let ext = ext.to_ascii_lowercase();
-            let ext = ext.to_str();
-
-            match ext {
-                Some("bmp") | Some("jpg") | Some("jpeg") | Some("jpe") | Some("jif")
-                | Some("jfif") | Some("jfi") | Some("dds") | Some("hdr") | Some("ico")
-                | Some("qoi") | Some("tiff") | Some("pgm") | Some("pbm") | Some("ppm")
-                | Some("pnm") | Some("exr") => {
-                    self.push_img(&mut i, current_path, FoximgImage::new_dynamic);
-                }
-                Some("apng") | Some("png") => {
-                    self.push_img(&mut i, current_path, FoximgImage::new_png)
-                }
-                Some("webp") => self.push_img(&mut i, current_path, FoximgImage::new_webp),
-                Some("gif") => self.push_img(&mut i, current_path, FoximgImage::new_gif),
-                _ => (),
+            if let Some(loader) = self::image_loader(&current_path) {
+                self.push_img(&mut i, current_path, loader);
             }
         }
         i
     }
 
-    /// Gets the closest image alphabetically to `path` if it points to an invalid image file. Searches
-    /// through `paths` by calling parameter `search_by`. The return value of an `Err` must be the
-    /// erroneous result of a binary search, that contains the index where a matching element could
-    /// be inserted while maintaining a;lphabetical order.
+    /// Sorts the images pushed so far by their paths, keeping track of where the current one ends
+    /// up.
+    fn sort_images(&mut self) {
+        let mut images: Vec<_> = self
+            .paths
+            .drain(..)
+            .zip(self.images_loader.drain(..))
+            .collect();
+        images.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        self.current = images.iter().position(|(path, _)| path == self.path);
+        (self.paths, self.images_loader) = images.into_iter().unzip();
+    }
+
+    /// Gets the closest image alphabetically to `path` if it points to an invalid image file. `paths`
+    /// must be sorted. Past the last image, the last image is the closest one.
     fn get_closest_image_alphabetically(&self) -> Option<usize> {
         self.f.rl.trace_log(
             TraceLogLevel::LOG_INFO,
@@ -570,6 +1015,7 @@ impl<'a> FoximgFolder<'a> {
                 <PathBuf as AsRef<Path>>::as_ref(other).cmp(self.path)
             })
             .err()
+            .map(|i| i.min(self.paths.len().saturating_sub(1)))
     }
 
     /// Loads the folder into the gallery. This will return `Err` in case:
@@ -583,11 +1029,13 @@ impl<'a> FoximgFolder<'a> {
 
         let i = self.push_images(iter);
         if i > 0 {
+            self.sort_images();
             let current = self
                 .current
                 .or_else(|| self.get_closest_image_alphabetically())
                 .unwrap_or_default();
-            let images = FoximgImages::new(self.paths, self.images_loader, current);
+            let images =
+                FoximgImages::new(self.paths, self.images_loader, current, &self.f.settings);
 
             self.f.rl.trace_log(
                 TraceLogLevel::LOG_INFO,
@@ -647,9 +1095,44 @@ impl Foximg {
 
         images.update_window(self);
         self.images = Some(images);
+        if self.filter.is_active() && self.lock.is_none() {
+            self.scan_folder(FoximgFilter::default());
+        }
         Ok(())
     }
 
+    /// Puts an image that was removed from the gallery back at index `i` and opens it. If the
+    /// gallery got unloaded since, the image's folder is loaded instead. Nothing happens if the
+    /// gallery is showing another folder.
+    pub fn reinsert_img(&mut self, path: &Path, i: usize, loader: FoximgImageLoader) {
+        let Some(ref mut images) = self.images else {
+            self.load_folder(path);
+            return;
+        };
+
+        if images.paths.first().and_then(|path| path.parent()) != path.parent() {
+            return;
+        }
+
+        let i = images.insert(i, path.to_path_buf(), loader);
+        self.images_with(|f, images| {
+            images.set_current(i);
+            images.update_window(f);
+        });
+    }
+
+    /// Unloads the gallery if every image was removed from it.
+    pub fn close_gallery_if_empty(&mut self) {
+        if self.images.as_ref().is_none_or(|images| images.len() > 0) {
+            return;
+        }
+
+        self.images = None;
+        self.title = crate::format_title(&mut self.rl, &self.rl_thread, &self.title_format, None);
+        self.rl
+            .set_window_title(&self.rl_thread, &self.title.replace('\n', ""));
+    }
+
     pub fn load_folder(&mut self, path: impl AsRef<Path>) {
         let path = path.as_ref();
         if let Err(e) = self.try_load_folder(path) {
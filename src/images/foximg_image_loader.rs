@@ -24,9 +24,12 @@ use image::{
 };
 use raylib::prelude::*;
 
-use crate::config::{FoximgIcon, FoximgStyle};
+use crate::{
+    adjust::FoximgAdjustments,
+    config::{FoximgIcon, FoximgStyle},
+};
 
-use super::{AnimationLoops, FoximgImage, FoximgImageAnimated};
+use super::{AnimationLoops, FoximgHistory, FoximgImage, FoximgImageAnimated};
 
 /// Represents a function that constructs a `FoximgImage.`
 pub type FoximgImageLoader =
@@ -125,6 +128,8 @@ impl FoximgImage {
             rotation: 0.,
             width_mult: 1,
             height_mult: 1,
+            adjustments: FoximgAdjustments::default(),
+            history: FoximgHistory::default(),
         })
     }
 
@@ -0,0 +1,553 @@
+//! Defines the history of edits made to each image, which rotations, mirrors and colour adjustments
+//! go through so that they can be undone and redone. foximg has no crop tool, so there are no crops
+//! to undo. Edits stay unsaved until they're baked into a copy of the image or over its file, and
+//! foximg asks about them before leaving an edited image or quitting.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use image::DynamicImage;
+use raylib::prelude::*;
+
+use crate::{Foximg, FoximgDraw, adjust::FoximgAdjustments, culling, keybinds::FoximgAction};
+
+use super::{FoximgImage, FoximgImages, metadata};
+
+/// How many edits of each image can be undone. Older edits are forgotten, which keeps the history
+/// of an image within a few kilobytes.
+const MAX_EDITS: usize = 128;
+
+/// An edit made to an image, which knows how to undo itself.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FoximgEdit {
+    FlipHorizontal,
+    FlipVertical,
+    Rotate {
+        from: f32,
+        to: f32,
+    },
+    Adjust {
+        from: FoximgAdjustments,
+        to: FoximgAdjustments,
+    },
+}
+
+impl FoximgEdit {
+    fn apply(self, img: &mut FoximgImage) {
+        match self {
+            FoximgEdit::FlipHorizontal => img.width_mult = -img.width_mult,
+            FoximgEdit::FlipVertical => img.height_mult = -img.height_mult,
+            FoximgEdit::Rotate { to, .. } => img.rotation = to,
+            FoximgEdit::Adjust { to, .. } => img.adjustments = to,
+        }
+    }
+
+    fn revert(self, img: &mut FoximgImage) {
+        match self {
+            FoximgEdit::FlipHorizontal | FoximgEdit::FlipVertical => self.apply(img),
+            FoximgEdit::Rotate { from, .. } => img.rotation = from,
+            FoximgEdit::Adjust { from, .. } => img.adjustments = from,
+        }
+    }
+}
+
+impl fmt::Display for FoximgEdit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FoximgEdit::FlipHorizontal => "horizontal mirror",
+            FoximgEdit::FlipVertical => "vertical mirror",
+            FoximgEdit::Rotate { .. } => "rotation",
+            FoximgEdit::Adjust { .. } => "colour adjustments",
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct FoximgHistory {
+    undo: VecDeque<FoximgEdit>,
+    redo: Vec<FoximgEdit>,
+}
+
+impl FoximgImage {
+    /// Applies `edit` and remembers it so that it can be undone.
+    pub fn edit(&mut self, edit: FoximgEdit) {
+        edit.apply(self);
+        self.history.redo.clear();
+        self.history.undo.push_back(edit);
+        if self.history.undo.len() > MAX_EDITS {
+            self.history.undo.pop_front();
+        }
+    }
+
+    /// Like `edit`, but a rotation right after another one is merged into it, so that fine
+    /// rotations made by holding a key are undone at once.
+    pub fn edit_merging(&mut self, edit: FoximgEdit) {
+        match (self.history.undo.back_mut(), edit) {
+            (Some(FoximgEdit::Rotate { to, .. }), FoximgEdit::Rotate { to: new_to, .. })
+                if self.history.redo.is_empty() =>
+            {
+                *to = new_to;
+                edit.apply(self);
+            }
+            _ => self.edit(edit),
+        }
+    }
+
+    /// Undoes the last edit. Returns it, or `None` if there's nothing to undo.
+    pub fn undo(&mut self) -> Option<FoximgEdit> {
+        let edit = self.history.undo.pop_back()?;
+        edit.revert(self);
+        self.history.redo.push(edit);
+        Some(edit)
+    }
+
+    /// Redoes the last undone edit. Returns it, or `None` if there's nothing to redo.
+    pub fn redo(&mut self) -> Option<FoximgEdit> {
+        let edit = self.history.redo.pop()?;
+        edit.apply(self);
+        self.history.undo.push_back(edit);
+        Some(edit)
+    }
+
+    pub fn adjustments(&self) -> FoximgAdjustments {
+        self.adjustments
+    }
+
+    /// Shows `adjustments` without remembering them, such as while they're being picked.
+    pub fn preview_adjustments(&mut self, adjustments: FoximgAdjustments) {
+        self.adjustments = adjustments;
+    }
+
+    /// Returns whether the image looks any different from its file.
+    pub fn has_unsaved_edits(&self) -> bool {
+        self.rotation != 0.
+            || self.width_mult != 1
+            || self.height_mult != 1
+            || !self.adjustments.is_identity()
+    }
+
+    /// Goes back to how the image looks in its file, forgetting its history.
+    pub fn discard_edits(&mut self) {
+        self.rotation = 0.;
+        self.width_mult = 1;
+        self.height_mult = 1;
+        self.adjustments = FoximgAdjustments::default();
+        self.history = FoximgHistory::default();
+    }
+
+    /// Returns `image` with the edits of this image baked in. Only rotations by multiples of 90
+    /// degrees can be baked.
+    fn bake_edits(&self, image: DynamicImage) -> anyhow::Result<DynamicImage> {
+        let mut image = if self.adjustments.is_identity() {
+            image
+        } else {
+            self.adjustments.bake(&image)
+        };
+
+        if self.width_mult == -1 {
+            image = image.fliph();
+        }
+        if self.height_mult == -1 {
+            image = image.flipv();
+        }
+
+        Ok(match self.rotation {
+            0. => image,
+            90. => image.rotate90(),
+            180. => image.rotate180(),
+            270. => image.rotate270(),
+            rotation => anyhow::bail!("Can't save a rotation of {rotation} degrees"),
+        })
+    }
+}
+
+impl FoximgImages {
+    /// Returns the image at index `i` if it's loaded, without loading it.
+    pub fn loaded(&self, i: usize) -> Option<Rc<RefCell<FoximgImage>>> {
+        self.images[i].upgrade()
+    }
+
+    /// Returns the indices of every loaded image with unsaved edits.
+    pub fn edited_indices(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| {
+                self.loaded(i)
+                    .is_some_and(|img| img.borrow().has_unsaved_edits())
+            })
+            .collect()
+    }
+
+    /// Bakes the edits of the image at index `i` into a copy of it next to it if `as_copy` is set,
+    /// or over its file otherwise. The image keeps its format, bit depth and metadata. Returns the
+    /// path the edits were saved to, or `None` if the image has none.
+    ///
+    /// Saving a copy discards the edits of the image itself, since they're in the copy now.
+    /// Saving over the file loads the image again.
+    fn save_edits(
+        &mut self,
+        i: usize,
+        as_copy: bool,
+        rl: &mut RaylibHandle,
+        rl_thread: &RaylibThread,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let Some(img) = self
+            .loaded(i)
+            .filter(|img| img.borrow().has_unsaved_edits())
+        else {
+            return Ok(None);
+        };
+
+        if img.borrow().animation.is_some() {
+            anyhow::bail!("Can't save edits to animated images");
+        }
+
+        let path = &self.paths[i];
+        let (image, format, metadata) = metadata::open(path)?;
+        let file = metadata::encode(&img.borrow().bake_edits(image)?, format, metadata)?;
+        let output = if as_copy {
+            self::edited_path(path)
+        } else {
+            path.clone()
+        };
+        self::write_file(&output, &file)?;
+
+        if as_copy {
+            img.borrow_mut().discard_edits();
+            return Ok(Some(output));
+        }
+
+        let reloaded = self.images_loader[i](rl, rl_thread, path)?;
+        let reloaded = Rc::try_unwrap(reloaded)
+            .map_err(|_| anyhow::anyhow!("Reloaded image is still in use"))?
+            .into_inner();
+        *img.borrow_mut() = reloaded;
+        Ok(Some(output))
+    }
+}
+
+/// Returns where the edited copy of the image at `path` is saved: next to it, with "-edited" after
+/// its name.
+fn edited_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-edited.{}", ext.to_string_lossy()),
+        None => format!("{stem}-edited"),
+    };
+    culling::unique_path(path.parent().unwrap_or(Path::new("")), Path::new(&name))
+}
+
+/// Writes `file` to `path`. It's written next to it and then moved over it, so that the image is
+/// never left half written.
+fn write_file(path: &Path, file: &[u8]) -> anyhow::Result<()> {
+    let mut tmp_path = path.to_path_buf().into_os_string();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let written = File::create(&tmp_path).and_then(|mut tmp| {
+        tmp.write_all(file)?;
+        tmp.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// What to do with the unsaved edits of an image.
+#[derive(Clone, Copy, PartialEq)]
+enum FoximgEditsAnswer {
+    Save,
+    Discard,
+    /// Stay on the image, keeping its edits.
+    Cancel,
+}
+
+impl Foximg {
+    /// Undoes the last edit of the current image if `undo` is set, or redoes the last undone one
+    /// otherwise.
+    fn step_history(&mut self, undo: bool) {
+        let mut stepped = None;
+        if let Some(ref mut images) = self.images {
+            images.img_with(&mut self.rl, &self.rl_thread, |mut img| {
+                stepped = if undo { img.undo() } else { img.redo() };
+            });
+        }
+
+        match (stepped, undo) {
+            (Some(edit), true) => self.toast(format!("Undid {edit}")),
+            (Some(edit), false) => self.toast(format!("Redid {edit}")),
+            (None, true) => self.toast("Nothing to undo"),
+            (None, false) => self.toast("Nothing to redo"),
+        }
+    }
+
+    pub fn undo_edit(&mut self) {
+        self.step_history(true);
+    }
+
+    pub fn redo_edit(&mut self) {
+        self.step_history(false);
+    }
+
+    /// Undoes the last edit of the current image if `Undo` is pressed, or redoes the last undone
+    /// one if `Redo` is. Returns true if so.
+    pub fn undo_edit_img(&mut self) -> bool {
+        if self.images.is_none() {
+            return false;
+        }
+
+        if self.is_action_pressed_or_repeat(FoximgAction::Undo) {
+            self.undo_edit();
+            true
+        } else if self.is_action_pressed_or_repeat(FoximgAction::Redo) {
+            self.redo_edit();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Bakes the edits of the image at index `i` into a copy of it if `as_copy` is set, or over its
+    /// file otherwise. Copies are put into the gallery next to the image. Returns true if the
+    /// edits got saved.
+    pub fn save_edits(&mut self, i: usize, as_copy: bool) -> bool {
+        let mut saved = false;
+        self.images_with(|f, images| {
+            let path = images.paths()[i].clone();
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            match images.save_edits(i, as_copy, &mut f.rl, &f.rl_thread) {
+                Ok(Some(output)) if as_copy => {
+                    f.copy_sidecar(&path, &output);
+                    if f.lock.is_none() {
+                        let at = images.paths().partition_point(|path| *path < output);
+                        images.insert(at, output.clone(), images.loader(i));
+                    }
+
+                    let copy = output.file_name().unwrap_or_default().to_string_lossy();
+                    f.toast(format!("Saved edits to \"{copy}\""));
+                    saved = true;
+                }
+                Ok(Some(_)) => {
+                    f.toast(format!("Saved edits over \"{name}\""));
+                    saved = true;
+                }
+                Ok(None) => {
+                    f.toast("No edits to save");
+                    saved = true;
+                }
+                Err(e) => {
+                    f.rl.trace_log(
+                        TraceLogLevel::LOG_ERROR,
+                        &format!("FOXIMG: Failed to save edits to {path:?}:"),
+                    );
+                    f.rl.trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                    f.toast(format!("Couldn't save edits to \"{name}\""));
+                }
+            }
+        });
+        saved
+    }
+
+    /// Bakes the edits of the current image into a copy of it.
+    pub fn save_current_edits(&mut self) {
+        if let Some(ref images) = self.images {
+            self.save_edits(images.img_current() - 1, true);
+        }
+    }
+
+    /// Bakes the edits of the current image over its file.
+    pub fn overwrite_current_edits(&mut self) {
+        if let Some(ref images) = self.images {
+            self.save_edits(images.img_current() - 1, false);
+        }
+    }
+
+    /// Goes back to how the current image looks in its file.
+    pub fn discard_current_edits(&mut self) {
+        if let Some(ref mut images) = self.images {
+            images.img_with(&mut self.rl, &self.rl_thread, |mut img| img.discard_edits());
+            self.toast("Discarded edits");
+        }
+    }
+
+    /// Shows a prompt asking what to do with unsaved edits. This runs its own loop until the prompt
+    /// gets answered. When `quitting`, the prompt can't be cancelled, and it stays up even if the
+    /// window is asked to close. Saving saves into copies of the images.
+    fn ask_about_edits(&mut self, message: &str, quitting: bool) -> FoximgEditsAnswer {
+        let buttons = if quitting {
+            "Save Copy;Discard"
+        } else {
+            "Save Copy;Discard;Cancel"
+        };
+
+        // The keys that left the image are still pressed until a frame of the prompt is drawn.
+        let mut drawn = false;
+        while quitting || !self.rl.window_should_close() {
+            self.update();
+
+            let answer = if !drawn {
+                None
+            } else if self.is_action_pressed(FoximgAction::SaveEdits) {
+                Some(FoximgEditsAnswer::Save)
+            } else if self.is_action_pressed(FoximgAction::DiscardEdits) {
+                Some(FoximgEditsAnswer::Discard)
+            } else if !quitting && self.is_action_pressed(FoximgAction::KeepEdits) {
+                Some(FoximgEditsAnswer::Cancel)
+            } else {
+                None
+            };
+            if let Some(answer) = answer {
+                return answer;
+            }
+
+            let mut result = -1;
+            FoximgDraw::begin(self, |mut d, images| {
+                if let Some(images) = images {
+                    d.draw_current_img(images);
+                }
+
+                result = d.draw_edits_prompt(message, buttons);
+            });
+            drawn = true;
+
+            match result {
+                1 => return FoximgEditsAnswer::Save,
+                2 => return FoximgEditsAnswer::Discard,
+                0 | 3 if !quitting => return FoximgEditsAnswer::Cancel,
+                _ => (),
+            }
+        }
+
+        FoximgEditsAnswer::Cancel
+    }
+
+    /// Asks what to do with the unsaved edits of the image at `previous` if the gallery moved on
+    /// from it. Cancelling goes back to it.
+    pub fn check_left_edits(&mut self, previous: Option<PathBuf>) {
+        let (Some(previous), Some(images)) = (previous, &self.images) else {
+            return;
+        };
+
+        if images.img_path() == previous {
+            return;
+        }
+
+        let Some(i) = images.paths().iter().position(|path| *path == previous) else {
+            return;
+        };
+        if !images
+            .loaded(i)
+            .is_some_and(|img| img.borrow().has_unsaved_edits())
+        {
+            return;
+        }
+
+        let name = previous.file_name().unwrap_or_default().to_string_lossy();
+        let message = format!("\"{name}\" has unsaved edits.");
+        let mut answer = self.ask_about_edits(&message, false);
+        if answer == FoximgEditsAnswer::Save && !self.save_edits(i, true) {
+            answer = FoximgEditsAnswer::Cancel;
+        }
+
+        self.images_with(|f, images| match answer {
+            FoximgEditsAnswer::Save => (),
+            FoximgEditsAnswer::Discard => {
+                if let Some(img) = images.loaded(i) {
+                    img.borrow_mut().discard_edits();
+                }
+            }
+            FoximgEditsAnswer::Cancel => {
+                images.set_current(i);
+                images.update_window(f);
+            }
+        });
+    }
+
+    /// Asks what to do with every unsaved edit in the gallery before it's closed, such as when
+    /// quitting or opening another folder. Returns false if the user cancelled, which can't be done
+    /// when `quitting`.
+    pub fn check_all_edits(&mut self, quitting: bool) -> bool {
+        let edited = match self.images {
+            Some(ref images) => images.edited_indices(),
+            None => return true,
+        };
+        self.check_edits(edited, quitting)
+    }
+
+    /// Asks what to do with the unsaved edits of the images at the indices in `edited`, all at once.
+    /// Returns false if the user cancelled, which can't be done when `quitting`.
+    pub fn check_edits(&mut self, edited: Vec<usize>, quitting: bool) -> bool {
+        let Some(ref images) = self.images else {
+            return true;
+        };
+
+        // Saved copies get inserted into the gallery, which moves the images after them, so the
+        // edited images are kept track of by their paths.
+        let paths: Vec<_> = edited.iter().map(|&i| images.paths()[i].clone()).collect();
+        let message = match paths[..] {
+            [] => return true,
+            [ref path] => format!(
+                "\"{}\" has unsaved edits.",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            _ => format!("{} images have unsaved edits.", paths.len()),
+        };
+
+        match self.ask_about_edits(&message, quitting) {
+            FoximgEditsAnswer::Save => {
+                let mut saved = true;
+                for path in paths {
+                    let i = self
+                        .images
+                        .as_ref()
+                        .and_then(|images| images.paths().iter().position(|p| *p == path));
+                    saved &= i.is_some_and(|i| self.save_edits(i, true));
+                }
+                saved || quitting
+            }
+            FoximgEditsAnswer::Discard => true,
+            FoximgEditsAnswer::Cancel => false,
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the prompt asking what to do with unsaved edits. Returns the button that was clicked
+    /// starting from 1, 0 if it was closed, and -1 if it's still waiting for an answer.
+    fn draw_edits_prompt(&mut self, message: &str, buttons: &str) -> i32 {
+        const WIDTH: f32 = 360.;
+        const HEIGHT: f32 = 120.;
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let screen_height = self.d.get_screen_height().as_f32();
+
+        self.d.draw_rectangle_rec(
+            rrect(0, 0, screen_width, screen_height),
+            self.style.bg.alpha(0.5),
+        );
+        self.d.gui_message_box(
+            rrect(
+                screen_width / 2. - WIDTH / 2.,
+                screen_height / 2. - HEIGHT / 2.,
+                WIDTH,
+                HEIGHT,
+            ),
+            "Unsaved Edits",
+            message,
+            buttons,
+        )
+    }
+}
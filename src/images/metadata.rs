@@ -0,0 +1,248 @@
+//! Carries the metadata of images over when their edits are saved. The image crate reads ICC
+//! profiles and EXIF, and writes ICC profiles, so EXIF and XMP get spliced into the encoded file by
+//! hand. Only JPEG and PNG files get their EXIF and XMP back, which are the formats they're almost
+//! always found in.
+
+use std::{fs, io::Cursor, path::Path};
+
+use image::{
+    DynamicImage, ImageDecoder, ImageEncoder, ImageFormat, ImageReader,
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+};
+
+/// The quality JPEG files are saved with. It's high, since the image already lost some quality
+/// when it was first saved.
+const JPEG_QUALITY: u8 = 95;
+
+/// What APP1 segments holding XMP start with in JPEG files.
+const JPEG_XMP: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// What APP1 segments holding EXIF start with in JPEG files.
+const JPEG_EXIF: &[u8] = b"Exif\0\0";
+/// The keyword of the iTXt chunk holding XMP in PNG files.
+const PNG_XMP: &[u8] = b"XML:com.adobe.xmp\0";
+const PNG_SIGNATURE: usize = 8;
+/// The EXIF tag telling viewers how to rotate or mirror the image.
+const EXIF_ORIENTATION: u16 = 0x0112;
+
+/// The metadata of an image that should survive re-encoding it.
+#[derive(Default)]
+pub struct FoximgMetadata {
+    icc: Option<Vec<u8>>,
+    exif: Option<Vec<u8>>,
+    xmp: Option<Vec<u8>>,
+}
+
+/// Decodes the image at `path` at its own bit depth, along with the format it's in and its
+/// metadata. Metadata that can't be read is left out.
+pub fn open(path: &Path) -> anyhow::Result<(DynamicImage, ImageFormat, FoximgMetadata)> {
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader
+        .format()
+        .ok_or_else(|| anyhow::anyhow!("Not a recognized or supported image"))?;
+
+    let mut decoder = reader.into_decoder()?;
+    let icc = decoder.icc_profile().ok().flatten();
+    let exif = decoder.exif_metadata().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder)?;
+    let xmp = fs::read(path)
+        .ok()
+        .and_then(|file| self::find_xmp(&file, format));
+
+    Ok((image, format, FoximgMetadata { icc, exif, xmp }))
+}
+
+/// Encodes `image` as `format` with `metadata`. The EXIF orientation is reset, since `image` has
+/// its edits baked in as foximg showed them, and other programs would rotate it again otherwise.
+pub fn encode(
+    image: &DynamicImage,
+    format: ImageFormat,
+    mut metadata: FoximgMetadata,
+) -> anyhow::Result<Vec<u8>> {
+    if let Some(ref mut exif) = metadata.exif {
+        self::reset_orientation(exif);
+    }
+
+    let mut file = Vec::new();
+    let (bytes, width, height) = (image.as_bytes(), image.width(), image.height());
+    let color = image.color().into();
+    match format {
+        ImageFormat::Jpeg => {
+            let mut encoder = JpegEncoder::new_with_quality(&mut file, JPEG_QUALITY);
+            if let Some(icc) = metadata.icc {
+                encoder.set_icc_profile(icc)?;
+            }
+            encoder.write_image(bytes, width, height, color)?;
+            self::insert_jpeg_segments(&mut file, metadata.exif, metadata.xmp);
+        }
+        ImageFormat::Png => {
+            let mut encoder = PngEncoder::new(&mut file);
+            if let Some(icc) = metadata.icc {
+                encoder.set_icc_profile(icc)?;
+            }
+            encoder.write_image(bytes, width, height, color)?;
+            self::insert_png_chunks(&mut file, metadata.exif, metadata.xmp);
+        }
+        ImageFormat::WebP => {
+            let mut encoder = WebPEncoder::new_lossless(&mut file);
+            if let Some(icc) = metadata.icc {
+                encoder.set_icc_profile(icc)?;
+            }
+            encoder.write_image(bytes, width, height, color)?;
+        }
+        format => image.write_to(&mut Cursor::new(&mut file), format)?,
+    }
+    Ok(file)
+}
+
+/// Returns the XMP packet embedded in `file`, if it has one.
+fn find_xmp(file: &[u8], format: ImageFormat) -> Option<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => {
+            let mut at = 2;
+            // Segments end where the compressed data starts.
+            while file.get(at) == Some(&0xFF) && file.get(at + 1) != Some(&0xDA) {
+                let marker = *file.get(at + 1)?;
+                let len = usize::from(u16::from_be_bytes([*file.get(at + 2)?, *file.get(at + 3)?]));
+                let segment = file.get(at + 4..at + 2 + len)?;
+                if marker == 0xE1 && segment.starts_with(JPEG_XMP) {
+                    return Some(segment[JPEG_XMP.len()..].to_vec());
+                }
+                at += 2 + len;
+            }
+            None
+        }
+        ImageFormat::Png => {
+            let mut at = PNG_SIGNATURE;
+            while let Some(header) = file.get(at..at + 8) {
+                let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+                let data = file.get(at + 8..at + 8 + len)?;
+                if &header[4..] == b"iTXt" && data.starts_with(PNG_XMP) {
+                    // Compressed XMP is rare enough to be left out.
+                    let rest = &data[PNG_XMP.len()..];
+                    if rest.first() != Some(&0) {
+                        return None;
+                    }
+                    // Skip the compression flag and method, then the language tag and the
+                    // translated keyword.
+                    let mut text = rest.get(2..)?;
+                    for _ in 0..2 {
+                        let end = text.iter().position(|&b| b == 0)?;
+                        text = &text[end + 1..];
+                    }
+                    return Some(text.to_vec());
+                }
+                at += 12 + len;
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Sets the orientation in the first IFD of the TIFF structure `exif` to 1, which leaves the image
+/// as it is. EXIF that can't be read is left as it is.
+fn reset_orientation(exif: &mut [u8]) -> Option<()> {
+    let big_endian = match exif.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |exif: &[u8], at: usize| -> Option<u16> {
+        let bytes = [*exif.get(at)?, *exif.get(at + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+
+    let offset = exif.get(4..8)?.try_into().ok()?;
+    let ifd = if big_endian {
+        u32::from_be_bytes(offset)
+    } else {
+        u32::from_le_bytes(offset)
+    } as usize;
+
+    for entry in 0..usize::from(u16_at(exif, ifd)?) {
+        let at = ifd + 2 + entry * 12;
+        if u16_at(exif, at)? == EXIF_ORIENTATION {
+            // Orientation is a single SHORT, which sits at the start of the value field.
+            let one = if big_endian {
+                1u16.to_be_bytes()
+            } else {
+                1u16.to_le_bytes()
+            };
+            exif.get_mut(at + 8..at + 10)?.copy_from_slice(&one);
+            return Some(());
+        }
+    }
+    None
+}
+
+/// Inserts APP1 segments with `exif` and `xmp` into the encoded JPEG `file`, right after its JFIF
+/// segment. Metadata too big for a single segment is left out.
+fn insert_jpeg_segments(file: &mut Vec<u8>, exif: Option<Vec<u8>>, xmp: Option<Vec<u8>>) {
+    let mut at = 2;
+    if file.get(2..4) == Some(&[0xFF, 0xE0]) {
+        at += 2 + usize::from(u16::from_be_bytes([file[4], file[5]]));
+    }
+
+    let mut segments = Vec::new();
+    for (header, data) in [(JPEG_EXIF, exif), (JPEG_XMP, xmp)] {
+        let Some(data) = data else {
+            continue;
+        };
+        let Ok(len) = u16::try_from(2 + header.len() + data.len()) else {
+            continue;
+        };
+
+        segments.extend_from_slice(&[0xFF, 0xE1]);
+        segments.extend_from_slice(&len.to_be_bytes());
+        segments.extend_from_slice(header);
+        segments.extend_from_slice(&data);
+    }
+    file.splice(at..at, segments);
+}
+
+/// Inserts an eXIf chunk with `exif` and an iTXt chunk with `xmp` into the encoded PNG `file`,
+/// right after its IHDR chunk.
+fn insert_png_chunks(file: &mut Vec<u8>, exif: Option<Vec<u8>>, xmp: Option<Vec<u8>>) {
+    let xmp = xmp.map(|xmp| {
+        let mut data = PNG_XMP.to_vec();
+        // Uncompressed, with no language tag or translated keyword.
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&xmp);
+        data
+    });
+
+    let mut chunks = Vec::new();
+    for (kind, data) in [(b"eXIf", exif), (b"iTXt", xmp)] {
+        let Some(data) = data else {
+            continue;
+        };
+
+        let start = chunks.len();
+        chunks.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunks.extend_from_slice(kind);
+        chunks.extend_from_slice(&data);
+        let crc = self::crc32(&chunks[start + 4..]);
+        chunks.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    // IHDR always comes first, and always holds 13 bytes.
+    let at = PNG_SIGNATURE + 12 + 13;
+    file.splice(at..at, chunks);
+}
+
+/// The CRC-32 that PNG chunks end with.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
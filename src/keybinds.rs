@@ -0,0 +1,671 @@
+//! Defines the keybinds of foximg. Every control is a named action that's bound to one or more
+//! chords of keys or mouse buttons in "foximg_keybinds.toml". Missing actions keep their default
+//! chords, and actions whose chords conflict with another action fall back to their defaults.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{Foximg, config::FoximgConfig};
+
+macro_rules! foximg_actions {
+    ($($(#[doc = $doc:literal])* $action:ident: $name:literal => [$($chord:literal),* $(,)?],)*) => {
+        /// Everything that can be bound to a chord.
+        #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+        pub enum FoximgAction {
+            $($(#[doc = $doc])* #[serde(rename = $name)] $action,)*
+        }
+
+        impl FoximgAction {
+            pub const ALL: &[Self] = &[$(Self::$action),*];
+
+            /// Returns the name of the action in "foximg_keybinds.toml".
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Self::$action => $name),*
+                }
+            }
+
+            /// Returns the chords the action is bound to by default.
+            const fn defaults(self) -> &'static [&'static str] {
+                match self {
+                    $(Self::$action => &[$($chord),*]),*
+                }
+            }
+        }
+    };
+}
+
+foximg_actions! {
+    /// Zooms in by 0.5 while held.
+    ZoomIn: "zoom_in" => ["W"],
+    /// Zooms out by 0.5 while held.
+    ZoomOut: "zoom_out" => ["S"],
+    /// Zooms in by 0.1 while held.
+    ZoomInSlow: "zoom_in_slow" => ["Ctrl+W"],
+    /// Zooms out by 0.1 while held.
+    ZoomOutSlow: "zoom_out_slow" => ["Ctrl+S"],
+    /// Pans while held and zoomed in. Holding Ctrl as well pans at half the speed.
+    PanUp: "pan_up" => ["K", "Up"],
+    PanDown: "pan_down" => ["J", "Down"],
+    PanLeft: "pan_left" => ["H", "Left"],
+    PanRight: "pan_right" => ["L", "Right"],
+    Rotate90: "rotate_90" => ["E"],
+    RotateN90: "rotate_n90" => ["Q"],
+    /// Rotates by 1 deg while held.
+    Rotate1: "rotate_1" => ["Ctrl+E"],
+    /// Rotates by -1 deg while held.
+    RotateN1: "rotate_n1" => ["Ctrl+Q"],
+    FlipHorizontal: "flip_horizontal" => ["Shift+Q"],
+    FlipVertical: "flip_vertical" => ["Shift+E"],
+    Undo: "undo" => ["Ctrl+Z"],
+    Redo: "redo" => ["Ctrl+Shift+Z", "Ctrl+Y"],
+    PreviousImage: "previous_image" => ["A", "MouseBack"],
+    NextImage: "next_image" => ["D", "MouseForward"],
+    FirstImage: "first_image" => ["0"],
+    LastImage: "last_image" => ["Shift+4"],
+    /// Goes to the image whose number was typed before it.
+    GoTo: "go_to" => ["G"],
+    Find: "find" => ["Ctrl+P"],
+    GridView: "grid_view" => ["Tab"],
+    Filmstrip: "filmstrip" => ["F"],
+    Slideshow: "slideshow" => ["Space"],
+    Compare: "compare" => ["C"],
+    CompareLayout: "compare_layout" => ["Shift+C"],
+    DiffAmplificationUp: "diff_amplification_up" => ["]"],
+    DiffAmplificationDown: "diff_amplification_down" => ["["],
+    DiffThresholdUp: "diff_threshold_up" => ["Shift+]"],
+    DiffThresholdDown: "diff_threshold_down" => ["Shift+["],
+    OnionSkin: "onion_skin" => ["O"],
+    OnionSource: "onion_source" => ["Shift+O"],
+    Pause: "pause" => ["P"],
+    PreviousFrame: "previous_frame" => [","],
+    NextFrame: "next_frame" => ["."],
+    Histogram: "histogram" => ["I"],
+    HistogramScale: "histogram_scale" => ["Shift+I"],
+    Clipping: "clipping" => ["X"],
+    Peaking: "focus_peaking" => ["Z"],
+    NextChannel: "next_channel" => ["V"],
+    PreviousChannel: "previous_channel" => ["Shift+V"],
+    RedChannel: "red_channel" => ["Alt+R"],
+    GreenChannel: "green_channel" => ["Alt+G"],
+    BlueChannel: "blue_channel" => ["Alt+B"],
+    AlphaChannel: "alpha_channel" => ["Alt+A"],
+    AdjustColors: "adjust_colors" => ["B"],
+    Resize: "resize" => ["R"],
+    Rename: "rename" => ["F2"],
+    RenameMany: "rename_many" => ["Shift+F2"],
+    Rate0: "rate_0" => ["Ctrl+0"],
+    Rate1: "rate_1" => ["Ctrl+1"],
+    Rate2: "rate_2" => ["Ctrl+2"],
+    Rate3: "rate_3" => ["Ctrl+3"],
+    Rate4: "rate_4" => ["Ctrl+4"],
+    Rate5: "rate_5" => ["Ctrl+5"],
+    /// Takes away the colour label of the current image.
+    ClearLabel: "clear_label" => ["Ctrl+Shift+0"],
+    /// Gives the current image a colour label, or takes it away if it already has it.
+    LabelRed: "label_red" => ["Ctrl+Shift+1"],
+    LabelYellow: "label_yellow" => ["Ctrl+Shift+2"],
+    LabelGreen: "label_green" => ["Ctrl+Shift+3"],
+    LabelBlue: "label_blue" => ["Ctrl+Shift+4"],
+    LabelPurple: "label_purple" => ["Ctrl+Shift+5"],
+    Tags: "tags" => ["Ctrl+T"],
+    Mark: "mark" => ["M"],
+    Trash: "trash" => ["Del"],
+    /// Sends the current image to the destination with the same number in "foximg_culling.toml".
+    Cull1: "cull_1" => ["Alt+1"],
+    Cull2: "cull_2" => ["Alt+2"],
+    Cull3: "cull_3" => ["Alt+3"],
+    Cull4: "cull_4" => ["Alt+4"],
+    Cull5: "cull_5" => ["Alt+5"],
+    Cull6: "cull_6" => ["Alt+6"],
+    Cull7: "cull_7" => ["Alt+7"],
+    Cull8: "cull_8" => ["Alt+8"],
+    Cull9: "cull_9" => ["Alt+9"],
+    UndoFileOp: "undo_file_op" => ["U"],
+    Filter: "filter" => ["/"],
+    Settings: "settings" => ["Ctrl+,"],
+    Fullscreen: "toggle_fullscreen" => ["F11"],
+    /// Saves the edits of an image into a copy of it when asked about them.
+    SaveEdits: "save_edits" => ["S", "Enter"],
+    /// Discards the edits of an image when asked about them.
+    DiscardEdits: "discard_edits" => ["D"],
+    /// Goes back to an edited image when asked about its edits.
+    KeepEdits: "keep_edits" => ["Esc"],
+    /// Trashes an image when asked whether to.
+    ConfirmTrash: "confirm_trash" => ["Y", "Enter"],
+    /// Keeps an image when asked whether to trash it.
+    CancelTrash: "cancel_trash" => ["N", "Esc"],
+}
+
+/// Where an action is used. Prompts take over the keyboard while they're shown, so their actions
+/// only conflict with the other actions of the same prompt.
+#[derive(Clone, Copy, PartialEq)]
+enum FoximgActionScope {
+    Viewer,
+    EditsPrompt,
+    TrashPrompt,
+}
+
+impl FoximgAction {
+    const fn scope(self) -> FoximgActionScope {
+        match self {
+            Self::SaveEdits | Self::DiscardEdits | Self::KeepEdits => {
+                FoximgActionScope::EditsPrompt
+            }
+            Self::ConfirmTrash | Self::CancelTrash => FoximgActionScope::TrashPrompt,
+            _ => FoximgActionScope::Viewer,
+        }
+    }
+}
+
+/// The names keys are written with in chords, which is also how menus show them.
+const KEYS: &[(&str, KeyboardKey)] = &[
+    ("A", KeyboardKey::KEY_A),
+    ("B", KeyboardKey::KEY_B),
+    ("C", KeyboardKey::KEY_C),
+    ("D", KeyboardKey::KEY_D),
+    ("E", KeyboardKey::KEY_E),
+    ("F", KeyboardKey::KEY_F),
+    ("G", KeyboardKey::KEY_G),
+    ("H", KeyboardKey::KEY_H),
+    ("I", KeyboardKey::KEY_I),
+    ("J", KeyboardKey::KEY_J),
+    ("K", KeyboardKey::KEY_K),
+    ("L", KeyboardKey::KEY_L),
+    ("M", KeyboardKey::KEY_M),
+    ("N", KeyboardKey::KEY_N),
+    ("O", KeyboardKey::KEY_O),
+    ("P", KeyboardKey::KEY_P),
+    ("Q", KeyboardKey::KEY_Q),
+    ("R", KeyboardKey::KEY_R),
+    ("S", KeyboardKey::KEY_S),
+    ("T", KeyboardKey::KEY_T),
+    ("U", KeyboardKey::KEY_U),
+    ("V", KeyboardKey::KEY_V),
+    ("W", KeyboardKey::KEY_W),
+    ("X", KeyboardKey::KEY_X),
+    ("Y", KeyboardKey::KEY_Y),
+    ("Z", KeyboardKey::KEY_Z),
+    ("0", KeyboardKey::KEY_ZERO),
+    ("1", KeyboardKey::KEY_ONE),
+    ("2", KeyboardKey::KEY_TWO),
+    ("3", KeyboardKey::KEY_THREE),
+    ("4", KeyboardKey::KEY_FOUR),
+    ("5", KeyboardKey::KEY_FIVE),
+    ("6", KeyboardKey::KEY_SIX),
+    ("7", KeyboardKey::KEY_SEVEN),
+    ("8", KeyboardKey::KEY_EIGHT),
+    ("9", KeyboardKey::KEY_NINE),
+    ("F1", KeyboardKey::KEY_F1),
+    ("F2", KeyboardKey::KEY_F2),
+    ("F3", KeyboardKey::KEY_F3),
+    ("F4", KeyboardKey::KEY_F4),
+    ("F5", KeyboardKey::KEY_F5),
+    ("F6", KeyboardKey::KEY_F6),
+    ("F7", KeyboardKey::KEY_F7),
+    ("F8", KeyboardKey::KEY_F8),
+    ("F9", KeyboardKey::KEY_F9),
+    ("F10", KeyboardKey::KEY_F10),
+    ("F11", KeyboardKey::KEY_F11),
+    ("F12", KeyboardKey::KEY_F12),
+    ("Space", KeyboardKey::KEY_SPACE),
+    ("Tab", KeyboardKey::KEY_TAB),
+    ("Enter", KeyboardKey::KEY_ENTER),
+    ("Esc", KeyboardKey::KEY_ESCAPE),
+    ("Backspace", KeyboardKey::KEY_BACKSPACE),
+    ("Ins", KeyboardKey::KEY_INSERT),
+    ("Del", KeyboardKey::KEY_DELETE),
+    ("Home", KeyboardKey::KEY_HOME),
+    ("End", KeyboardKey::KEY_END),
+    ("PageUp", KeyboardKey::KEY_PAGE_UP),
+    ("PageDown", KeyboardKey::KEY_PAGE_DOWN),
+    ("Up", KeyboardKey::KEY_UP),
+    ("Down", KeyboardKey::KEY_DOWN),
+    ("Left", KeyboardKey::KEY_LEFT),
+    ("Right", KeyboardKey::KEY_RIGHT),
+    (",", KeyboardKey::KEY_COMMA),
+    (".", KeyboardKey::KEY_PERIOD),
+    ("/", KeyboardKey::KEY_SLASH),
+    ("\\", KeyboardKey::KEY_BACKSLASH),
+    (";", KeyboardKey::KEY_SEMICOLON),
+    ("'", KeyboardKey::KEY_APOSTROPHE),
+    ("-", KeyboardKey::KEY_MINUS),
+    ("=", KeyboardKey::KEY_EQUAL),
+    ("[", KeyboardKey::KEY_LEFT_BRACKET),
+    ("]", KeyboardKey::KEY_RIGHT_BRACKET),
+    ("`", KeyboardKey::KEY_GRAVE),
+    ("Num0", KeyboardKey::KEY_KP_0),
+    ("Num1", KeyboardKey::KEY_KP_1),
+    ("Num2", KeyboardKey::KEY_KP_2),
+    ("Num3", KeyboardKey::KEY_KP_3),
+    ("Num4", KeyboardKey::KEY_KP_4),
+    ("Num5", KeyboardKey::KEY_KP_5),
+    ("Num6", KeyboardKey::KEY_KP_6),
+    ("Num7", KeyboardKey::KEY_KP_7),
+    ("Num8", KeyboardKey::KEY_KP_8),
+    ("Num9", KeyboardKey::KEY_KP_9),
+    ("Num.", KeyboardKey::KEY_KP_DECIMAL),
+    ("Num/", KeyboardKey::KEY_KP_DIVIDE),
+    ("Num*", KeyboardKey::KEY_KP_MULTIPLY),
+    ("Num-", KeyboardKey::KEY_KP_SUBTRACT),
+    ("Num+", KeyboardKey::KEY_KP_ADD),
+    ("NumEnter", KeyboardKey::KEY_KP_ENTER),
+];
+
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+    ("MouseLeft", MouseButton::MOUSE_BUTTON_LEFT),
+    ("MouseRight", MouseButton::MOUSE_BUTTON_RIGHT),
+    ("MouseMiddle", MouseButton::MOUSE_BUTTON_MIDDLE),
+    ("MouseSide", MouseButton::MOUSE_BUTTON_SIDE),
+    ("MouseExtra", MouseButton::MOUSE_BUTTON_EXTRA),
+    ("MouseForward", MouseButton::MOUSE_BUTTON_FORWARD),
+    ("MouseBack", MouseButton::MOUSE_BUTTON_BACK),
+];
+
+/// Returns true if either the left or right one of two modifier keys is held down.
+fn modifier_down(rl: &RaylibHandle, left: KeyboardKey, right: KeyboardKey) -> bool {
+    rl.is_key_down(left) || rl.is_key_down(right)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FoximgInput {
+    Key(KeyboardKey),
+    Mouse(MouseButton),
+}
+
+/// A key or mouse button, and the modifiers that must be held down with it.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct FoximgChord {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub input: FoximgInput,
+}
+
+impl FoximgChord {
+    /// Returns true if the modifiers of the chord are the ones held down. A chord without Ctrl
+    /// still matches while it's held if `any_ctrl` is set.
+    fn modifiers_match(&self, rl: &RaylibHandle, any_ctrl: bool) -> bool {
+        let ctrl = modifier_down(
+            rl,
+            KeyboardKey::KEY_LEFT_CONTROL,
+            KeyboardKey::KEY_RIGHT_CONTROL,
+        );
+        let shift = modifier_down(
+            rl,
+            KeyboardKey::KEY_LEFT_SHIFT,
+            KeyboardKey::KEY_RIGHT_SHIFT,
+        );
+        let alt = modifier_down(rl, KeyboardKey::KEY_LEFT_ALT, KeyboardKey::KEY_RIGHT_ALT);
+
+        (self.ctrl == ctrl || (any_ctrl && !self.ctrl)) && self.shift == shift && self.alt == alt
+    }
+
+    pub fn is_pressed(&self, rl: &RaylibHandle) -> bool {
+        self.modifiers_match(rl, false)
+            && match self.input {
+                FoximgInput::Key(key) => rl.is_key_pressed(key),
+                FoximgInput::Mouse(button) => rl.is_mouse_button_pressed(button),
+            }
+    }
+
+    /// Like `is_pressed`, but also returns true while the key is being held down and repeats.
+    pub fn is_pressed_or_repeat(&self, rl: &RaylibHandle) -> bool {
+        self.modifiers_match(rl, false)
+            && match self.input {
+                FoximgInput::Key(key) => rl.is_key_pressed(key) || rl.is_key_pressed_repeat(key),
+                FoximgInput::Mouse(button) => rl.is_mouse_button_pressed(button),
+            }
+    }
+
+    pub fn is_down(&self, rl: &RaylibHandle, any_ctrl: bool) -> bool {
+        self.modifiers_match(rl, any_ctrl)
+            && match self.input {
+                FoximgInput::Key(key) => rl.is_key_down(key),
+                FoximgInput::Mouse(button) => rl.is_mouse_button_down(button),
+            }
+    }
+
+    /// Returns true if the chord is used by foximg for something that can't be bound in `scope`.
+    /// These are the left and right mouse buttons everywhere. In the viewer, they're also Escape and
+    /// Backspace, and the numbers that are typed before skipping images.
+    fn is_reserved(&self, scope: FoximgActionScope) -> bool {
+        let key = match self.input {
+            FoximgInput::Mouse(button) => {
+                return matches!(
+                    button,
+                    MouseButton::MOUSE_BUTTON_LEFT | MouseButton::MOUSE_BUTTON_RIGHT
+                );
+            }
+            FoximgInput::Key(_) if scope != FoximgActionScope::Viewer => return false,
+            FoximgInput::Key(key) => key,
+        };
+
+        let plain = !self.ctrl && !self.shift && !self.alt;
+        let n = key as u32;
+        let (zero, nine) = (KeyboardKey::KEY_ZERO as u32, KeyboardKey::KEY_NINE as u32);
+
+        plain
+            && (matches!(key, KeyboardKey::KEY_ESCAPE | KeyboardKey::KEY_BACKSPACE)
+                || (n > zero && n <= nine))
+    }
+}
+
+impl fmt::Display for FoximgChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+
+        let name = match self.input {
+            FoximgInput::Key(key) => KEYS.iter().find(|&&(_, k)| k == key).map(|&(name, _)| name),
+            FoximgInput::Mouse(button) => MOUSE_BUTTONS
+                .iter()
+                .find(|&&(_, b)| b == button)
+                .map(|&(name, _)| name),
+        };
+        write!(f, "{}", name.unwrap_or("?"))
+    }
+}
+
+impl FromStr for FoximgChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        let mut rest = s.trim();
+        // Modifiers are stripped from the front, so that keys like "Num+" still parse.
+        while let Some((modifier, after)) =
+            rest.split_once('+').filter(|(_, after)| !after.is_empty())
+        {
+            match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ctrl = true,
+                "shift" => shift = true,
+                "alt" => alt = true,
+                _ => break,
+            }
+            rest = after.trim_start();
+        }
+
+        let input = KEYS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(rest))
+            .map(|&(_, key)| FoximgInput::Key(key))
+            .or_else(|| {
+                MOUSE_BUTTONS
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(rest))
+                    .map(|&(_, button)| FoximgInput::Mouse(button))
+            })
+            .ok_or_else(|| format!("\"{rest}\" isn't a key or mouse button in \"{s}\""))?;
+
+        Ok(Self {
+            ctrl,
+            shift,
+            alt,
+            input,
+        })
+    }
+}
+
+impl TryFrom<String> for FoximgChord {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<FoximgChord> for String {
+    fn from(chord: FoximgChord) -> Self {
+        chord.to_string()
+    }
+}
+
+/// Two actions that were bound to the same chord, or an action bound to a reserved chord if
+/// `with` is `None`. Whichever of them was changed from its defaults falls back to them.
+#[derive(Clone, Copy)]
+struct FoximgConflict {
+    chord: FoximgChord,
+    action: FoximgAction,
+    with: Option<FoximgAction>,
+}
+
+/// Keybinds are read one entry at a time, so that an unknown action or an invalid chord only leaves
+/// itself out.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(
+    from = "BTreeMap<String, toml::Value>",
+    into = "BTreeMap<FoximgAction, Vec<FoximgChord>>"
+)]
+pub struct FoximgKeybinds {
+    binds: BTreeMap<FoximgAction, Vec<FoximgChord>>,
+    /// What the menus show as the shortcut of each action, which is its first chord.
+    labels: BTreeMap<FoximgAction, String>,
+    conflicts: Vec<FoximgConflict>,
+    /// The entries that were left out of the file, and why.
+    invalid: Vec<String>,
+}
+
+impl Default for FoximgKeybinds {
+    fn default() -> Self {
+        Self::from(BTreeMap::<FoximgAction, Vec<FoximgChord>>::new())
+    }
+}
+
+impl From<BTreeMap<FoximgAction, Vec<FoximgChord>>> for FoximgKeybinds {
+    fn from(mut binds: BTreeMap<FoximgAction, Vec<FoximgChord>>) -> Self {
+        let defaults = |action: FoximgAction| -> Vec<FoximgChord> {
+            action
+                .defaults()
+                .iter()
+                .map(|chord| chord.parse().expect("default keybinds should parse"))
+                .collect()
+        };
+
+        for &action in FoximgAction::ALL {
+            binds.entry(action).or_insert_with(|| defaults(action));
+        }
+
+        let mut conflicts = Vec::new();
+        while let Some(conflict) = self::find_conflict(&binds) {
+            let mut reset = false;
+            for action in [Some(conflict.action), conflict.with].into_iter().flatten() {
+                let default = defaults(action);
+                if binds[&action] != default {
+                    binds.insert(action, default);
+                    reset = true;
+                }
+            }
+
+            conflicts.push(conflict);
+            // Neither action was changed, so there's nothing left to fall back to.
+            if !reset {
+                break;
+            }
+        }
+
+        let labels = binds
+            .iter()
+            .filter_map(|(&action, chords)| Some((action, chords.first()?.to_string())))
+            .collect();
+
+        Self {
+            binds,
+            labels,
+            conflicts,
+            invalid: Vec::new(),
+        }
+    }
+}
+
+impl From<BTreeMap<String, toml::Value>> for FoximgKeybinds {
+    fn from(file: BTreeMap<String, toml::Value>) -> Self {
+        let mut binds = BTreeMap::new();
+        let mut invalid = Vec::new();
+        for (name, chords) in file {
+            let Some(&action) = FoximgAction::ALL
+                .iter()
+                .find(|action| action.name() == name)
+            else {
+                invalid.push(format!("\"{name}\" isn't an action. Ignoring it"));
+                continue;
+            };
+
+            let chords = match chords {
+                toml::Value::Array(chords) => chords,
+                chord => vec![chord],
+            };
+            let len = chords.len();
+            let chords: Vec<FoximgChord> = chords
+                .into_iter()
+                .filter_map(|chord| match chord.as_str().map(str::parse) {
+                    Some(Ok(chord)) => Some(chord),
+                    Some(Err(e)) => {
+                        invalid.push(format!("{e}. Ignoring it"));
+                        None
+                    }
+                    None => {
+                        invalid.push(format!("{chord} isn't a chord of \"{name}\". Ignoring it"));
+                        None
+                    }
+                })
+                .collect();
+
+            // An action that was left without any chords keeps its defaults, unless it was
+            // unbound on purpose.
+            if chords.is_empty() && len > 0 {
+                continue;
+            }
+            binds.insert(action, chords);
+        }
+
+        let mut keybinds = Self::from(binds);
+        keybinds.invalid = invalid;
+        keybinds
+    }
+}
+
+impl From<FoximgKeybinds> for BTreeMap<FoximgAction, Vec<FoximgChord>> {
+    fn from(keybinds: FoximgKeybinds) -> Self {
+        keybinds.binds
+    }
+}
+
+/// Returns the first chord that's bound to more than one action in the same scope, or is reserved.
+fn find_conflict(binds: &BTreeMap<FoximgAction, Vec<FoximgChord>>) -> Option<FoximgConflict> {
+    let mut bound: Vec<(FoximgChord, FoximgAction)> = Vec::new();
+    for (&action, chords) in binds {
+        for &chord in chords {
+            if chord.is_reserved(action.scope()) {
+                return Some(FoximgConflict {
+                    chord,
+                    action,
+                    with: None,
+                });
+            }
+
+            match bound
+                .iter()
+                .find(|&&(other, with)| other == chord && with.scope() == action.scope())
+            {
+                Some(&(_, with)) if with != action => {
+                    return Some(FoximgConflict {
+                        chord,
+                        action,
+                        with: Some(with),
+                    });
+                }
+                _ => bound.push((chord, action)),
+            }
+        }
+    }
+    None
+}
+
+impl FoximgKeybinds {
+    fn chords(&self, action: FoximgAction) -> &[FoximgChord] {
+        self.binds.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the shortcut shown for `action` in menus, or `None` if it isn't bound.
+    pub fn label(&self, action: FoximgAction) -> Option<&str> {
+        self.labels.get(&action).map(String::as_str)
+    }
+}
+
+impl FoximgConfig for FoximgKeybinds {
+    const FILE: &str = "foximg_keybinds.toml";
+    const LOCAL: bool = false;
+
+    fn update(&self, rl: &mut RaylibHandle) {
+        rl.trace_log(
+            TraceLogLevel::LOG_INFO,
+            &format!("    > actions: {}", self.binds.len()),
+        );
+
+        for problem in self.problems() {
+            rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("FOXIMG: {problem}"));
+        }
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let conflicts = self.conflicts.iter().map(|conflict| match conflict.with {
+            Some(with) => format!(
+                "\"{}\" is bound to both \"{}\" and \"{}\". Falling back to defaults",
+                conflict.chord,
+                conflict.action.name(),
+                with.name(),
+            ),
+            None => format!(
+                "\"{}\" can't be bound to \"{}\". Falling back to defaults",
+                conflict.chord,
+                conflict.action.name(),
+            ),
+        });
+        self.invalid.iter().cloned().chain(conflicts).collect()
+    }
+}
+
+impl Foximg {
+    /// Returns true if a chord `action` is bound to was just pressed.
+    pub(crate) fn is_action_pressed(&self, action: FoximgAction) -> bool {
+        self.keybinds
+            .chords(action)
+            .iter()
+            .any(|chord| chord.is_pressed(&self.rl))
+    }
+
+    /// Returns the index of the first of `actions` that was just pressed.
+    pub(crate) fn which_action_pressed(&self, actions: &[FoximgAction]) -> Option<usize> {
+        actions
+            .iter()
+            .position(|&action| self.is_action_pressed(action))
+    }
+
+    /// Returns true if a chord `action` is bound to was just pressed or is being held down.
+    pub(crate) fn is_action_pressed_or_repeat(&self, action: FoximgAction) -> bool {
+        self.keybinds
+            .chords(action)
+            .iter()
+            .any(|chord| chord.is_pressed_or_repeat(&self.rl))
+    }
+
+    /// Returns true if a chord `action` is bound to is held down. Chords without Ctrl still count
+    /// while it's held if `any_ctrl` is set.
+    pub(crate) fn is_action_down(&self, action: FoximgAction, any_ctrl: bool) -> bool {
+        self.keybinds
+            .chords(action)
+            .iter()
+            .any(|chord| chord.is_down(&self.rl, any_ctrl))
+    }
+}
@@ -5,22 +5,58 @@ use std::{
 };
 
 use aho_corasick::{AhoCorasick, MatchKind};
-use config::{FoximgConfig, FoximgIcon, /* FoximgSettings , */FoximgState, FoximgStyle};
+use channels::FoximgChannelView;
+use compare::FoximgCompare;
+use config::{FoximgCompareSettings, FoximgConfig, FoximgConfigWatcher, FoximgCullSettings, FoximgHistogramSettings, FoximgIcon, FoximgOnionSettings, FoximgOverlaySettings, FoximgSettings, FoximgSlideshowSettings, FoximgState, FoximgStyle, FoximgTrashSettings};
+use filmstrip::FoximgFilmstrip;
+use filter::{FoximgFilter, FoximgFilterScan};
 use foximg_log::FoximgLogOut;
+use grid::FoximgGrid;
+use histogram::FoximgHistogramPanel;
+use image::imageops::FilterType;
 use images::FoximgImages;
+use keybinds::{FoximgAction, FoximgKeybinds};
 use menu::FoximgMenu;
+use onion::FoximgOnion;
+use overlays::FoximgOverlays;
 use raylib::prelude::*;
+use slideshow::FoximgSlideshow;
+use culling::FoximgFileOp;
 use resources::FoximgResources;
+use toast::FoximgToasts;
 
 use crate::images::FoximgImage;
 
+mod adjust;
+mod channels;
 mod cli;
+mod compare;
 mod config;
 mod controls;
+mod diff;
+mod filter;
+mod finder;
+mod culling;
+mod filmstrip;
 mod foximg_log;
+mod grid;
+mod histogram;
 mod images;
+mod keybinds;
 mod menu;
+mod onion;
+mod overlays;
+mod prompt;
+mod rename;
+mod resize;
 mod resources;
+mod settings;
+mod slideshow;
+mod thumbnail;
+mod toast;
+mod trash;
+mod xdg;
+mod xmp;
 
 struct FoximgInstance {
     path: PathBuf,
@@ -197,16 +233,19 @@ impl FoximgBtnsBounds {
     /// Get the mouse position using [`get_mouse_position`].
     ///
     /// [`get_mouse_position`]: raylib::core::window::RaylibHandle::get_mouse_position
-    pub fn new(rl: &RaylibHandle, mouse_pos: Vector2) -> Self {
+    ///
+    /// `bottom` is the height of the area along the bottom edge of the window the buttons must not
+    /// cover, such as the filmstrip.
+    pub fn new(rl: &RaylibHandle, mouse_pos: Vector2, bottom: f32) -> Self {
         let window_width = rl.get_screen_width().as_f32();
-        let window_height = rl.get_screen_height().as_f32();
+        let btn_height = rl.get_screen_height().as_f32() - bottom;
         let btn_width = window_width / 6.;
         let right_btn_x = window_width - btn_width;
-        let mouse_on_left_btn = mouse_pos.x < btn_width;
-        let mouse_on_right_btn = mouse_pos.x > right_btn_x;
+        let mouse_on_left_btn = mouse_pos.x < btn_width && mouse_pos.y < btn_height;
+        let mouse_on_right_btn = mouse_pos.x > right_btn_x && mouse_pos.y < btn_height;
 
         Self {
-            btn_height: window_height,
+            btn_height,
             btn_width,
             right_btn_x,
             mouse_on_left_btn,
@@ -239,6 +278,22 @@ struct FoximgDraw<'a> {
     style: &'a FoximgStyle,
     state: &'a FoximgState,
     resources: &'a FoximgResources,
+    keybinds: &'a FoximgKeybinds,
+    grid: Option<&'a FoximgGrid>,
+    filmstrip: Option<&'a FoximgFilmstrip>,
+    slideshow: Option<&'a FoximgSlideshow>,
+    slideshow_settings: &'a FoximgSlideshowSettings,
+    compare: Option<&'a FoximgCompare>,
+    compare_settings: &'a FoximgCompareSettings,
+    onion: Option<&'a FoximgOnion>,
+    onion_settings: &'a FoximgOnionSettings,
+    histogram: Option<&'a FoximgHistogramPanel>,
+    histogram_settings: &'a FoximgHistogramSettings,
+    overlays: FoximgOverlays,
+    channel_view: FoximgChannelView,
+    trash_settings: &'a FoximgTrashSettings,
+    toasts: &'a FoximgToasts,
+    filter: &'a FoximgFilter,
     mouse_wheel: &'a mut f32,
     camera: &'a mut Camera2D,
     skip_count: &'a str,
@@ -311,6 +366,22 @@ impl<'a> FoximgDraw<'a> {
         );
     }
 
+    /// Returns the scale `img` is drawn at to fit the screen.
+    fn img_scale(&self, img: &FoximgImage, screen_width: f32, screen_height: f32) -> f32 {
+        if self.scaleto {
+            return 1.;
+        }
+
+        let screen_ratio = screen_width / screen_height;
+        let texture_ratio = img.width().as_f32() / img.height().as_f32();
+
+        if screen_ratio > texture_ratio {
+            screen_height / img.height().as_f32()
+        } else {
+            screen_width / img.width().as_f32()
+        }
+    }
+
     pub fn draw_current_img(&mut self, images: &mut FoximgImages) {
         let Some(img) = images.img_get(&mut self.d, self.rl_thread) else {
             self.draw_large_centered_text(":(");
@@ -319,29 +390,61 @@ impl<'a> FoximgDraw<'a> {
 
         img.borrow_mut().update_texture(&self.d);
         let img = img.borrow();
+        let overlay = self.onion_overlay(images, &img);
+        let overlay = overlay.as_ref().map(|overlay| overlay.borrow());
+        let (tint, overlay_tint) = self.onion_tints();
+        let shaders = self.overlay_shaders();
+        let channels = self.channel_shader(&img);
 
         let screen_width = self.d.get_screen_width().as_f32();
-        let screen_height = self.d.get_screen_height().as_f32();
-        let scale = if self.scaleto { 1. } else {
-            let screen_ratio = screen_width / screen_height;
-            let texture_ratio = img.width().as_f32() / img.height().as_f32();
-
-            if screen_ratio > texture_ratio {
-                screen_height / img.height().as_f32()
-            } else {
-                screen_height / img.width().as_f32()
-            }
-        };
+        let screen_height = self.d.get_screen_height().as_f32() - self.filmstrip_height();
+        let scale = self.img_scale(&img, screen_width, screen_height);
+        let overlay_scale = overlay
+            .as_ref()
+            .map(|overlay| self.img_scale(overlay, screen_width, screen_height));
 
         if *self.mouse_wheel > 0. {
             let mut c = self.d.begin_mode2D(*self.camera);
-            img.draw_center_scaled(&mut c, screen_width, screen_height, scale);
+            img.draw_center_shaded(&mut c, channels, screen_width, screen_height, scale, tint);
+            if let (Some(overlay), Some(overlay_scale)) = (&overlay, overlay_scale) {
+                overlay.draw_like(
+                    &mut c,
+                    &img,
+                    screen_width,
+                    screen_height,
+                    overlay_scale,
+                    false,
+                    overlay_tint,
+                );
+            }
+            for shader in &shaders {
+                let mut shader = shader.borrow_mut();
+                let mut s = c.begin_shader_mode(&mut shader);
+                img.draw_center_scaled(&mut s, screen_width, screen_height, scale, Color::WHITE);
+            }
         } else {
             *self.camera = Camera2D {
                 zoom: 1.,
                 ..Default::default()
             };
-            img.draw_center_scaled(&mut self.d, screen_width, screen_height, scale);
+            img.draw_center_shaded(&mut self.d, channels, screen_width, screen_height, scale, tint);
+            if let (Some(overlay), Some(overlay_scale)) = (&overlay, overlay_scale) {
+                overlay.draw_like(
+                    &mut self.d,
+                    &img,
+                    screen_width,
+                    screen_height,
+                    overlay_scale,
+                    false,
+                    overlay_tint,
+                );
+            }
+            for shader in &shaders {
+                let mut shader = shader.borrow_mut();
+                let mut s = self.d.begin_shader_mode(&mut shader);
+                img.draw_center_scaled(&mut s, screen_width, screen_height, scale, Color::WHITE);
+            }
+            self.draw_slideshow_fade(screen_width, screen_height);
         }
         img.draw_manipulation_info(
             &mut self.d,
@@ -349,10 +452,28 @@ impl<'a> FoximgDraw<'a> {
             self.style,
             screen_width,
             screen_height,
+            self.channel_view.label(),
         );
 
         self.draw_fullscreen_title();
         self.draw_skip_count(&img, screen_width, screen_height);
+        let current = images.img_current() - 1;
+        if images.marked(current) {
+            self.draw_mark(rrect(0, 0, screen_width, screen_height));
+        }
+        self.draw_xmp(images.xmp(current));
+    }
+
+    /// Draws the dot in the top right corner of `rect` that shows an image is marked.
+    pub fn draw_mark(&mut self, rect: Rectangle) {
+        const RADIUS: f32 = 6.;
+        const MARGIN: f32 = 6.;
+
+        self.d.draw_circle_v(
+            rvec2(rect.x + rect.width - RADIUS - MARGIN, rect.y + RADIUS + MARGIN),
+            RADIUS,
+            self.style.accent,
+        );
     }
 
     fn draw_btns(&mut self, images: &mut FoximgImages) {
@@ -391,12 +512,29 @@ impl<'a> FoximgDraw<'a> {
         foximg: &'a mut Foximg,
         f: impl FnOnce(FoximgDraw<'a>, Option<&'a mut Box<FoximgImages>>),
     ) {
+        let filmstrip_shown = foximg.filmstrip_shown();
         let d = foximg.rl.begin_drawing(&foximg.rl_thread);
         let mut d = Self {
             d,
             style: &foximg.style,
             state: &foximg.state,
             resources: &foximg.resources,
+            keybinds: &foximg.keybinds,
+            grid: foximg.grid.as_ref(),
+            filmstrip: foximg.filmstrip.as_ref().filter(|_| filmstrip_shown),
+            slideshow: foximg.slideshow.as_ref(),
+            slideshow_settings: &foximg.slideshow_settings,
+            compare: foximg.compare.as_ref(),
+            compare_settings: &foximg.compare_settings,
+            onion: foximg.onion.as_ref(),
+            onion_settings: &foximg.onion_settings,
+            histogram: foximg.histogram.as_ref(),
+            histogram_settings: &foximg.histogram_settings,
+            overlays: foximg.overlays,
+            channel_view: foximg.channel_view,
+            trash_settings: &foximg.trash_settings,
+            toasts: &foximg.toasts,
+            filter: &foximg.filter,
             mouse_wheel: &mut foximg.mouse_wheel,
             camera: &mut foximg.camera,
             skip_count: &foximg.skip_count,
@@ -422,10 +560,34 @@ impl<'a> FoximgDraw<'a> {
 
 pub struct Foximg {
     style: FoximgStyle,
+    /// The theme library, kept up to date by the config watcher.
+    themes: Vec<FoximgStyle>,
     state: FoximgState,
-    // settings: FoximgSettings,
+    settings: FoximgSettings,
+    config_watcher: FoximgConfigWatcher,
+    icon: FoximgIcon,
     resources: FoximgResources,
+    keybinds: FoximgKeybinds,
     images: Option<Box<FoximgImages>>,
+    grid: Option<FoximgGrid>,
+    filmstrip: Option<FoximgFilmstrip>,
+    slideshow: Option<FoximgSlideshow>,
+    slideshow_settings: FoximgSlideshowSettings,
+    compare: Option<FoximgCompare>,
+    compare_settings: FoximgCompareSettings,
+    onion: Option<FoximgOnion>,
+    onion_settings: FoximgOnionSettings,
+    histogram: Option<FoximgHistogramPanel>,
+    histogram_settings: FoximgHistogramSettings,
+    overlays: FoximgOverlays,
+    overlay_settings: FoximgOverlaySettings,
+    channel_view: FoximgChannelView,
+    file_ops: Vec<FoximgFileOp>,
+    trash_settings: FoximgTrashSettings,
+    cull_settings: FoximgCullSettings,
+    toasts: FoximgToasts,
+    filter: FoximgFilter,
+    filter_scan: Option<FoximgFilterScan>,
 
     mouse_pos: Vector2,
     btn_bounds: FoximgBtnsBounds,
@@ -478,7 +640,6 @@ impl Foximg {
         }
 
         let (mut rl, rl_thread) = rl_builder.build();
-        rl.set_window_state(rl.get_window_state().set_window_topmost(args.ontop));
         rl.set_exit_key(None);
         rl.set_target_fps(60);
 
@@ -495,14 +656,21 @@ impl Foximg {
         // Style must be initialized before state because on Windows the titlebar's color gets updated
         // only once it's resized. The window can't get resized if it's already maximized, so the
         // window appears in light mode on startup otherwise.
+        let settings = FoximgSettings::new(&mut rl);
+        let themes = FoximgStyle::library(&mut rl);
+        let watch_style = args.style.is_none();
         let style = args.style.take()
             .inspect(|style| {
                 rl.trace_log(TraceLogLevel::LOG_INFO, "Loaded style from arguments:");
                 style.update(&mut rl);
             })
-            .unwrap_or_else(|| FoximgStyle::new(&mut rl));
+            .unwrap_or_else(|| FoximgStyle::selected(&mut rl, &themes, &settings));
 
-        let state = if instance
+        if args.ontop {
+            rl.set_window_state(rl.get_window_state().set_window_topmost(true));
+        }
+
+        let state = if settings.remember_window && instance
             .as_ref()
             .is_some_and(|instance| matches!(instance.owner(), Ok(true)))
         {
@@ -514,18 +682,48 @@ impl Foximg {
             }).unwrap_or_default()
         };
 
-        // let settings = FoximgSettings::new(&mut rl);
+        let filmstrip = (state.filmstrip && args.lock.is_none()).then(|| FoximgFilmstrip::new(&rl));
+        let slideshow_settings = FoximgSlideshowSettings::new(&mut rl);
+        let slideshow = args.slideshow.filter(|_| args.lock.is_none()).map(FoximgSlideshow::new);
+        let compare_settings = FoximgCompareSettings::new(&mut rl);
+        let onion_settings = FoximgOnionSettings::new(&mut rl);
+        let histogram_settings = FoximgHistogramSettings::new(&mut rl);
+        let overlay_settings = FoximgOverlaySettings::new(&mut rl);
+        let trash_settings = FoximgTrashSettings::new(&mut rl);
+        let cull_settings = FoximgCullSettings::new(&mut rl);
+        let keybinds = FoximgKeybinds::new(&mut rl);
         let resources = FoximgResources::new(&mut rl, &rl_thread);
         let icon = FoximgIcon::new(&mut rl);
+        // Watching starts once every config file exists, so that creating them isn't a change.
+        let config_watcher = FoximgConfigWatcher::new(watch_style);
 
-        images::set_window_icon(&mut rl, &style, icon);
+        images::set_window_icon(&mut rl, &style, icon.clone());
         rl.trace_log(
             TraceLogLevel::LOG_INFO,
             "FOXIMG: Foximg initialized successfully",
         );
 
-        Self {
+        let mut foximg = Self {
             images: None,
+            grid: None,
+            filmstrip,
+            slideshow,
+            slideshow_settings,
+            compare: None,
+            compare_settings,
+            onion: None,
+            onion_settings,
+            histogram: None,
+            histogram_settings,
+            overlays: FoximgOverlays::default(),
+            overlay_settings,
+            channel_view: FoximgChannelView::default(),
+            file_ops: Vec::new(),
+            trash_settings,
+            cull_settings,
+            toasts: FoximgToasts::default(),
+            filter: args.filter.take().unwrap_or_default(),
+            filter_scan: None,
             mouse_pos: Vector2::zero(),
             btn_bounds: FoximgBtnsBounds::default(),
             mouse_wheel: 0.,
@@ -539,15 +737,21 @@ impl Foximg {
             undecorated: args.undecorated,
             scaleto: args.scaleto,
             state,
-            // settings,
+            settings,
+            config_watcher,
+            icon,
             style,
+            themes,
             resources,
+            keybinds,
             title_format,
             title,
             rl,
             rl_thread,
             instance,
-        }
+        };
+        foximg.update_overlay_shaders();
+        foximg
     }
 
     fn toggle_fullscreen(&mut self) {
@@ -564,17 +768,23 @@ impl Foximg {
             instsance.update(&self.rl);
         }
 
-        if self.rl.is_key_pressed(KeyboardKey::KEY_F11) {
+        if self.is_action_pressed(FoximgAction::Fullscreen) {
             self.toggle_fullscreen();
         }
 
+        self.reload_configs();
         self.mouse_pos = self.rl.get_mouse_position();
+        self.toasts.update(self.rl.get_frame_time());
+        self.update_adjust_shader();
     }
 
     fn get_dropped_img(&mut self) {
         if self.rl.is_file_dropped() {
             let files = self.rl.load_dropped_files();
             if let Some(path) = files.paths().first() {
+                if !self.check_all_edits(false) {
+                    return;
+                }
                 self.load_folder(path);
             }
         }
@@ -607,7 +817,32 @@ impl Foximg {
             Foximg::rotate_1_img,
             Foximg::rotate_n90_img,
             Foximg::rotate_90_img,
+            Foximg::undo_edit_img,
+            Foximg::channel_view_img,
             Foximg::update_gallery,
+            Foximg::grid_view,
+            Foximg::filmstrip_view,
+            Foximg::play_slideshow,
+            Foximg::compare_img,
+            Foximg::adjust_diff_img,
+            Foximg::onion_img,
+            Foximg::pause_img,
+            Foximg::step_frame_img,
+            Foximg::histogram_img,
+            Foximg::clipping_img,
+            Foximg::peaking_img,
+            Foximg::adjust_colors_key,
+            Foximg::resize_img_key,
+            Foximg::trash_img,
+            Foximg::undo_file_op_img,
+            Foximg::cull_img,
+            Foximg::rename_img_key,
+            Foximg::mark_img,
+            Foximg::rate_img,
+            Foximg::tag_img,
+            Foximg::filter_img,
+            Foximg::find_img_key,
+            Foximg::settings_key,
             Foximg::jump_to,
             Foximg::delete_skip,
             Foximg::escape_skip,
@@ -617,8 +852,10 @@ impl Foximg {
         ];
 
         POLL_IMG_EVENTS.iter().find(|event| event(self));
-        self.zoom_scroll_img();
-        self.pan_img();
+        if !self.mouse_on_filmstrip() && !self.compare_dragging() {
+            self.zoom_scroll_img();
+            self.pan_img();
+        }
         self.pan_img_up();
         self.pan_img_down();
         self.pan_img_left();
@@ -632,11 +869,24 @@ impl Foximg {
 
         while !self.rl.window_should_close() {
             self.update();
-            self.btn_bounds = FoximgBtnsBounds::new(&self.rl, self.mouse_pos);
+            self.btn_bounds =
+                FoximgBtnsBounds::new(&self.rl, self.mouse_pos, self.filmstrip_height());
             if let None | Some(FoximgLock::Images) = self.lock {
+                self.update_filter_scan();
+                let previous = self.images.as_ref().map(|images| images.img_path().to_path_buf());
                 self.get_dropped_img();
-                self.update_mouse_cursor();
-                self.manipulate_img();
+                if self.grid.is_some() {
+                    self.update_grid();
+                } else {
+                    self.update_mouse_cursor();
+                    self.update_onion();
+                    self.update_compare();
+                    self.update_histogram();
+                    self.update_filmstrip();
+                    self.manipulate_img();
+                    self.update_slideshow();
+                }
+                self.check_left_edits(previous);
     
                 if self
                     .rl
@@ -644,18 +894,32 @@ impl Foximg {
                 {
                     let keep_running = FoximgMenu::init(&mut self).run();
                     if !keep_running {
-                        return;
+                        break;
                     }
                 }
             }
 
             FoximgDraw::begin(&mut self, |mut d, images| {
                 if let Some(images) = images {
-                    d.draw_current_img(images);
-                    d.draw_btns(images);
+                    if d.grid.is_some() {
+                        d.draw_grid(images);
+                    } else {
+                        if d.compare.is_some() && d.onion.is_none() {
+                            d.draw_compare(images);
+                        } else {
+                            d.draw_current_img(images);
+                        }
+                        d.draw_histogram();
+                        d.draw_filmstrip(images);
+                        d.draw_btns(images);
+                    }
                 }
+
+                d.draw_toasts();
             });
         }
+
+        self.check_all_edits(true);
     }
 
     /// Do something mutably with the current images. Calls the closure only if theres images loaded.
@@ -712,10 +976,15 @@ pub fn format_title(
     const PATTERN_IMAGES_LEN: usize = 3;
     const PATTERN_IMAGES_CURRENT: usize = 4;
     const PATTERN_WIDTH: usize = 5;
-    const PATTERNS_LEN: usize = 8;
+    const PATTERN_RATING: usize = 8;
+    const PATTERN_LABEL: usize = 9;
+    const PATTERN_TAGS: usize = 10;
+    const PATTERNS_LEN: usize = 11;
 
     static AC: LazyLock<AhoCorasick> = LazyLock::new(|| {
-        static TITLE_PATTERNS: [&str; PATTERNS_LEN] = ["%f", "%h", "%n", "%l", "%u", "%w", "%v", "\\%"];
+        static TITLE_PATTERNS: [&str; PATTERNS_LEN] = [
+            "%f", "%h", "%n", "%l", "%u", "%w", "%v", "\\%", "%r", "%c", "%t",
+        ];
 
         AhoCorasick::builder()
             .match_kind(MatchKind::LeftmostFirst)
@@ -736,6 +1005,9 @@ pub fn format_title(
             env!("CARGO_PKG_VERSION")
         }.into(),
         "%".into(),
+        "0".into(),
+        String::new(),
+        String::new(),
     ];
 
     if let Some(ref mut images) = images {
@@ -754,6 +1026,11 @@ pub fn format_title(
             replace_with[PATTERN_HEIGHT] = img.height().to_string();
             replace_with[PATTERN_WIDTH] = img.width().to_string();
         });
+
+        let xmp = images.xmp(images.img_current() - 1);
+        replace_with[PATTERN_RATING] = xmp.rating.to_string();
+        replace_with[PATTERN_LABEL] = xmp.label.map(|label| label.name()).unwrap_or_default().into();
+        replace_with[PATTERN_TAGS] = xmp.tags.join(", ");
     }
 
     let title = AC.replace_all(title, &replace_with);
@@ -774,7 +1051,12 @@ enum FoximgInfoLanguage {
 
 enum FoximgMode {
     Help(Option<anyhow::Error>),
+    ConfigCheck,
     Info(FoximgInfoLanguage),
+    Diff(FoximgInfoLanguage),
+    Resize,
+    Thumbnail,
+    Thumbnailer,
     Version,
     Normal,
 }
@@ -791,9 +1073,19 @@ pub struct FoximgArgs<'a> {
     lock: Option<FoximgLock>,
     quiet: bool,
     scaleto: bool,
+    slideshow: Option<f32>,
+    filter: Option<FoximgFilter>,
     state: Option<FoximgState>,
     style: Option<FoximgStyle>,
     title: Option<&'a str>,
+    thumbnail_output: Option<&'a str>,
+    thumbnail_size: Option<&'a str>,
+    diff_path: Option<&'a str>,
+    diff_threshold: u8,
+    resize_output: Option<&'a str>,
+    resize_target: Option<&'a str>,
+    resample: FilterType,
+    histogram: bool,
     transparent: bool,
     undecorated: bool,
     ontop: bool,
@@ -808,9 +1100,19 @@ impl Default for FoximgArgs<'_> {
             lock: None,
             quiet: false,
             scaleto: false,
+            slideshow: None,
+            filter: None,
             state: None,
             style: None,
             title: None,
+            thumbnail_output: None,
+            thumbnail_size: None,
+            diff_path: None,
+            diff_threshold: 0,
+            resize_output: None,
+            resize_target: None,
+            resample: FilterType::Lanczos3,
+            histogram: false,
             transparent: false,
             undecorated: false,
             ontop: false,
@@ -829,19 +1131,72 @@ impl<'a> FoximgArgs<'a> {
         }
     }
 
+    /// Prints info as `language`. This is the info about the input image unless diffing images.
+    fn set_language(&mut self, language: FoximgInfoLanguage) {
+        if let FoximgMode::Diff(_) = self.mode {
+            self.mode = FoximgMode::Diff(language);
+        } else {
+            self.mode = FoximgMode::Info(language);
+        }
+    }
+
     fn parse_long_option(&mut self, arg: &'a str) -> Result<(), Option<anyhow::Error>> {
         if arg == "--help" {
             return Err(None);
+        } else if arg == "--config-check" {
+            self.mode = FoximgMode::ConfigCheck;
         } else if arg == "--info" {
-            self.mode = FoximgMode::Info(FoximgInfoLanguage::Toml);
+            self.set_language(FoximgInfoLanguage::Toml);
+        } else if arg == "--histogram" {
+            self.histogram = true;
         } else if arg == "--json" {
-            self.mode = FoximgMode::Info(FoximgInfoLanguage::Json);
+            self.set_language(FoximgInfoLanguage::Json);
         } else if arg == "--lock" {
             self.set_lock();
         } else if arg == "--quiet" {
             self.quiet = true;
         } else if arg == "--scaleto" {
             self.scaleto = true;
+        } else if let Some(slideshow) = arg.strip_prefix("--slideshow") {
+            return self::parse_option_with_arg(arg, slideshow, |slideshow| {
+                match slideshow.parse::<f32>() {
+                    Ok(interval) if interval > 0. => {
+                        self.slideshow = Some(interval);
+                        Ok(())
+                    }
+                    _ => Err(Some(anyhow::anyhow!("Invalid slideshow interval \"{slideshow}\""))),
+                }
+            });
+        } else if let Some(filter) = arg.strip_prefix("--filter") {
+            return self::parse_option_with_arg(arg, filter, |filter| {
+                match filter.parse() {
+                    Ok(filter) => {
+                        self.filter = Some(filter);
+                        Ok(())
+                    }
+                    Err(e) => Err(Some(anyhow::anyhow!("Invalid filter \"{filter}\": {e}"))),
+                }
+            });
+        } else if let Some(resample) = arg.strip_prefix("--resample") {
+            return self::parse_option_with_arg(arg, resample, |resample| {
+                match resize::parse_filter(resample) {
+                    Some(filter) => {
+                        self.resample = filter;
+                        Ok(())
+                    }
+                    None => Err(Some(anyhow::anyhow!("Unknown filter \"{resample}\""))),
+                }
+            });
+        } else if let Some(threshold) = arg.strip_prefix("--threshold") {
+            return self::parse_option_with_arg(arg, threshold, |threshold| {
+                match threshold.parse() {
+                    Ok(threshold) => {
+                        self.diff_threshold = threshold;
+                        Ok(())
+                    }
+                    Err(_) => Err(Some(anyhow::anyhow!("Invalid threshold \"{threshold}\""))),
+                }
+            });
         } else if let Some(state) = arg.strip_prefix("--state") {
             return self::parse_option_with_arg(arg, state, |state| {
                 self::parse_toml_arg(&mut self.state, state)
@@ -855,6 +1210,8 @@ impl<'a> FoximgArgs<'a> {
                 self.title = Some(title);
                 Ok(())
             });
+        } else if arg == "--thumbnailer" {
+            self.mode = FoximgMode::Thumbnailer;
         } else if arg == "--transparent" {
             self.transparent = true;
         } else if arg == "--undecorated" {
@@ -876,7 +1233,7 @@ impl<'a> FoximgArgs<'a> {
             if c == 'h' {
                 return Err(None);
             } else if c == 'i' {
-                self.mode = FoximgMode::Info(FoximgInfoLanguage::Toml);
+                self.set_language(FoximgInfoLanguage::Toml);
             } else if c == 'l' {
                 self.set_lock();
             } else if c == 'q' {
@@ -904,7 +1261,28 @@ impl<'a> FoximgArgs<'a> {
             let is_long_option = arg.chars().nth(0) == Some('-') 
                 && arg.chars().nth(1) == Some('-');
 
-            if is_long_option {
+            if arg == "--thumbnail" {
+                // The thumbnailer mode takes positional arguments, so that it can be used as-is in
+                // the Exec line of a .thumbnailer file.
+                self.mode = FoximgMode::Thumbnail;
+                self.path = args.next().map(|arg| arg.as_str());
+                self.thumbnail_output = args.next().map(|arg| arg.as_str());
+                self.thumbnail_size = args.next().map(|arg| arg.as_str());
+                break;
+            } else if arg == "--diff" {
+                let language = match self.mode {
+                    FoximgMode::Info(language) => language,
+                    _ => FoximgInfoLanguage::Toml,
+                };
+                self.mode = FoximgMode::Diff(language);
+                self.path = args.next().map(|arg| arg.as_str());
+                self.diff_path = args.next().map(|arg| arg.as_str());
+            } else if arg == "--resize" {
+                self.mode = FoximgMode::Resize;
+                self.path = args.next().map(|arg| arg.as_str());
+                self.resize_output = args.next().map(|arg| arg.as_str());
+                self.resize_target = args.next().map(|arg| arg.as_str());
+            } else if is_long_option {
                 if let Err(e) = self.parse_long_option(arg) {
                     self.mode = FoximgMode::Help(e);
                 }
@@ -921,7 +1299,12 @@ impl<'a> FoximgArgs<'a> {
 
         match self.mode {
             FoximgMode::Help(e) => Box::new(|| self::help(e)),
+            FoximgMode::ConfigCheck => Box::new(|| cli::config_check::run(self)),
             FoximgMode::Info(language) => Box::new(move || cli::run(self, language)),
+            FoximgMode::Diff(language) => Box::new(move || cli::diff::run(self, language)),
+            FoximgMode::Resize => Box::new(|| cli::resize::run(self)),
+            FoximgMode::Thumbnail => Box::new(|| cli::thumbnail::run(self)),
+            FoximgMode::Thumbnailer => Box::new(cli::thumbnail::thumbnailer),
             FoximgMode::Normal => Box::new(|| self::run(self)),
             FoximgMode::Version => Box::new(self::version),
         }
@@ -1050,15 +1433,30 @@ fn try_help(e: Option<anyhow::Error>) -> io::Result<()> {
     writeln!(out, "{green_color}Usage:{reset_color}")?;
     writeln!(out, "    foximg {gray_color}[OPTION...] [PATH]{reset_color}")?;
     writeln!(out, "{green_color}Options:{reset_color}")?;
+    writeln!(out, "    {gray_color}    --config-check  {reset_color}Check every config file for problems without changing them")?;
+    writeln!(out, "    {gray_color}    --diff A B      {reset_color}Print how different B is from A as TOML, or as JSON with --json")?;
+    writeln!(out, "                        {reset_color}Exits with 2 if any pixels differ by more than the threshold")?;
+    writeln!(out, "    {gray_color}    --filter=QUERY  {reset_color}Show only the images matching QUERY")?;
     writeln!(out, "    {gray_color}-h, --help          {reset_color}Print help")?;
+    writeln!(out, "    {gray_color}    --histogram     {reset_color}Print the histogram of input image along with -i or --json")?;
     writeln!(out, "    {gray_color}-i, --info          {reset_color}Print info about input image as TOML")?;
     writeln!(out, "    {gray_color}    --json          {reset_color}Print info about input image as JSON")?;
     writeln!(out, "    {gray_color}-l, --lock          {reset_color}Show only the input image. Use -ll to lock the UI as well")?;
     writeln!(out, "    {gray_color}-q, --quiet         {reset_color}Don't print log messages. Don't print EXIF metadata with -i")?;
+    writeln!(out, "    {gray_color}    --resample=FILTER")?;
+    writeln!(out, "                        {reset_color}Resize with FILTER: nearest, triangle, catmullrom, gaussian or lanczos3 (default)")?;
+    writeln!(out, "    {gray_color}    --resize INPUT OUTPUT SIZE")?;
+    writeln!(out, "                        {reset_color}Resize INPUT to SIZE and write it to OUTPUT in the format of INPUT")?;
     writeln!(out, "    {gray_color}-s, --scaleto       {reset_color}Scale window to the size of the current image")?;
+    writeln!(out, "    {gray_color}    --slideshow=SECONDS")?;
+    writeln!(out, "                        {reset_color}Start a slideshow that shows each image for SECONDS")?;
     writeln!(out, "    {gray_color}    --state=TOML    {reset_color}Set window's state according to the format in foximg_state.toml")?;
     writeln!(out, "    {gray_color}    --style=TOML    {reset_color}Set window's style according to the format in foximg_style.toml")?;
+    writeln!(out, "    {gray_color}    --threshold=N   {reset_color}Count pixels differing by more than N as different with --diff")?;
     writeln!(out, "    {gray_color}    --title=FORMAT  {reset_color}Set window's title")?;
+    writeln!(out, "    {gray_color}    --thumbnail INPUT OUTPUT SIZE")?;
+    writeln!(out, "                        {reset_color}Write a freedesktop thumbnail of INPUT to OUTPUT as PNG")?;
+    writeln!(out, "    {gray_color}    --thumbnailer   {reset_color}Print a .thumbnailer file registering foximg as a thumbnailer")?;
     writeln!(out, "    {gray_color}    --transparent   {reset_color}Set window to be transparent")?;
     writeln!(out, "    {gray_color}    --undecorated   {reset_color}Set window to not have a border")?;
     writeln!(out, "    {gray_color}    --ontop         {reset_color}Set window always on top")?;
@@ -1066,6 +1464,19 @@ fn try_help(e: Option<anyhow::Error>) -> io::Result<()> {
     writeln!(out, "    {gray_color}    --version       {reset_color}Print foximg's version")?;
     writeln!(out, "\n{green_color}TOML:{reset_color}")?;
     writeln!(out, "    Use either a TOML document with newlines substituted by semicolons, or a path to a TOML document.")?;
+    writeln!(out, "\n{green_color}SIZE:{reset_color}")?;
+    writeln!(out, "    WxH for an exact size, Wx or xH to keep the aspect ratio, N% to scale by, or N for the longer side.")?;
+    writeln!(out, "\n{green_color}QUERY:{reset_color}")?;
+    writeln!(out, "    Space-separated terms, all of which an image must match. Ranges are written as MIN..MAX, and either side can be left out.")?;
+    writeln!(out, "    {gray_color}name:GLOB      {reset_color}Name matches GLOB, as in IMG_*.jpg. Bare words are names as well")?;
+    writeln!(out, "    {gray_color}regex:REGEX    {reset_color}Name matches REGEX")?;
+    writeln!(out, "    {gray_color}format:EXT,... {reset_color}Image is in one of the formats, as in png,webp")?;
+    writeln!(out, "    {gray_color}width:RANGE    {reset_color}Width in pixels is in RANGE. height: works the same")?;
+    writeln!(out, "    {gray_color}size:RANGE     {reset_color}File size is in RANGE, as in 100K..2M")?;
+    writeln!(out, "    {gray_color}date:RANGE     {reset_color}EXIF date is in RANGE, as in 2024-01-01..2024-12-31")?;
+    writeln!(out, "    {gray_color}rating:N       {reset_color}Image is rated at least N stars")?;
+    writeln!(out, "    {gray_color}label:LABEL    {reset_color}Image has the colour label LABEL")?;
+    writeln!(out, "    {gray_color}tag:TAG        {reset_color}Image is tagged with TAG")?;
     writeln!(out, "\n{green_color}FORMAT specifiers:{reset_color}")?;
     writeln!(out, "    {gray_color}%f  {reset_color}Current image's path")?;
     writeln!(out, "    {gray_color}%h  {reset_color}Current image's height")?;
@@ -1073,6 +1484,9 @@ fn try_help(e: Option<anyhow::Error>) -> io::Result<()> {
     writeln!(out, "    {gray_color}%l  {reset_color}Number of images loaded")?;
     writeln!(out, "    {gray_color}%u  {reset_color}Current image's number")?;
     writeln!(out, "    {gray_color}%w  {reset_color}Current image's width")?;
+    writeln!(out, "    {gray_color}%r  {reset_color}Current image's rating from 0 to 5")?;
+    writeln!(out, "    {gray_color}%c  {reset_color}Current image's colour label")?;
+    writeln!(out, "    {gray_color}%t  {reset_color}Current image's tags")?;
     writeln!(out, "    {gray_color}%v  {reset_color}foximg's version")?;
     writeln!(out, "    {gray_color}%!  {reset_color}If no images, omit the text on the right side until another {gray_color}%!{reset_color} or end of text")?;
     Ok(())
@@ -1,19 +1,47 @@
+use std::borrow::Cow;
+
 use raylib::prelude::*;
 
-use crate::{Foximg, FoximgDraw, resources};
+use crate::{
+    Foximg, FoximgDraw, resources,
+    channels::FoximgChannelView,
+    config::{FoximgCompareFit, FoximgCompareLayout, FoximgHistogramScale, FoximgOnionSource},
+    filter::FoximgFilter,
+    keybinds::FoximgAction,
+    xmp::FoximgLabel,
+};
 
-#[derive(PartialEq)]
+#[derive(Clone)]
 enum MenuBtnType {
     OnPressedExit(fn(&mut FoximgMenu) -> bool),
     OnPressed(fn(&mut FoximgMenu)),
     OnDown(fn(&mut FoximgMenu)),
+    /// Like `OnPressed`, but draws a check box that's filled in when the second function returns
+    /// true.
+    Toggle(fn(&mut FoximgMenu), fn(&FoximgDraw) -> bool),
     SubMenu(&'static [MenuBtn]),
+    /// Like `SubMenu`, but opens the theme submenu, which is made from the theme library every time
+    /// the menu is opened.
+    Themes,
+    /// Switches to the theme at this index of the theme library. Draws a check box that's filled in
+    /// when the second field is true, meaning it's the current theme.
+    Theme(usize, bool),
+}
+
+#[derive(Clone, PartialEq)]
+enum MenuShortcut {
+    /// Shows the keybind of the action.
+    Action(FoximgAction),
+    /// Shows a shortcut that's handled by the OS instead of foximg.
+    Os(&'static str),
+    /// Shows the author of a theme.
+    Author(Cow<'static, str>),
 }
 
-#[derive(PartialEq)]
+#[derive(Clone)]
 struct MenuBtn {
-    pub name: &'static str,
-    pub shortcut_text: Option<&'static str>,
+    pub name: Cow<'static, str>,
+    pub shortcut: Option<MenuShortcut>,
     pub btn_type: MenuBtnType,
 }
 
@@ -23,21 +51,33 @@ impl MenuBtn {
 
     pub const fn new(name: &'static str, btn_type: MenuBtnType) -> Self {
         Self {
-            name,
+            name: Cow::Borrowed(name),
             btn_type,
-            shortcut_text: None,
+            shortcut: None,
         }
     }
 
     pub const fn new_shortcut(
+        name: &'static str,
+        btn_type: MenuBtnType,
+        action: FoximgAction,
+    ) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            btn_type,
+            shortcut: Some(MenuShortcut::Action(action)),
+        }
+    }
+
+    pub const fn new_os_shortcut(
         name: &'static str,
         btn_type: MenuBtnType,
         shortcut: &'static str,
     ) -> Self {
         Self {
-            name,
+            name: Cow::Borrowed(name),
             btn_type,
-            shortcut_text: Some(shortcut),
+            shortcut: Some(MenuShortcut::Os(shortcut)),
         }
     }
 
@@ -52,7 +92,7 @@ impl MenuBtn {
                     return (false, event(fm));
                 }
             }
-            MenuBtnType::OnPressed(event) => {
+            MenuBtnType::OnPressed(event) | MenuBtnType::Toggle(event, _) => {
                 if fm
                     .f
                     .rl
@@ -66,7 +106,7 @@ impl MenuBtn {
                     event(fm)
                 }
             }
-            MenuBtnType::SubMenu(_) => {
+            MenuBtnType::SubMenu(_) | MenuBtnType::Themes => {
                 if fm
                     .f
                     .rl
@@ -75,13 +115,23 @@ impl MenuBtn {
                     fm.delay = 0.
                 }
             }
+            MenuBtnType::Theme(i, _) => {
+                if fm
+                    .f
+                    .rl
+                    .is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+                {
+                    fm.f.switch_theme(i);
+                    return (false, true);
+                }
+            }
         }
         (true, true)
     }
 }
 
 /// The index at which the foximg right-click menu must be shown from when no image gallery is loaded.
-const FOXIMG_MENU_NO_IMAGES: usize = 3;
+const FOXIMG_MENU_NO_IMAGES: usize = 12;
 
 static FOXIMG_MENU: &[MenuBtn] = {
     const EXIT_SHORTCUT: &str = if cfg!(target_os = "windows") {
@@ -91,31 +141,681 @@ static FOXIMG_MENU: &[MenuBtn] = {
     };
 
     static FOXIMG_MENU_ROTATE: &[MenuBtn] = &[
-        MenuBtn::new_shortcut("+90 deg", MenuBtnType::OnPressed(btn_90deg), "E"),
-        MenuBtn::new_shortcut("-90 deg", MenuBtnType::OnPressed(btn_n90deg), "Q"),
-        MenuBtn::new_shortcut("+1 deg", MenuBtnType::OnDown(btn_1deg), "Ctrl+E"),
-        MenuBtn::new_shortcut("-1 deg", MenuBtnType::OnDown(btn_n1deg), "Ctrl+Q"),
+        MenuBtn::new_shortcut(
+            "+90 deg",
+            MenuBtnType::OnPressed(btn_90deg),
+            FoximgAction::Rotate90,
+        ),
+        MenuBtn::new_shortcut(
+            "-90 deg",
+            MenuBtnType::OnPressed(btn_n90deg),
+            FoximgAction::RotateN90,
+        ),
+        MenuBtn::new_shortcut(
+            "+1 deg",
+            MenuBtnType::OnDown(btn_1deg),
+            FoximgAction::Rotate1,
+        ),
+        MenuBtn::new_shortcut(
+            "-1 deg",
+            MenuBtnType::OnDown(btn_n1deg),
+            FoximgAction::RotateN1,
+        ),
     ];
 
     static FOXIMG_MENU_MIRROR: &[MenuBtn] = &[
         MenuBtn::new_shortcut(
             "Horizontal",
             MenuBtnType::OnPressed(btn_horizontal),
-            "Shift+Q",
+            FoximgAction::FlipHorizontal,
+        ),
+        MenuBtn::new_shortcut(
+            "Vertical",
+            MenuBtnType::OnPressed(btn_vertical),
+            FoximgAction::FlipVertical,
         ),
-        MenuBtn::new_shortcut("Vertical", MenuBtnType::OnPressed(btn_vertical), "Shift+E"),
     ];
 
     static FOXIMG_MENU_NAVIGATE: &[MenuBtn] = &[
         MenuBtn::new_shortcut(
             "First Image",
             MenuBtnType::OnPressedExit(btn_first_img),
-            "0",
+            FoximgAction::FirstImage,
         ),
         MenuBtn::new_shortcut(
             "Last Image",
             MenuBtnType::OnPressedExit(btn_last_img),
-            "Shift+4",
+            FoximgAction::LastImage,
+        ),
+        MenuBtn::new_shortcut(
+            "Go to...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.find_img();
+                true
+            }),
+            FoximgAction::Find,
+        ),
+        MenuBtn::new_shortcut(
+            "Grid View",
+            MenuBtnType::OnPressedExit(btn_grid),
+            FoximgAction::GridView,
+        ),
+        MenuBtn::new_shortcut(
+            "Filmstrip",
+            MenuBtnType::OnPressedExit(btn_filmstrip),
+            FoximgAction::Filmstrip,
+        ),
+    ];
+
+    static FOXIMG_MENU_SLIDESHOW_INTERVAL: &[MenuBtn] = &[
+        MenuBtn::new(
+            "2 seconds",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.interval = 2.),
+                |d| d.slideshow_settings.interval == 2.,
+            ),
+        ),
+        MenuBtn::new(
+            "5 seconds",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.interval = 5.),
+                |d| d.slideshow_settings.interval == 5.,
+            ),
+        ),
+        MenuBtn::new(
+            "10 seconds",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.interval = 10.),
+                |d| d.slideshow_settings.interval == 10.,
+            ),
+        ),
+        MenuBtn::new(
+            "30 seconds",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.interval = 30.),
+                |d| d.slideshow_settings.interval == 30.,
+            ),
+        ),
+        MenuBtn::new(
+            "60 seconds",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.interval = 60.),
+                |d| d.slideshow_settings.interval == 60.,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_SLIDESHOW: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Play",
+            MenuBtnType::Toggle(|fm| fm.f.toggle_slideshow(), |d| d.slideshow.is_some()),
+            FoximgAction::Slideshow,
+        ),
+        MenuBtn::new(
+            "Interval",
+            MenuBtnType::SubMenu(FOXIMG_MENU_SLIDESHOW_INTERVAL),
+        ),
+        MenuBtn::new(
+            "Loop",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.repeat = !s.repeat),
+                |d| d.slideshow_settings.repeat,
+            ),
+        ),
+        MenuBtn::new(
+            "Shuffle",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.shuffle = !s.shuffle),
+                |d| d.slideshow_settings.shuffle,
+            ),
+        ),
+        MenuBtn::new(
+            "Wait for Animations",
+            MenuBtnType::Toggle(
+                |fm| {
+                    fm.f.set_slideshow_settings(|s| {
+                        s.wait_for_animations = !s.wait_for_animations
+                    })
+                },
+                |d| d.slideshow_settings.wait_for_animations,
+            ),
+        ),
+        MenuBtn::new(
+            "Pause While Zoomed",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.pause_on_zoom = !s.pause_on_zoom),
+                |d| d.slideshow_settings.pause_on_zoom,
+            ),
+        ),
+        MenuBtn::new(
+            "Crossfade",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_slideshow_settings(|s| s.crossfade = !s.crossfade),
+                |d| d.slideshow_settings.crossfade,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_COMPARE_AMPLIFICATION: &[MenuBtn] = &[
+        MenuBtn::new(
+            "1x",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_amplification = 1.),
+                |d| d.compare_settings.diff_amplification == 1.,
+            ),
+        ),
+        MenuBtn::new(
+            "4x",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_amplification = 4.),
+                |d| d.compare_settings.diff_amplification == 4.,
+            ),
+        ),
+        MenuBtn::new(
+            "8x",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_amplification = 8.),
+                |d| d.compare_settings.diff_amplification == 8.,
+            ),
+        ),
+        MenuBtn::new(
+            "16x",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_amplification = 16.),
+                |d| d.compare_settings.diff_amplification == 16.,
+            ),
+        ),
+        MenuBtn::new(
+            "64x",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_amplification = 64.),
+                |d| d.compare_settings.diff_amplification == 64.,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_COMPARE_THRESHOLD: &[MenuBtn] = &[
+        MenuBtn::new(
+            "0",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_threshold = 0),
+                |d| d.compare_settings.diff_threshold == 0,
+            ),
+        ),
+        MenuBtn::new(
+            "1",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_threshold = 1),
+                |d| d.compare_settings.diff_threshold == 1,
+            ),
+        ),
+        MenuBtn::new(
+            "4",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_threshold = 4),
+                |d| d.compare_settings.diff_threshold == 4,
+            ),
+        ),
+        MenuBtn::new(
+            "16",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_threshold = 16),
+                |d| d.compare_settings.diff_threshold == 16,
+            ),
+        ),
+        MenuBtn::new(
+            "64",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.diff_threshold = 64),
+                |d| d.compare_settings.diff_threshold == 64,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_COMPARE: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Pin Current Image",
+            MenuBtnType::Toggle(|fm| fm.f.toggle_compare(), |d| d.compare.is_some()),
+            FoximgAction::Compare,
+        ),
+        MenuBtn::new(
+            "Side by Side",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.layout = FoximgCompareLayout::SideBySide),
+                |d| d.compare_settings.layout == FoximgCompareLayout::SideBySide,
+            ),
+        ),
+        MenuBtn::new(
+            "Top and Bottom",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.layout = FoximgCompareLayout::TopBottom),
+                |d| d.compare_settings.layout == FoximgCompareLayout::TopBottom,
+            ),
+        ),
+        MenuBtn::new(
+            "Split Slider",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.layout = FoximgCompareLayout::Split),
+                |d| d.compare_settings.layout == FoximgCompareLayout::Split,
+            ),
+        ),
+        MenuBtn::new(
+            "Difference",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.layout = FoximgCompareLayout::Difference),
+                |d| d.compare_settings.layout == FoximgCompareLayout::Difference,
+            ),
+        ),
+        MenuBtn::new(
+            "Amplification",
+            MenuBtnType::SubMenu(FOXIMG_MENU_COMPARE_AMPLIFICATION),
+        ),
+        MenuBtn::new(
+            "Threshold",
+            MenuBtnType::SubMenu(FOXIMG_MENU_COMPARE_THRESHOLD),
+        ),
+        MenuBtn::new(
+            "Scale to Match",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.fit = FoximgCompareFit::Scale),
+                |d| d.compare_settings.fit == FoximgCompareFit::Scale,
+            ),
+        ),
+        MenuBtn::new(
+            "Align Top Left",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_compare_settings(|s| s.fit = FoximgCompareFit::TopLeft),
+                |d| d.compare_settings.fit == FoximgCompareFit::TopLeft,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_ONION_OPACITY: &[MenuBtn] = &[
+        MenuBtn::new(
+            "25%",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_onion_settings(|s| s.opacity = 0.25),
+                |d| d.onion_settings.opacity == 0.25,
+            ),
+        ),
+        MenuBtn::new(
+            "50%",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_onion_settings(|s| s.opacity = 0.5),
+                |d| d.onion_settings.opacity == 0.5,
+            ),
+        ),
+        MenuBtn::new(
+            "75%",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_onion_settings(|s| s.opacity = 0.75),
+                |d| d.onion_settings.opacity == 0.75,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_ONION: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Show",
+            MenuBtnType::Toggle(|fm| fm.f.toggle_onion(), |d| d.onion.is_some()),
+            FoximgAction::OnionSkin,
+        ),
+        MenuBtn::new(
+            "Previous Image",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_onion_settings(|s| s.source = FoximgOnionSource::Previous),
+                |d| d.onion_settings.source == FoximgOnionSource::Previous,
+            ),
+        ),
+        MenuBtn::new(
+            "Next Image",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_onion_settings(|s| s.source = FoximgOnionSource::Next),
+                |d| d.onion_settings.source == FoximgOnionSource::Next,
+            ),
+        ),
+        MenuBtn::new(
+            "Pinned Image",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_onion_settings(|s| s.source = FoximgOnionSource::Pinned),
+                |d| d.onion_settings.source == FoximgOnionSource::Pinned,
+            ),
+        ),
+        MenuBtn::new("Opacity", MenuBtnType::SubMenu(FOXIMG_MENU_ONION_OPACITY)),
+        MenuBtn::new(
+            "Tint",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_onion_settings(|s| s.tint = !s.tint),
+                |d| d.onion_settings.tint,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_HISTOGRAM: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Show",
+            MenuBtnType::Toggle(|fm| fm.f.toggle_histogram(), |d| d.histogram.is_some()),
+            FoximgAction::Histogram,
+        ),
+        MenuBtn::new(
+            "Linear Scale",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_histogram_settings(|s| s.scale = FoximgHistogramScale::Linear),
+                |d| d.histogram_settings.scale == FoximgHistogramScale::Linear,
+            ),
+        ),
+        MenuBtn::new(
+            "Log Scale",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_histogram_settings(|s| s.scale = FoximgHistogramScale::Log),
+                |d| d.histogram_settings.scale == FoximgHistogramScale::Log,
+            ),
+        ),
+        MenuBtn::new(
+            "Luma",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_histogram_settings(|s| s.luma = !s.luma),
+                |d| d.histogram_settings.luma,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_OVERLAYS: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Clipping Warnings",
+            MenuBtnType::Toggle(|fm| fm.f.toggle_clipping(), |d| d.overlays.clipping),
+            FoximgAction::Clipping,
+        ),
+        MenuBtn::new_shortcut(
+            "Focus Peaking",
+            MenuBtnType::Toggle(|fm| fm.f.toggle_peaking(), |d| d.overlays.peaking),
+            FoximgAction::Peaking,
+        ),
+    ];
+
+    static FOXIMG_MENU_EDIT: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Undo",
+            MenuBtnType::OnPressed(|fm| fm.f.undo_edit()),
+            FoximgAction::Undo,
+        ),
+        MenuBtn::new_shortcut(
+            "Redo",
+            MenuBtnType::OnPressed(|fm| fm.f.redo_edit()),
+            FoximgAction::Redo,
+        ),
+        MenuBtn::new(
+            "Save Edits as Copy",
+            MenuBtnType::OnPressed(|fm| fm.f.save_current_edits()),
+        ),
+        MenuBtn::new(
+            "Overwrite with Edits",
+            MenuBtnType::OnPressed(|fm| fm.f.overwrite_current_edits()),
+        ),
+        MenuBtn::new(
+            "Discard Edits",
+            MenuBtnType::OnPressed(|fm| fm.f.discard_current_edits()),
+        ),
+    ];
+
+    static FOXIMG_MENU_ADJUST: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Adjust Colors...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.adjust_colors();
+                true
+            }),
+            FoximgAction::AdjustColors,
+        ),
+        MenuBtn::new(
+            "Save Adjusted...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.save_adjusted();
+                true
+            }),
+        ),
+        MenuBtn::new(
+            "Reset",
+            MenuBtnType::OnPressed(|fm| fm.f.reset_adjustments()),
+        ),
+    ];
+
+    static FOXIMG_MENU_CHANNELS: &[MenuBtn] = &[
+        MenuBtn::new(
+            "All Channels",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_channel_view(FoximgChannelView::Color),
+                |d| d.channel_view == FoximgChannelView::Color,
+            ),
+        ),
+        MenuBtn::new_shortcut(
+            "Red",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_channel_view(FoximgChannelView::Red),
+                |d| d.channel_view == FoximgChannelView::Red,
+            ),
+            FoximgAction::RedChannel,
+        ),
+        MenuBtn::new_shortcut(
+            "Green",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_channel_view(FoximgChannelView::Green),
+                |d| d.channel_view == FoximgChannelView::Green,
+            ),
+            FoximgAction::GreenChannel,
+        ),
+        MenuBtn::new_shortcut(
+            "Blue",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_channel_view(FoximgChannelView::Blue),
+                |d| d.channel_view == FoximgChannelView::Blue,
+            ),
+            FoximgAction::BlueChannel,
+        ),
+        MenuBtn::new_shortcut(
+            "Alpha",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_channel_view(FoximgChannelView::Alpha),
+                |d| d.channel_view == FoximgChannelView::Alpha,
+            ),
+            FoximgAction::AlphaChannel,
+        ),
+        MenuBtn::new(
+            "Ignore Alpha",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_channel_view(FoximgChannelView::Opaque),
+                |d| d.channel_view == FoximgChannelView::Opaque,
+            ),
+        ),
+        MenuBtn::new(
+            "Premultiplied",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_channel_view(FoximgChannelView::Premultiplied),
+                |d| d.channel_view == FoximgChannelView::Premultiplied,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_FILTER_RATING: &[MenuBtn] = &[
+        MenuBtn::new(
+            "Any Rating",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.min_rating = 0),
+                |d| d.filter.min_rating == 0,
+            ),
+        ),
+        MenuBtn::new(
+            "1+ Star",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.min_rating = 1),
+                |d| d.filter.min_rating == 1,
+            ),
+        ),
+        MenuBtn::new(
+            "2+ Stars",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.min_rating = 2),
+                |d| d.filter.min_rating == 2,
+            ),
+        ),
+        MenuBtn::new(
+            "3+ Stars",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.min_rating = 3),
+                |d| d.filter.min_rating == 3,
+            ),
+        ),
+        MenuBtn::new(
+            "4+ Stars",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.min_rating = 4),
+                |d| d.filter.min_rating == 4,
+            ),
+        ),
+        MenuBtn::new(
+            "5+ Stars",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.min_rating = 5),
+                |d| d.filter.min_rating == 5,
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_FILTER_LABEL: &[MenuBtn] = &[
+        MenuBtn::new(
+            "Any Label",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.label = None),
+                |d| d.filter.label.is_none(),
+            ),
+        ),
+        MenuBtn::new(
+            "Red",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.label = Some(FoximgLabel::Red)),
+                |d| d.filter.label == Some(FoximgLabel::Red),
+            ),
+        ),
+        MenuBtn::new(
+            "Yellow",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.label = Some(FoximgLabel::Yellow)),
+                |d| d.filter.label == Some(FoximgLabel::Yellow),
+            ),
+        ),
+        MenuBtn::new(
+            "Green",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.label = Some(FoximgLabel::Green)),
+                |d| d.filter.label == Some(FoximgLabel::Green),
+            ),
+        ),
+        MenuBtn::new(
+            "Blue",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.label = Some(FoximgLabel::Blue)),
+                |d| d.filter.label == Some(FoximgLabel::Blue),
+            ),
+        ),
+        MenuBtn::new(
+            "Purple",
+            MenuBtnType::Toggle(
+                |fm| fm.f.set_filter(|filter| filter.label = Some(FoximgLabel::Purple)),
+                |d| d.filter.label == Some(FoximgLabel::Purple),
+            ),
+        ),
+    ];
+
+    static FOXIMG_MENU_FILTER: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Edit...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.edit_filter();
+                true
+            }),
+            FoximgAction::Filter,
+        ),
+        MenuBtn::new("Rating", MenuBtnType::SubMenu(FOXIMG_MENU_FILTER_RATING)),
+        MenuBtn::new("Label", MenuBtnType::SubMenu(FOXIMG_MENU_FILTER_LABEL)),
+        MenuBtn::new(
+            "Tag...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.filter_by_tag();
+                true
+            }),
+        ),
+        MenuBtn::new(
+            "Show All",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.set_filter(|filter| *filter = FoximgFilter::default());
+                true
+            }),
+        ),
+    ];
+
+    static FOXIMG_MENU_FILE: &[MenuBtn] = &[
+        MenuBtn::new_shortcut(
+            "Rename...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.rename_current();
+                true
+            }),
+            FoximgAction::Rename,
+        ),
+        MenuBtn::new_shortcut(
+            "Rename Many",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.batch_rename();
+                true
+            }),
+            FoximgAction::RenameMany,
+        ),
+        MenuBtn::new_shortcut(
+            "Tags...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.edit_tags();
+                true
+            }),
+            FoximgAction::Tags,
+        ),
+        MenuBtn::new_shortcut(
+            "Resize...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.resize_img();
+                true
+            }),
+            FoximgAction::Resize,
+        ),
+        MenuBtn::new_shortcut(
+            "Mark",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.toggle_mark_current();
+                true
+            }),
+            FoximgAction::Mark,
+        ),
+        MenuBtn::new_shortcut(
+            "Move to Trash",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.trash_current();
+                true
+            }),
+            FoximgAction::Trash,
+        ),
+        MenuBtn::new_shortcut(
+            "Undo",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.undo_file_op();
+                true
+            }),
+            FoximgAction::UndoFileOp,
+        ),
+        MenuBtn::new(
+            "Ask Before Trashing",
+            MenuBtnType::Toggle(
+                |fm| fm.f.toggle_trash_confirm(),
+                |d| d.trash_settings.confirm,
+            ),
         ),
     ];
 
@@ -129,6 +829,10 @@ static FOXIMG_MENU: &[MenuBtn] = {
             "Image File",
         );
 
+        if !fm.f.check_all_edits(false) {
+            return true;
+        }
+
         if let Some(path) = tinyfiledialogs::open_file_dialog("Open...", "", Some(FILTER)) {
             fm.f.load_folder(path);
         } else {
@@ -198,17 +902,46 @@ static FOXIMG_MENU: &[MenuBtn] = {
         true
     }
 
+    fn btn_grid(fm: &mut FoximgMenu<'_>) -> bool {
+        fm.f.toggle_grid();
+        true
+    }
+
+    fn btn_filmstrip(fm: &mut FoximgMenu<'_>) -> bool {
+        fm.f.toggle_filmstrip();
+        true
+    }
+
     &[
         MenuBtn::new("Rotate", MenuBtnType::SubMenu(FOXIMG_MENU_ROTATE)),
         MenuBtn::new("Mirror", MenuBtnType::SubMenu(FOXIMG_MENU_MIRROR)),
+        MenuBtn::new("Edit", MenuBtnType::SubMenu(FOXIMG_MENU_EDIT)),
         MenuBtn::new("Navigate", MenuBtnType::SubMenu(FOXIMG_MENU_NAVIGATE)),
+        MenuBtn::new("Slideshow", MenuBtnType::SubMenu(FOXIMG_MENU_SLIDESHOW)),
+        MenuBtn::new("Compare", MenuBtnType::SubMenu(FOXIMG_MENU_COMPARE)),
+        MenuBtn::new("Onion Skin", MenuBtnType::SubMenu(FOXIMG_MENU_ONION)),
+        MenuBtn::new("Histogram", MenuBtnType::SubMenu(FOXIMG_MENU_HISTOGRAM)),
+        MenuBtn::new("Overlays", MenuBtnType::SubMenu(FOXIMG_MENU_OVERLAYS)),
+        MenuBtn::new("Channels", MenuBtnType::SubMenu(FOXIMG_MENU_CHANNELS)),
+        MenuBtn::new("Adjust", MenuBtnType::SubMenu(FOXIMG_MENU_ADJUST)),
+        MenuBtn::new("Filter", MenuBtnType::SubMenu(FOXIMG_MENU_FILTER)),
         MenuBtn::new("Open...", MenuBtnType::OnPressedExit(btn_open)),
+        MenuBtn::new("File", MenuBtnType::SubMenu(FOXIMG_MENU_FILE)),
         MenuBtn::new_shortcut(
             "Toggle Fullscreen",
             MenuBtnType::OnPressedExit(btn_toggle_fullscreen),
-            "F11",
+            FoximgAction::Fullscreen,
         ),
-        MenuBtn::new_shortcut("Exit", MenuBtnType::OnPressedExit(|_| false), EXIT_SHORTCUT),
+        MenuBtn::new("Theme", MenuBtnType::Themes),
+        MenuBtn::new_shortcut(
+            "Settings...",
+            MenuBtnType::OnPressedExit(|fm| {
+                fm.f.open_settings();
+                true
+            }),
+            FoximgAction::Settings,
+        ),
+        MenuBtn::new_os_shortcut("Exit", MenuBtnType::OnPressedExit(|_| false), EXIT_SHORTCUT),
     ]
 };
 
@@ -221,20 +954,26 @@ struct FoximgUpdateSubMenu<'a, 'b> {
 impl<'a, 'b> FoximgUpdateSubMenu<'a, 'b> {
     const CLOSE_DELAY: f32 = 600.;
 
-    fn open_sub_menu(&mut self, sub_menu: &'static [MenuBtn]) {
+    fn open_sub_menu(&mut self) {
+        let sub_menu = match self.fm.menus[self.col][self.row].btn_type {
+            MenuBtnType::SubMenu(sub_menu) => Cow::Borrowed(sub_menu),
+            MenuBtnType::Themes => Cow::Owned(self.fm.theme_menu.clone()),
+            _ => return,
+        };
+
         self.fm.f.rl.trace_log(
             TraceLogLevel::LOG_DEBUG,
             &format!("FOXIMG: Opened sub-menu (Depth: {})", self.col + 1),
         );
-        self.fm.menus.push(sub_menu);
         self.fm.rects.push(self::get_rect(
             rvec2(self.fm.rects[self.col].x, self.fm.rects[self.col].y)
                 + rvec2(
                     MenuBtn::WIDTH as u32,
                     MenuBtn::HEIGHT as u32 * self.row as u32,
                 ),
-            sub_menu,
+            &sub_menu,
         ));
+        self.fm.menus.push(sub_menu);
         self.fm.delay = Self::CLOSE_DELAY;
         self.fm.showing = (self.col, self.row);
     }
@@ -263,11 +1002,19 @@ impl<'a, 'b> FoximgUpdateSubMenu<'a, 'b> {
     }
 
     pub fn update(mut self) {
-        if let MenuBtnType::SubMenu(sub_menu) = self.fm.menus[self.col][self.row].btn_type {
-            if self.fm.menus.len() < self.col + 2
-                || (sub_menu != self.fm.menus[self.col + 1] && self.close_sub_menu())
-            {
-                self.open_sub_menu(sub_menu);
+        if let Some(sub_menu) = self.fm.get_sub_menu(self.col, self.row) {
+            let shown = self
+                .fm
+                .menus
+                .get(self.col + 1)
+                // Buttons hold functions, which can't be compared, so submenus are told apart by the
+                // names of their buttons.
+                .is_some_and(|shown| {
+                    let names = |btn: &MenuBtn| btn.name.clone();
+                    shown.iter().map(names).eq(sub_menu.iter().map(names))
+                });
+            if self.fm.menus.len() < self.col + 2 || (!shown && self.close_sub_menu()) {
+                self.open_sub_menu();
             }
         } else if self.fm.menus.len() >= self.col + 2 {
             self.close_sub_menu();
@@ -278,7 +1025,7 @@ impl<'a, 'b> FoximgUpdateSubMenu<'a, 'b> {
 }
 
 impl FoximgDraw<'_> {
-    fn draw_menu_shadow(&mut self, menu: &'static [MenuBtn], x: f32, y: f32) {
+    fn draw_menu_shadow(&mut self, menu: &[MenuBtn], x: f32, y: f32) {
         let shadow_x = x + MenuBtn::HEIGHT / 8.;
         let shadow_y = y + MenuBtn::HEIGHT / 8.;
 
@@ -291,10 +1038,10 @@ impl FoximgDraw<'_> {
         );
     }
 
-    fn draw_menu(&mut self, menu: &'static [MenuBtn], x: f32, mut y: f32) {
+    fn draw_menu(&mut self, menu: &[MenuBtn], x: f32, mut y: f32) {
         for btn in menu {
             self.d
-                .gui_button(rrect(x, y, MenuBtn::WIDTH, MenuBtn::HEIGHT), btn.name);
+                .gui_button(rrect(x, y, MenuBtn::WIDTH, MenuBtn::HEIGHT), &btn.name);
 
             let border_color = Color::get_color(
                 self.d
@@ -304,7 +1051,7 @@ impl FoximgDraw<'_> {
 
             const PADDING: f32 = 6.;
 
-            if let MenuBtnType::SubMenu(_) = btn.btn_type {
+            if let MenuBtnType::SubMenu(_) | MenuBtnType::Themes = btn.btn_type {
                 let mut point_a = rvec2(x + MenuBtn::WIDTH, y + MenuBtn::HEIGHT / 2.);
                 let mut point_b = rvec2(x + MenuBtn::WIDTH - MenuBtn::HEIGHT, y);
                 let mut point_c = rvec2(x + MenuBtn::WIDTH - MenuBtn::HEIGHT, y + MenuBtn::HEIGHT);
@@ -319,9 +1066,40 @@ impl FoximgDraw<'_> {
                     .draw_triangle(point_a, point_b, point_c, border_color);
             }
 
+            const CHECK_BOX_SIDE: f32 = MenuBtn::HEIGHT / 2.;
+
+            let checked = match btn.btn_type {
+                MenuBtnType::Toggle(_, checked) => Some(checked(self)),
+                MenuBtnType::Theme(_, current) => Some(current),
+                _ => None,
+            };
+
+            let mut shortcut_right_offset = 0.;
+            if let Some(checked) = checked {
+                let check_box = rrect(
+                    x + MenuBtn::WIDTH - CHECK_BOX_SIDE - PADDING,
+                    y + (MenuBtn::HEIGHT - CHECK_BOX_SIDE) / 2.,
+                    CHECK_BOX_SIDE,
+                    CHECK_BOX_SIDE,
+                );
+
+                if checked {
+                    self.d.draw_rectangle_rec(check_box, border_color);
+                } else {
+                    self.d.draw_rectangle_lines_ex(check_box, 1., border_color);
+                }
+                shortcut_right_offset = CHECK_BOX_SIDE + PADDING;
+            }
+
             y += MenuBtn::HEIGHT;
 
-            if let Some(shortcut_text) = btn.shortcut_text {
+            let shortcut_text = match btn.shortcut {
+                Some(MenuShortcut::Action(action)) => self.keybinds.label(action),
+                Some(MenuShortcut::Os(shortcut)) => Some(shortcut),
+                Some(MenuShortcut::Author(ref author)) => Some(&**author),
+                None => None,
+            };
+            if let Some(shortcut_text) = shortcut_text {
                 const BUTTON_Y_OFFSET: f32 = 1.;
                 const FONT_SIZE: f32 = resources::BUTTON_FONT_SIZE;
                 const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
@@ -330,7 +1108,7 @@ impl FoximgDraw<'_> {
                     self.resources
                         .yudit
                         .measure_text(shortcut_text, FONT_SIZE, FONT_SPACING);
-                let text_position = rvec2(x + MenuBtn::WIDTH, y)
+                let text_position = rvec2(x + MenuBtn::WIDTH - shortcut_right_offset, y)
                     - text_size
                     - rvec2(PADDING, PADDING / 2. + BUTTON_Y_OFFSET);
 
@@ -348,22 +1126,22 @@ impl FoximgDraw<'_> {
 
     fn draw_menu_objects(
         &mut self,
-        menus: &[&'static [MenuBtn]],
+        menus: &[Cow<'static, [MenuBtn]>],
         rects: &[Rectangle],
         hovering_on: (usize, usize),
         showing: (usize, usize),
-        draw: fn(&mut Self, menu: &'static [MenuBtn], x: f32, y: f32),
+        draw: fn(&mut Self, menu: &[MenuBtn], x: f32, y: f32),
     ) {
         let col = showing.0;
         let row = showing.1;
         let showing = &menus[col][row];
 
-        if let MenuBtnType::SubMenu(sub_menu) = showing.btn_type {
-            draw(self, sub_menu, rects[col + 1].x, rects[col + 1].y);
+        if let MenuBtnType::SubMenu(_) | MenuBtnType::Themes = showing.btn_type {
+            draw(self, &menus[col + 1], rects[col + 1].x, rects[col + 1].y);
         }
 
         for i in 0..=hovering_on.0 {
-            draw(self, menus[i], rects[i].x, rects[i].y);
+            draw(self, &menus[i], rects[i].x, rects[i].y);
         }
     }
 }
@@ -371,8 +1149,9 @@ impl FoximgDraw<'_> {
 pub struct FoximgMenu<'a> {
     f: &'a mut Foximg,
 
-    menus: Vec<&'static [MenuBtn]>,
+    menus: Vec<Cow<'static, [MenuBtn]>>,
     rects: Vec<Rectangle>,
+    theme_menu: Vec<MenuBtn>,
     hovering_on: (usize, usize),
     showing: (usize, usize),
     delay: f32,
@@ -381,17 +1160,28 @@ pub struct FoximgMenu<'a> {
 impl<'a> FoximgMenu<'a> {
     pub fn init(f: &'a mut Foximg) -> Self {
         /// Maximum number of submenus + 1.
-        const MAX_DEPTH: usize = 2;
+        const MAX_DEPTH: usize = 3;
 
         let mut menus = Vec::with_capacity(MAX_DEPTH);
-        menus.push(if f.images.is_some() {
+        menus.push(Cow::Borrowed(if f.images.is_some() {
             self::FOXIMG_MENU
         } else {
             &self::FOXIMG_MENU[self::FOXIMG_MENU_NO_IMAGES..]
-        });
+        }));
 
         let mut rects = Vec::with_capacity(MAX_DEPTH);
-        rects.push(self::get_rect(f.mouse_pos, menus[0]));
+        rects.push(self::get_rect(f.mouse_pos, &menus[0]));
+
+        let theme_menu = f
+            .themes
+            .iter()
+            .enumerate()
+            .map(|(i, theme)| MenuBtn {
+                name: theme.optionals.name.clone().unwrap_or_default(),
+                shortcut: theme.optionals.author.clone().map(MenuShortcut::Author),
+                btn_type: MenuBtnType::Theme(i, *theme == f.style),
+            })
+            .collect();
 
         let hovering_on = (0, 0);
         f.rl.trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Opened right-click menu");
@@ -400,6 +1190,7 @@ impl<'a> FoximgMenu<'a> {
             f,
             menus,
             rects,
+            theme_menu,
             hovering_on,
             showing: hovering_on,
             delay: 0.,
@@ -417,6 +1208,15 @@ impl<'a> FoximgMenu<'a> {
             })
     }
 
+    /// Returns the submenu the button at `col` and `row` opens, if it opens one.
+    fn get_sub_menu(&self, col: usize, row: usize) -> Option<&[MenuBtn]> {
+        match self.menus[col][row].btn_type {
+            MenuBtnType::SubMenu(sub_menu) => Some(sub_menu),
+            MenuBtnType::Themes => Some(&self.theme_menu),
+            _ => None,
+        }
+    }
+
     fn update_hovering_on(&mut self) {
         if let Some((col, row)) = self.get_bounds() {
             self.hovering_on = (col, row);
@@ -465,7 +1265,8 @@ impl<'a> FoximgMenu<'a> {
 
             let col = self.hovering_on.0;
             let row = self.hovering_on.1;
-            let (keep_menu, event_result) = self.menus[col][row].update(&mut self);
+            // The button is cloned since the theme submenu isn't static.
+            let (keep_menu, event_result) = self.menus[col][row].clone().update(&mut self);
 
             if !keep_menu {
                 return event_result;
@@ -473,9 +1274,15 @@ impl<'a> FoximgMenu<'a> {
 
             FoximgDraw::begin(self.f, |mut d, images| {
                 if let Some(images) = images {
-                    d.draw_current_img(images);
+                    if d.grid.is_some() {
+                        d.draw_grid(images);
+                    } else {
+                        d.draw_current_img(images);
+                        d.draw_filmstrip(images);
+                    }
                 }
 
+                d.draw_toasts();
                 d.draw_menu_objects(
                     &self.menus,
                     &self.rects,
@@ -506,7 +1313,7 @@ impl Drop for FoximgMenu<'_> {
     }
 }
 
-fn get_rect(pos: Vector2, menu: &'static [MenuBtn]) -> Rectangle {
+fn get_rect(pos: Vector2, menu: &[MenuBtn]) -> Rectangle {
     rrect(
         pos.x,
         pos.y,
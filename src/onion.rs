@@ -0,0 +1,252 @@
+//! Defines the onion skin, which overlays another image over the current one to check how well they
+//! line up. The overlay is the previous or next image in the gallery, the image pinned for
+//! comparing, or the previous or next frame of an animation while it's paused.
+
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw,
+    config::{FoximgConfig, FoximgOnionSettings, FoximgOnionSource},
+    images::{FoximgImage, FoximgImages},
+    keybinds::FoximgAction,
+};
+
+/// A frame of a paused animation, made into an image of its own to be overlaid.
+struct FoximgOnionFrame {
+    path: PathBuf,
+    index: usize,
+    img: Rc<RefCell<FoximgImage>>,
+}
+
+/// The previous or next image, kept apart from the gallery's cache so that overlaying it doesn't
+/// push other images out of it. `img` is `None` if it failed to load.
+struct FoximgOnionNeighbor {
+    path: PathBuf,
+    img: Option<Rc<RefCell<FoximgImage>>>,
+}
+
+#[derive(Default)]
+pub struct FoximgOnion {
+    frame: Option<FoximgOnionFrame>,
+    neighbor: Option<FoximgOnionNeighbor>,
+}
+
+impl FoximgOnionSource {
+    /// Returns how many images or frames away from the current one the overlay is, or `None` for
+    /// the pinned image.
+    fn offset(self) -> Option<isize> {
+        match self {
+            FoximgOnionSource::Previous => Some(-1),
+            FoximgOnionSource::Next => Some(1),
+            FoximgOnionSource::Pinned => None,
+        }
+    }
+}
+
+impl Foximg {
+    /// Shows or hides the onion skin. Does nothing if the UI is locked.
+    pub fn toggle_onion(&mut self) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        if self.onion.take().is_some() {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Hid onion skin");
+        } else {
+            self.onion = Some(FoximgOnion::default());
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Showed onion skin");
+            if self.onion_settings.source == FoximgOnionSource::Pinned && self.compare.is_none() {
+                match self.keybinds.label(FoximgAction::Compare) {
+                    Some(key) => self.toast(format!("No image is pinned. Press {key} to pin one")),
+                    None => self.toast("No image is pinned"),
+                }
+            }
+        }
+    }
+
+    /// Changes the onion skin settings and saves them.
+    pub fn set_onion_settings(&mut self, f: impl FnOnce(&mut FoximgOnionSettings)) {
+        f(&mut self.onion_settings);
+        self.onion_settings.to_file(&self.rl);
+    }
+
+    /// Toggles the onion skin if `OnionSkin` is pressed, or switches to the next image to overlay if
+    /// `OnionSource` is pressed while it's shown. Returns true if so.
+    pub fn onion_img(&mut self) -> bool {
+        let source = self.is_action_pressed(FoximgAction::OnionSource);
+        if self.images.is_none() || !(source || self.is_action_pressed(FoximgAction::OnionSkin)) {
+            return false;
+        }
+
+        if self.onion.is_some() && source {
+            self.set_onion_settings(|settings| {
+                settings.source = match settings.source {
+                    FoximgOnionSource::Previous => FoximgOnionSource::Next,
+                    FoximgOnionSource::Next => FoximgOnionSource::Pinned,
+                    FoximgOnionSource::Pinned => FoximgOnionSource::Previous,
+                }
+            });
+            let source = match self.onion_settings.source {
+                FoximgOnionSource::Previous => "the previous image",
+                FoximgOnionSource::Next => "the next image",
+                FoximgOnionSource::Pinned => "the pinned image",
+            };
+            self.toast(format!("Overlaying {source}"));
+        } else {
+            self.toggle_onion();
+        }
+        true
+    }
+
+    /// Updates the onion skin. Makes an image out of the frame next to the current one while the
+    /// current image is a paused animation, and loads the image next to it otherwise.
+    pub fn update_onion(&mut self) {
+        let Some(mut onion) = self.onion.take() else {
+            return;
+        };
+
+        let offset = self.onion_settings.source.offset();
+        if let (Some(offset), Some(images)) = (offset, &mut self.images) {
+            let path = images.img_path().to_path_buf();
+            if let Some(img) = images.img_get(&mut self.rl, &self.rl_thread) {
+                let img = img.borrow();
+                match img.paused_frame(offset) {
+                    Some((index, _))
+                        if onion
+                            .frame
+                            .as_ref()
+                            .is_some_and(|frame| frame.path == path && frame.index == index) => {}
+                    // Frames are as big as the whole animation, which is what the texture of the
+                    // animation gets made from.
+                    Some((index, rgba))
+                        if rgba.len() == (img.width() * img.height() * 4) as usize =>
+                    {
+                        let frame = FoximgImage::from_rgba(
+                            &mut self.rl,
+                            &self.rl_thread,
+                            rgba,
+                            img.width(),
+                            img.height(),
+                        );
+                        onion.frame = match frame {
+                            Ok(frame) => Some(FoximgOnionFrame {
+                                path,
+                                index,
+                                img: Rc::new(RefCell::new(frame)),
+                            }),
+                            Err(e) => {
+                                self.rl.trace_log(
+                                    TraceLogLevel::LOG_ERROR,
+                                    "FOXIMG: Failed to make an image of the next animation frame:",
+                                );
+                                self.rl
+                                    .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                                None
+                            }
+                        };
+                    }
+                    _ => onion.frame = None,
+                }
+            }
+            self.update_onion_neighbor(&mut onion, offset);
+        } else {
+            onion.frame = None;
+            onion.neighbor = None;
+        }
+
+        self.onion = Some(onion);
+    }
+
+    /// Loads the image `offset` images away from the current one into `onion` if it isn't loaded
+    /// there yet. If the gallery already has it loaded, that image is shared instead.
+    fn update_onion_neighbor(&mut self, onion: &mut FoximgOnion, offset: isize) {
+        let Some(ref images) = self.images else {
+            return;
+        };
+
+        let Some(i) = (images.img_current() - 1)
+            .checked_add_signed(offset)
+            .filter(|&i| i < images.len())
+        else {
+            onion.neighbor = None;
+            return;
+        };
+
+        let path = &images.paths()[i];
+        if onion
+            .neighbor
+            .as_ref()
+            .is_some_and(|neighbor| neighbor.path == *path)
+        {
+            return;
+        }
+
+        let img = match images.loaded(i) {
+            Some(img) => Some(img),
+            None => match images.loader(i)(&mut self.rl, &self.rl_thread, path) {
+                Ok(img) => Some(img),
+                Err(e) => {
+                    self.rl.trace_log(
+                        TraceLogLevel::LOG_ERROR,
+                        &format!("FOXIMG: Failed to load {path:?} to overlay:"),
+                    );
+                    self.rl
+                        .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                    None
+                }
+            },
+        };
+        onion.neighbor = Some(FoximgOnionNeighbor {
+            path: path.clone(),
+            img,
+        });
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Returns the image the onion skin overlays over `img`, the current image, if it's shown.
+    pub fn onion_overlay(
+        &mut self,
+        images: &FoximgImages,
+        img: &FoximgImage,
+    ) -> Option<Rc<RefCell<FoximgImage>>> {
+        let onion = self.onion?;
+        let Some(offset) = self.onion_settings.source.offset() else {
+            return self.compare.map(|compare| compare.pinned_img());
+        };
+
+        if img.paused() {
+            return onion
+                .frame
+                .as_ref()
+                .filter(|frame| frame.path == images.img_path())
+                .map(|frame| frame.img.clone());
+        }
+
+        let i = (images.img_current() - 1)
+            .checked_add_signed(offset)
+            .filter(|&i| i < images.len())?;
+        onion
+            .neighbor
+            .as_ref()
+            .filter(|neighbor| neighbor.path == images.paths()[i])
+            .and_then(|neighbor| neighbor.img.clone())
+    }
+
+    /// Returns the tints the current image and the overlay are drawn with.
+    pub fn onion_tints(&self) -> (Color, Color) {
+        let settings = self.onion_settings;
+        if settings.tint {
+            (
+                *settings.current_tint,
+                settings.overlay_tint.alpha(settings.opacity),
+            )
+        } else {
+            (Color::WHITE, Color::WHITE.alpha(settings.opacity))
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Defines the overlays that help with culling photos: clipping warnings, which highlight clipped
+//! shadows and highlights, and focus peaking, which highlights sharp edges. Both are drawn by
+//! shaders over the current image, and stay shown while going through the gallery.
+
+use std::cell::RefCell;
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw,
+    config::{FoximgConfig, FoximgOverlaySettings},
+    keybinds::FoximgAction,
+};
+
+#[derive(Default, Clone, Copy)]
+pub struct FoximgOverlays {
+    pub clipping: bool,
+    pub peaking: bool,
+}
+
+impl Foximg {
+    /// Passes the overlay settings on to the shaders that draw the overlays.
+    pub fn update_overlay_shaders(&mut self) {
+        let settings = &self.overlay_settings;
+
+        let clipping = self.resources.clipping.get_mut();
+        let low = clipping.get_shader_location("low");
+        let high = clipping.get_shader_location("high");
+        let shadow_color = clipping.get_shader_location("shadowColor");
+        let highlight_color = clipping.get_shader_location("highlightColor");
+        // Nudge the thresholds half a step outwards so that channels right at them still count.
+        clipping.set_shader_value(low, (f32::from(settings.clipping_low) + 0.5) / 255.);
+        clipping.set_shader_value(high, (f32::from(settings.clipping_high) - 0.5) / 255.);
+        clipping.set_shader_value(shadow_color, settings.shadow_color.color_normalize());
+        clipping.set_shader_value(highlight_color, settings.highlight_color.color_normalize());
+
+        let peaking = self.resources.peaking.get_mut();
+        let threshold = peaking.get_shader_location("threshold");
+        let peaking_color = peaking.get_shader_location("peakingColor");
+        peaking.set_shader_value(threshold, settings.peaking_threshold);
+        peaking.set_shader_value(peaking_color, settings.peaking_color.color_normalize());
+    }
+
+    /// Changes the overlay settings, saves them and passes them on to the shaders.
+    pub fn set_overlay_settings(&mut self, f: impl FnOnce(&mut FoximgOverlaySettings)) {
+        f(&mut self.overlay_settings);
+        self.overlay_settings.to_file(&self.rl);
+        self.update_overlay_shaders();
+    }
+
+    /// Shows or hides the clipping warnings.
+    pub fn toggle_clipping(&mut self) {
+        self.overlays.clipping = !self.overlays.clipping;
+        self.toast(if self.overlays.clipping {
+            "Showing clipping warnings"
+        } else {
+            "Hid clipping warnings"
+        });
+    }
+
+    /// Shows or hides focus peaking.
+    pub fn toggle_peaking(&mut self) {
+        self.overlays.peaking = !self.overlays.peaking;
+        self.toast(if self.overlays.peaking {
+            "Showing focus peaking"
+        } else {
+            "Hid focus peaking"
+        });
+    }
+
+    /// Toggles the clipping warnings if `Clipping` is pressed. Returns true if so.
+    pub fn clipping_img(&mut self) -> bool {
+        if self.images.is_some() && self.is_action_pressed(FoximgAction::Clipping) {
+            self.toggle_clipping();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Toggles focus peaking if `Peaking` is pressed. Returns true if so.
+    pub fn peaking_img(&mut self) -> bool {
+        if self.images.is_some() && self.is_action_pressed(FoximgAction::Peaking) {
+            self.toggle_peaking();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<'a> FoximgDraw<'a> {
+    /// Returns the shaders of the overlays that are shown, in the order they're drawn in.
+    pub fn overlay_shaders(&self) -> Vec<&'a RefCell<Shader>> {
+        let resources = self.resources;
+        let mut shaders = Vec::with_capacity(2);
+        if self.overlays.peaking {
+            shaders.push(&resources.peaking);
+        }
+        if self.overlays.clipping {
+            shaders.push(&resources.clipping);
+        }
+        shaders
+    }
+}
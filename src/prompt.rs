@@ -0,0 +1,220 @@
+//! Defines prompts for typing text in, such as a new name for an image. Prompts take over the window
+//! until they're answered.
+
+use raylib::prelude::*;
+
+use crate::{Foximg, FoximgDraw, resources};
+
+/// The text being typed into a prompt.
+pub struct FoximgTextInput<'a> {
+    title: &'a str,
+    hint: &'a str,
+    text: String,
+    /// Appended to `text` if `keep_suffix` is set. This is how extensions are kept by default.
+    suffix: &'a str,
+    keep_suffix: bool,
+    error: Option<String>,
+}
+
+impl<'a> FoximgTextInput<'a> {
+    pub fn new(title: &'a str, hint: &'a str, text: String) -> Self {
+        Self {
+            title,
+            hint,
+            text,
+            suffix: "",
+            keep_suffix: true,
+            error: None,
+        }
+    }
+
+    /// Shows `suffix` after the text, keeping it unless Tab is pressed.
+    pub fn with_suffix(mut self, suffix: &'a str) -> Self {
+        self.suffix = suffix;
+        self
+    }
+
+    fn value(&self) -> String {
+        if self.keep_suffix {
+            format!("{}{}", self.text, self.suffix)
+        } else {
+            self.text.clone()
+        }
+    }
+
+    /// Moves the suffix into the text so that it can be edited, or back out of it if the text still
+    /// ends with it.
+    fn toggle_suffix(&mut self) {
+        if self.keep_suffix {
+            self.text.push_str(self.suffix);
+            self.keep_suffix = false;
+        } else if let Some(text) = self.text.strip_suffix(self.suffix) {
+            self.text = text.to_string();
+            self.keep_suffix = true;
+        }
+    }
+}
+
+impl Foximg {
+    /// Shows a prompt for typing into `input`. This runs its own loop until the prompt gets answered.
+    /// Enter submits the text to `validate`, which either accepts it or returns an error to show.
+    /// Escape cancels the prompt, and Tab toggles whether the suffix is kept.
+    pub fn prompt_text<T>(
+        &mut self,
+        mut input: FoximgTextInput,
+        mut validate: impl FnMut(&str) -> Result<T, String>,
+    ) -> Option<T> {
+        // Throw away anything typed before the prompt showed up.
+        while self.rl.get_char_pressed().is_some() {}
+
+        while !self.rl.window_should_close() {
+            self.update();
+
+            while let Some(c) = self.rl.get_char_pressed() {
+                input.text.push(c);
+                input.error = None;
+            }
+
+            if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE)
+                || self.rl.is_key_pressed_repeat(KeyboardKey::KEY_BACKSPACE)
+            {
+                input.text.pop();
+                input.error = None;
+            }
+
+            if self.rl.is_key_pressed(KeyboardKey::KEY_TAB) {
+                input.toggle_suffix();
+            } else if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                return None;
+            } else if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER)
+                || self.rl.is_key_pressed(KeyboardKey::KEY_KP_ENTER)
+            {
+                match validate(&input.value()) {
+                    Ok(value) => return Some(value),
+                    Err(e) => input.error = Some(e),
+                }
+            }
+
+            FoximgDraw::begin(self, |mut d, images| {
+                if let Some(images) = images {
+                    d.draw_current_img(images);
+                }
+
+                d.draw_text_input(&input);
+            });
+        }
+
+        None
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the prompt of a `FoximgTextInput` in the middle of the window.
+    fn draw_text_input(&mut self, input: &FoximgTextInput) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const WIDTH: f32 = 480.;
+        const PADDING: f32 = 10.;
+        const LINE: f32 = FONT_SIZE + PADDING;
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let screen_height = self.d.get_screen_height().as_f32();
+        let height = PADDING * 2. + LINE * 4.;
+        let rect = rrect(
+            screen_width / 2. - WIDTH / 2.,
+            screen_height / 2. - height / 2.,
+            WIDTH,
+            height,
+        );
+        let yudit = &self.resources.yudit;
+        let x = rect.x + PADDING;
+        let mut y = rect.y + PADDING;
+
+        self.d.draw_rectangle_rec(
+            rrect(0, 0, screen_width, screen_height),
+            self.style.bg.alpha(0.5),
+        );
+        self.d.draw_rectangle_rec(rect, *self.style.bg);
+        self.d
+            .draw_rectangle_lines_ex(rect, 1., self.style.accent.alpha(1.));
+
+        self.d.draw_text_ex(
+            yudit,
+            input.title,
+            rvec2(x, y),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent,
+        );
+        y += LINE;
+
+        let field = rrect(x, y - PADDING / 2., WIDTH - PADDING * 2., LINE);
+        self.d
+            .draw_rectangle_lines_ex(field, 1., self.style.accent.alpha(0.5));
+
+        // Long names are scrolled so that the end of the text stays in view.
+        let text_width = yudit.measure_text(&input.text, FONT_SIZE, FONT_SPACING).x;
+        let suffix_width = if input.keep_suffix {
+            yudit.measure_text(input.suffix, FONT_SIZE, FONT_SPACING).x
+        } else {
+            0.
+        };
+        let text_x =
+            x + PADDING / 2. - (text_width + suffix_width - (field.width - PADDING)).max(0.);
+
+        {
+            let mut s = self.d.begin_scissor_mode(
+                field.x as i32,
+                field.y as i32,
+                field.width as i32,
+                field.height as i32,
+            );
+            s.draw_text_ex(
+                yudit,
+                &input.text,
+                rvec2(text_x, y),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent,
+            );
+            if input.keep_suffix {
+                s.draw_text_ex(
+                    yudit,
+                    input.suffix,
+                    rvec2(text_x + text_width, y),
+                    FONT_SIZE,
+                    FONT_SPACING,
+                    self.style.accent.alpha(0.5),
+                );
+            }
+            if s.get_time() % 1. < 0.5 {
+                s.draw_rectangle_rec(
+                    rrect(text_x + text_width, y, 1, FONT_SIZE),
+                    self.style.accent,
+                );
+            }
+        }
+        y += LINE;
+
+        if let Some(ref error) = input.error {
+            self.d.draw_text_ex(
+                yudit,
+                error,
+                rvec2(x, y),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.command,
+            );
+        }
+        y += LINE;
+
+        self.d.draw_text_ex(
+            yudit,
+            input.hint,
+            rvec2(x, y),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent.alpha(0.5),
+        );
+    }
+}
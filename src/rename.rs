@@ -0,0 +1,439 @@
+//! Defines renaming images from inside foximg. The current image can be renamed on its own, or
+//! every marked image can be renamed at once following a pattern.
+
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use exif::{In, Tag, Value};
+use raylib::prelude::*;
+
+use crate::{Foximg, culling::FoximgFileOp, keybinds::FoximgAction, prompt::FoximgTextInput};
+
+/// Checks that `name` can be used as a file name.
+fn validate_name(name: &str) -> Result<(), String> {
+    const ILLEGAL: &[char] = if cfg!(target_os = "windows") {
+        &['/', '\\', '<', '>', ':', '"', '|', '?', '*']
+    } else {
+        &['/']
+    };
+
+    if name.is_empty() {
+        return Err("The name can't be empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("The name can't be \"{name}\""));
+    }
+    if let Some(c) = name.chars().find(|c| ILLEGAL.contains(c) || c.is_control()) {
+        return Err(format!("The name can't contain {c:?}"));
+    }
+    if cfg!(target_os = "windows") && (name.ends_with('.') || name.ends_with(' ')) {
+        return Err("The name can't end with a dot or a space".to_string());
+    }
+
+    Ok(())
+}
+
+/// Returns the path `from` gets renamed to with `name`, or an error if it's taken by another file.
+/// Files in `batch` are being renamed along with `from`, so their names don't count as taken.
+fn renamed_path(from: &Path, name: &str, batch: &HashSet<PathBuf>) -> Result<PathBuf, String> {
+    self::validate_name(name)?;
+
+    let to = from.with_file_name(name);
+    // Renaming a file to itself with a different case finds the file itself on case-insensitive
+    // filesystems.
+    let renamed = to
+        .canonicalize()
+        .is_ok_and(|to| to == from || batch.contains(&to));
+    if to != from && to.exists() && !batch.contains(&to) && !renamed {
+        return Err(format!("\"{name}\" already exists"));
+    }
+
+    Ok(to)
+}
+
+/// Returns a free temporary path in the folder of `path` to rename it to, keeping its extension so
+/// that its sidecar can follow it.
+fn temporary_path(path: &Path) -> PathBuf {
+    let extension = self::extension(path);
+    (0..)
+        .map(|n| path.with_file_name(format!(".foximg-rename-{n}{extension}")))
+        .find(|temporary| !temporary.exists())
+        .unwrap()
+}
+
+/// Returns the extension of `path` with its leading dot, or an empty string if it has none.
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default()
+}
+
+/// Returns when the image at `path` was taken according to its EXIF metadata, falling back to when
+/// the file was last modified.
+pub fn date_taken(path: &Path) -> Option<NaiveDateTime> {
+    let exif = File::open(path).ok().and_then(|file| {
+        exif::Reader::new()
+            .read_from_container(&mut BufReader::new(file))
+            .ok()
+    });
+
+    let date = exif.and_then(|exif| {
+        [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+            .into_iter()
+            .find_map(|tag| {
+                let Value::Ascii(ref ascii) = exif.get_field(tag, In::PRIMARY)?.value else {
+                    return None;
+                };
+
+                let date = exif::DateTime::from_ascii(ascii.first()?).ok()?;
+                NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())?
+                    .and_hms_opt(date.hour.into(), date.minute.into(), date.second.into())
+            })
+    });
+
+    date.or_else(|| {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()?;
+        Some(chrono::DateTime::<Local>::from(modified).naive_local())
+    })
+}
+
+/// Expands a batch rename pattern for the `n`th image, which is at `path`. The pattern is copied
+/// as-is except for these fields:
+///
+/// - `{n}`: The position of the image among the marked images, starting from 1. `{n:3}` pads it
+///   with zeroes to 3 digits.
+/// - `{name}`: The name of the image without its extension.
+/// - `{date}`, `{time}`: When the image was taken, as in `2025-01-31` and `23-59-59`.
+///   `{date:%Y%m%d}` takes a chrono format string instead.
+fn expand_pattern(pattern: &str, n: usize, path: &Path) -> Result<String, String> {
+    let mut name = String::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err("Unclosed \"{\" in the pattern".to_string());
+        };
+
+        let field = &rest[start + 1..start + end];
+        let (field, arg) = field.split_once(':').unwrap_or((field, ""));
+        let written = match (field, arg) {
+            ("n", "") => write!(name, "{n}"),
+            ("n", width) => {
+                let width: usize = width
+                    .parse()
+                    .map_err(|_| format!("\"{width}\" isn't a valid width"))?;
+                write!(name, "{n:0width$}")
+            }
+            ("name", "") => write!(
+                name,
+                "{}",
+                path.file_stem().unwrap_or_default().to_string_lossy()
+            ),
+            ("date" | "time", format) => {
+                let format = match (field, format) {
+                    ("date", "") => "%Y-%m-%d",
+                    ("time", "") => "%H-%M-%S",
+                    (_, format) => format,
+                };
+                let date = self::date_taken(path)
+                    .ok_or_else(|| format!("Couldn't find when {path:?} was taken"))?;
+                write!(name, "{}", date.format(format))
+            }
+            _ => return Err(format!("Unknown field \"{{{field}}}\"")),
+        };
+
+        written.map_err(|_| format!("\"{arg}\" isn't a valid date format"))?;
+        rest = &rest[start + end + 1..];
+    }
+
+    name.push_str(rest);
+    Ok(name)
+}
+
+impl Foximg {
+    /// Renames the image at index `i` from `from` to `to` and updates the gallery in place.
+    fn rename_img(&mut self, i: usize, from: &Path, to: &Path) -> anyhow::Result<()> {
+        fs::rename(from, to)?;
+        self.move_sidecar(from, to);
+        if let Some(ref mut images) = self.images {
+            images.set_path(i, to.to_path_buf());
+        }
+
+        Ok(())
+    }
+
+    /// Renames every file in `renames` from the first path to the second, along with the image at
+    /// the given index in the gallery. Every file is renamed to a temporary name first, so that a
+    /// file can take the name of another one in the same batch, as when swapping names. Stops at the
+    /// first rename that fails. Returns the renames that were done.
+    fn rename_batch(
+        &mut self,
+        renames: Vec<(Option<usize>, PathBuf, PathBuf)>,
+    ) -> Vec<(PathBuf, PathBuf)> {
+        let mut temporaries = Vec::with_capacity(renames.len());
+        let mut failed = None;
+        for (i, from, to) in renames {
+            if from == to {
+                continue;
+            }
+
+            let temporary = self::temporary_path(&from);
+            if let Err(e) = fs::rename(&from, &temporary) {
+                failed = Some((from, to, anyhow::Error::from(e)));
+                break;
+            }
+            self.move_sidecar(&from, &temporary);
+            temporaries.push((i, from, temporary, to));
+        }
+
+        let mut renamed = Vec::with_capacity(temporaries.len());
+        let mut temporaries = temporaries.into_iter();
+        if failed.is_none() {
+            for (i, from, temporary, to) in temporaries.by_ref() {
+                let res = if to.exists() {
+                    Err(anyhow::anyhow!("{to:?} already exists"))
+                } else {
+                    fs::rename(&temporary, &to).map_err(anyhow::Error::from)
+                };
+                if let Err(e) = res {
+                    self.restore_temporary(&from, &temporary);
+                    failed = Some((from, to, e));
+                    break;
+                }
+
+                self.move_sidecar(&temporary, &to);
+                if let (Some(i), Some(images)) = (i, &mut self.images) {
+                    images.set_path(i, to.clone());
+                }
+                renamed.push((from, to));
+            }
+        }
+
+        // Files that didn't get renamed get their names back.
+        for (_, from, temporary, _) in temporaries {
+            self.restore_temporary(&from, &temporary);
+        }
+
+        if let Some((from, to, e)) = failed {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_ERROR,
+                &format!("FOXIMG: Failed to rename {from:?} to {to:?}. Stopping here:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+        }
+
+        renamed
+    }
+
+    /// Gives the file renamed to `temporary` its name `from` back, logging if it can't be.
+    fn restore_temporary(&mut self, from: &Path, temporary: &Path) {
+        let res = if from.exists() {
+            Err(anyhow::anyhow!("{from:?} already exists"))
+        } else {
+            fs::rename(temporary, from).map_err(anyhow::Error::from)
+        };
+
+        match res {
+            Ok(()) => self.move_sidecar(temporary, from),
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    &format!(
+                        "FOXIMG: Failed to rename {temporary:?} back to {from:?}. It's left as is:"
+                    ),
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+            }
+        }
+    }
+
+    /// Shows a prompt for renaming the current image. The extension is kept unless Tab is pressed.
+    /// Does nothing if the UI is locked.
+    pub fn rename_current(&mut self) {
+        let Some(ref images) = self.images else {
+            return;
+        };
+
+        if self.lock.is_some() {
+            return;
+        }
+
+        let i = images.img_current() - 1;
+        let from = images.img_path().to_path_buf();
+        let extension = self::extension(&from);
+        let input = FoximgTextInput::new(
+            "Rename",
+            "Enter to rename, Esc to cancel, Tab to edit the extension",
+            from.file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        )
+        .with_suffix(&extension);
+
+        let batch = HashSet::new();
+        let Some(to) = self.prompt_text(input, |name| self::renamed_path(&from, name, &batch))
+        else {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Cancelled renaming");
+            return;
+        };
+
+        if to == from {
+            return;
+        }
+
+        let (from_name, to_name) = (
+            from.file_name().unwrap_or_default().to_string_lossy(),
+            to.file_name().unwrap_or_default().to_string_lossy(),
+        );
+
+        if let Err(e) = self.rename_img(i, &from, &to) {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_ERROR,
+                &format!("FOXIMG: Failed to rename {from:?} to {to:?}:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+            self.toast(format!("Couldn't rename \"{from_name}\""));
+            return;
+        }
+
+        self.toast(format!("Renamed \"{from_name}\" to \"{to_name}\""));
+        self.file_ops
+            .push(FoximgFileOp::Rename(vec![(from.clone(), to.clone())]));
+        self.images_with(|f, images| images.update_window(f));
+    }
+
+    /// Shows a prompt for renaming every marked image following a pattern. See `expand_pattern` for
+    /// the fields a pattern can have. Extensions are always kept. Does nothing if the UI is locked.
+    pub fn batch_rename(&mut self) {
+        let Some(ref images) = self.images else {
+            return;
+        };
+
+        if self.lock.is_some() {
+            return;
+        }
+
+        let marked: Vec<(usize, PathBuf)> = images
+            .marked_indices()
+            .into_iter()
+            .map(|i| (i, images.paths()[i].clone()))
+            .collect();
+        if marked.is_empty() {
+            match self.keybinds.label(FoximgAction::Mark) {
+                Some(key) => self.toast(format!(
+                    "No images are marked. Press {key} to mark an image"
+                )),
+                None => self.toast("No images are marked"),
+            }
+            return;
+        }
+
+        let title = format!("Rename {} Images", marked.len());
+        let input = FoximgTextInput::new(
+            &title,
+            "Fields: {n} {n:3} {name} {date} {time} {date:%Y%m%d}",
+            "{name}".to_string(),
+        );
+
+        let batch: HashSet<PathBuf> = marked.iter().map(|(_, from)| from.clone()).collect();
+        let renames = self.prompt_text(input, |pattern| {
+            let mut taken = HashSet::new();
+            let mut renames = Vec::with_capacity(marked.len());
+
+            for (n, (i, from)) in marked.iter().enumerate() {
+                let name = self::expand_pattern(pattern, n + 1, from)? + &self::extension(from);
+                let to = self::renamed_path(from, &name, &batch)?;
+                if !taken.insert(to.clone()) {
+                    return Err(format!("More than one image would be named \"{name}\""));
+                }
+
+                renames.push((Some(*i), from.clone(), to));
+            }
+
+            Ok(renames)
+        });
+
+        let Some(renames) = renames else {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Cancelled batch renaming");
+            return;
+        };
+
+        let count = renames.iter().filter(|(_, from, to)| from != to).count();
+        let renamed = self.rename_batch(renames);
+        if renamed.len() < count {
+            self.toast(format!(
+                "Couldn't rename {} of {count} images",
+                count - renamed.len()
+            ));
+        } else if !renamed.is_empty() {
+            self.toast(format!("Renamed {count} images"));
+        }
+        if renamed.is_empty() {
+            return;
+        }
+
+        self.file_ops.push(FoximgFileOp::Rename(renamed));
+        self.images_with(|f, images| images.update_window(f));
+    }
+
+    /// Renames images back to what they were called before, newest first.
+    pub fn undo_rename(&mut self, renamed: Vec<(PathBuf, PathBuf)>) {
+        // The indices are found before renaming anything, since the gallery can briefly have two
+        // images with the same path while names are being swapped back.
+        let undo = renamed
+            .iter()
+            .rev()
+            .map(|(from, to)| {
+                let i = self
+                    .images
+                    .as_ref()
+                    .and_then(|images| images.paths().iter().position(|path| path == to));
+                (i, to.clone(), from.clone())
+            })
+            .collect();
+        let undone = self.rename_batch(undo).len();
+
+        match renamed.as_slice() {
+            [(from, to)] if undone == 1 => self.toast(format!(
+                "Renamed \"{}\" back to \"{}\"",
+                to.file_name().unwrap_or_default().to_string_lossy(),
+                from.file_name().unwrap_or_default().to_string_lossy()
+            )),
+            _ if undone == renamed.len() => self.toast(format!("Renamed {undone} images back")),
+            _ => self.toast(format!(
+                "Couldn't rename {} of {} images back",
+                renamed.len() - undone,
+                renamed.len()
+            )),
+        }
+        self.images_with(|f, images| images.update_window(f));
+    }
+
+    /// Renames the current image if `Rename` is pressed, or every marked image if `RenameMany` is
+    /// pressed. Returns true if so.
+    pub fn rename_img_key(&mut self) -> bool {
+        if self.is_action_pressed(FoximgAction::Rename) {
+            self.rename_current();
+        } else if self.is_action_pressed(FoximgAction::RenameMany) {
+            self.batch_rename();
+        } else {
+            return false;
+        }
+        true
+    }
+}
@@ -0,0 +1,565 @@
+//! Defines resizing images with the `imageops` filters. The same engine backs the resize dialog and
+//! the headless `--resize` mode, so that both save the same images.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, TryRecvError},
+    },
+    thread,
+};
+
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageReader, imageops::FilterType};
+use raylib::prelude::*;
+
+use crate::{Foximg, FoximgDraw, images::FoximgImage, keybinds::FoximgAction, resources};
+
+/// The largest side images can be resized to, which keeps the preview within what GPUs can load as
+/// a texture.
+pub const MAX_SIDE: i32 = 16384;
+
+/// The largest percentage the resize dialog allows.
+const MAX_PERCENT: i32 = 400;
+
+/// How long the settings of the resize dialog must stay the same before the preview gets redone.
+const PREVIEW_DELAY: f64 = 0.25;
+
+/// Every filter images can be resized with, along with their names.
+pub const FILTERS: [(FilterType, &str); 5] = [
+    (FilterType::Nearest, "Nearest"),
+    (FilterType::Triangle, "Triangle"),
+    (FilterType::CatmullRom, "CatmullRom"),
+    (FilterType::Gaussian, "Gaussian"),
+    (FilterType::Lanczos3, "Lanczos3"),
+];
+
+/// Returns the filter named `name`, ignoring case.
+pub fn parse_filter(name: &str) -> Option<FilterType> {
+    FILTERS
+        .iter()
+        .find(|(_, filter)| filter.eq_ignore_ascii_case(name))
+        .map(|&(filter, _)| filter)
+}
+
+/// What an image gets resized to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FoximgResizeTarget {
+    /// An exact size. A side that's `None` follows the other one, keeping the aspect ratio.
+    Size(Option<u32>, Option<u32>),
+    Percent(f64),
+    /// The size of the longer side, keeping the aspect ratio.
+    LongEdge(u32),
+}
+
+impl FoximgResizeTarget {
+    /// Returns the size an image of `width` by `height` gets resized to. Neither side goes below 1.
+    pub fn dimensions(self, width: u32, height: u32) -> (u32, u32) {
+        let scaled = |side: u32, by: f64| ((f64::from(side) * by).round() as u32).max(1);
+        match self {
+            FoximgResizeTarget::Size(Some(w), Some(h)) => (w.max(1), h.max(1)),
+            FoximgResizeTarget::Size(Some(w), None) => {
+                (w.max(1), scaled(height, f64::from(w) / f64::from(width)))
+            }
+            FoximgResizeTarget::Size(None, Some(h)) => {
+                (scaled(width, f64::from(h) / f64::from(height)), h.max(1))
+            }
+            FoximgResizeTarget::Size(None, None) => (width, height),
+            FoximgResizeTarget::Percent(percent) => (
+                scaled(width, percent / 100.),
+                scaled(height, percent / 100.),
+            ),
+            FoximgResizeTarget::LongEdge(edge) => {
+                let by = f64::from(edge) / f64::from(width.max(height));
+                (scaled(width, by), scaled(height, by))
+            }
+        }
+    }
+}
+
+/// Parses `WxH`, `Wx` or `xH` as a size, `N%` as a percentage and a bare `N` as the long edge.
+impl FromStr for FoximgResizeTarget {
+    type Err = String;
+
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid size \"{target}\"");
+        let side = |side: &str| -> Result<Option<u32>, String> {
+            match side {
+                "" => Ok(None),
+                side => match side.parse() {
+                    Ok(side) if side > 0 => Ok(Some(side)),
+                    _ => Err(invalid()),
+                },
+            }
+        };
+
+        if let Some((width, height)) = target.split_once(['x', 'X']) {
+            match (side(width)?, side(height)?) {
+                (None, None) => Err(invalid()),
+                (width, height) => Ok(FoximgResizeTarget::Size(width, height)),
+            }
+        } else if let Some(percent) = target.strip_suffix('%') {
+            match percent.parse() {
+                Ok(percent) if percent > 0. => Ok(FoximgResizeTarget::Percent(percent)),
+                _ => Err(invalid()),
+            }
+        } else {
+            side(target)?
+                .map(FoximgResizeTarget::LongEdge)
+                .ok_or_else(invalid)
+        }
+    }
+}
+
+/// Decodes the image at `path`, along with the format it's in.
+pub fn open(path: &Path) -> anyhow::Result<(DynamicImage, ImageFormat)> {
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader
+        .format()
+        .ok_or_else(|| anyhow::anyhow!("Not a recognized or supported image"))?;
+    Ok((reader.decode()?, format))
+}
+
+/// Resizes `image` to `target` with `filter`, keeping its colour type.
+pub fn resize(
+    image: &DynamicImage,
+    target: FoximgResizeTarget,
+    filter: FilterType,
+) -> DynamicImage {
+    let (width, height) = target.dimensions(image.width(), image.height());
+    image.resize_exact(width, height, filter)
+}
+
+/// Returns where the resized copy of the image at `path` is saved by default: next to it, with
+/// "-resized" after its name.
+fn resized_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().unwrap_or("png".as_ref()).to_string_lossy();
+    path.with_file_name(format!("{stem}-resized.{ext}"))
+}
+
+/// The size modes of the resize dialog, separated by semicolons as raygui expects.
+const RESIZE_MODES: &str = "Size;Percent;Long Edge";
+
+/// What was clicked in the resize dialog.
+#[derive(Clone, Copy)]
+enum FoximgResizeAction {
+    Save,
+    Cancel,
+}
+
+/// The state of an open resize dialog.
+struct FoximgResizeDialog {
+    /// Shared with the thread the preview is resized on.
+    source: Arc<DynamicImage>,
+    format: ImageFormat,
+    /// Which of `RESIZE_MODES` the size is given in.
+    mode: i32,
+    width: i32,
+    height: i32,
+    lock_aspect: bool,
+    percent: i32,
+    long_edge: i32,
+    /// The index of the filter into `FILTERS`.
+    filter: i32,
+    /// Which value box is being typed into, if any.
+    editing: Option<usize>,
+    /// What the preview was last made for, even if making it failed.
+    preview_for: Option<(FoximgResizeTarget, FilterType)>,
+    preview: Option<(DynamicImage, FoximgImage)>,
+    /// The preview being resized on a background thread, and what for.
+    pending: Option<((FoximgResizeTarget, FilterType), Receiver<DynamicImage>)>,
+    /// What the settings were last frame, and when they changed to that.
+    settled: Option<(FoximgResizeTarget, FilterType, f64)>,
+}
+
+impl FoximgResizeDialog {
+    fn new(source: DynamicImage, format: ImageFormat) -> Self {
+        let (width, height) = source.dimensions();
+        let (width, height) = (width as i32, height as i32);
+        Self {
+            source: Arc::new(source),
+            format,
+            mode: 0,
+            width: width.min(MAX_SIDE),
+            height: height.min(MAX_SIDE),
+            lock_aspect: true,
+            percent: 100,
+            long_edge: width.max(height).min(MAX_SIDE),
+            filter: (FILTERS.len() - 1) as i32,
+            editing: None,
+            preview_for: None,
+            preview: None,
+            pending: None,
+            settled: None,
+        }
+    }
+
+    /// Returns the largest percentage the source can be resized by without a side going over
+    /// `MAX_SIDE`.
+    fn max_percent(&self) -> i32 {
+        let (width, height) = self.source.dimensions();
+        let longest = i64::from(width.max(height).max(1));
+        (i64::from(MAX_SIDE) * 100 / longest).clamp(1, i64::from(MAX_PERCENT)) as i32
+    }
+
+    fn target(&self) -> FoximgResizeTarget {
+        match self.mode {
+            0 => FoximgResizeTarget::Size(Some(self.width as u32), Some(self.height as u32)),
+            1 => FoximgResizeTarget::Percent(f64::from(self.percent.min(self.max_percent()))),
+            _ => FoximgResizeTarget::LongEdge(self.long_edge as u32),
+        }
+    }
+
+    fn filter(&self) -> FilterType {
+        FILTERS[self.filter as usize].0
+    }
+
+    /// Returns the resized image if the preview is up to date, or resizes it again otherwise.
+    fn resized(&self) -> DynamicImage {
+        match self.preview {
+            Some((ref resized, _)) if self.preview_for == Some((self.target(), self.filter())) => {
+                resized.clone()
+            }
+            _ => self::resize(&self.source, self.target(), self.filter()),
+        }
+    }
+
+    /// Redoes the preview on a background thread once the settings stop changing for
+    /// `PREVIEW_DELAY` seconds, and shows it once it's resized. Only one preview is resized at a
+    /// time.
+    fn update_preview(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rl_thread: &RaylibThread,
+    ) -> anyhow::Result<()> {
+        if let Some((resized_for, results)) = self.pending.take() {
+            match results.try_recv() {
+                Ok(resized) => {
+                    self.preview_for = Some(resized_for);
+                    self.preview = None;
+                    let rgba = resized.to_rgba8();
+                    let preview = FoximgImage::from_rgba(
+                        rl,
+                        rl_thread,
+                        rgba.as_raw(),
+                        rgba.width() as i32,
+                        rgba.height() as i32,
+                    )?;
+                    self.preview = Some((resized, preview));
+                }
+                Err(TryRecvError::Empty) => self.pending = Some((resized_for, results)),
+                Err(TryRecvError::Disconnected) => {
+                    self.preview_for = Some(resized_for);
+                    anyhow::bail!("The resize thread stopped");
+                }
+            }
+        }
+
+        let (target, filter) = (self.target(), self.filter());
+        let now = rl.get_time();
+        let settled_at = match self.settled {
+            Some((settled_target, settled_filter, at))
+                if (settled_target, settled_filter) == (target, filter) =>
+            {
+                at
+            }
+            _ => {
+                self.settled = Some((target, filter, now));
+                now
+            }
+        };
+
+        if self.pending.is_some()
+            || self.preview_for == Some((target, filter))
+            || now - settled_at < PREVIEW_DELAY
+        {
+            return Ok(());
+        }
+
+        let (sender, results) = mpsc::channel();
+        let source = Arc::clone(&self.source);
+        let spawned = thread::Builder::new()
+            .name("foximg-resize".to_string())
+            .spawn(move || {
+                // The dialog might've been closed in the meantime.
+                let _ = sender.send(self::resize(&source, target, filter));
+            });
+
+        match spawned {
+            Ok(_) => {
+                self.pending = Some(((target, filter), results));
+                Ok(())
+            }
+            Err(e) => {
+                self.preview_for = Some((target, filter));
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Keeps the aspect ratio of the source if a side changed from `before` while it's locked.
+    fn keep_aspect(&mut self, before: (i32, i32)) {
+        if !self.lock_aspect {
+            return;
+        }
+
+        let (width, height) = self.source.dimensions();
+        if self.width != before.0 {
+            let target = FoximgResizeTarget::Size(Some(self.width as u32), None);
+            self.height = (target.dimensions(width, height).1 as i32).min(MAX_SIDE);
+        } else if self.height != before.1 {
+            let target = FoximgResizeTarget::Size(None, Some(self.height as u32));
+            self.width = (target.dimensions(width, height).0 as i32).min(MAX_SIDE);
+        }
+    }
+}
+
+impl Foximg {
+    /// Shows the resize dialog for the current image. This runs its own loop until the resized
+    /// image is saved or the dialog is closed with its Cancel button or Escape. Does nothing if
+    /// the UI is locked.
+    pub fn resize_img(&mut self) {
+        let Some(ref images) = self.images else {
+            return;
+        };
+
+        if self.lock.is_some() {
+            return;
+        }
+
+        let path = images.img_path().to_path_buf();
+        let mut dialog = match self::open(&path) {
+            Ok((source, format)) => FoximgResizeDialog::new(source, format),
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    &format!("FOXIMG: Failed to open {path:?} for resizing:"),
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                self.toast("Couldn't open the image for resizing");
+                return;
+            }
+        };
+
+        let mut action = None;
+        while !self.rl.window_should_close() {
+            self.update();
+
+            match action.take() {
+                Some(FoximgResizeAction::Save) if self.save_resized(&path, &dialog) => break,
+                Some(FoximgResizeAction::Cancel) => break,
+                _ => (),
+            }
+
+            if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) || self.images.is_none() {
+                break;
+            }
+
+            if let Err(e) = dialog.update_preview(&mut self.rl, &self.rl_thread) {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    "FOXIMG: Failed to preview resized image:",
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+            }
+
+            FoximgDraw::begin(self, |mut d, images| {
+                let screen_width = d.d.get_screen_width().as_f32();
+                let screen_height = d.d.get_screen_height().as_f32();
+                match (&dialog.preview, images) {
+                    (Some((_, preview)), _) => {
+                        preview.draw_center_scaled(
+                            &mut d.d,
+                            screen_width,
+                            screen_height,
+                            1.,
+                            Color::WHITE,
+                        );
+                    }
+                    (None, Some(images)) => d.draw_current_img(images),
+                    (None, None) => (),
+                }
+                action = d.draw_resize_dialog(&mut dialog);
+            });
+        }
+    }
+
+    /// Opens the resize dialog if `Resize` is pressed. Returns true if so.
+    pub fn resize_img_key(&mut self) -> bool {
+        if self.images.is_some() && self.is_action_pressed(FoximgAction::Resize) {
+            self.resize_img();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Saves the image resized by `dialog` where the user picks, with the encoder of the format the
+    /// image at `path` is in. Returns true if it got saved.
+    fn save_resized(&mut self, path: &Path, dialog: &FoximgResizeDialog) -> bool {
+        let Some(output) = tinyfiledialogs::save_file_dialog(
+            "Save Resized Image",
+            &self::resized_path(path).to_string_lossy(),
+        ) else {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_DEBUG,
+                "FOXIMG: Cancelled saving resized image",
+            );
+            return false;
+        };
+
+        let output = PathBuf::from(output);
+        let name = output.file_name().unwrap_or_default().to_string_lossy();
+        match dialog.resized().save_with_format(&output, dialog.format) {
+            Ok(()) => {
+                self.toast(format!("Saved \"{name}\""));
+                true
+            }
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    &format!("FOXIMG: Failed to save resized image to {output:?}:"),
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                self.toast(format!("Couldn't save \"{name}\""));
+                false
+            }
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the resize dialog in the top right of the window, and returns what was clicked in it,
+    /// if anything.
+    fn draw_resize_dialog(
+        &mut self,
+        dialog: &mut FoximgResizeDialog,
+    ) -> Option<FoximgResizeAction> {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const WIDTH: f32 = 420.;
+        const PADDING: f32 = 10.;
+        const LABEL_WIDTH: f32 = 80.;
+        const ROW: f32 = 30.;
+        const CONTROL: f32 = 22.;
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let rect = rrect(
+            screen_width - WIDTH - PADDING,
+            PADDING,
+            WIDTH,
+            PADDING * 2. + ROW * 6.,
+        );
+        let yudit = &self.resources.yudit;
+        let x = rect.x + PADDING;
+        let inner_width = WIDTH - PADDING * 2.;
+        let mut y = rect.y + PADDING;
+
+        self.d.draw_rectangle_rec(rect, self.style.bg.alpha(0.9));
+        self.d
+            .draw_rectangle_lines_ex(rect, 1., self.style.accent.alpha(1.));
+
+        let (source_width, source_height) = dialog.source.dimensions();
+        self.d.draw_text_ex(
+            yudit,
+            &format!("Resize ({source_width}x{source_height})"),
+            rvec2(x, y),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent,
+        );
+        y += ROW;
+
+        // raygui lays out toggle groups from the size of their first toggle.
+        self.d.gui_toggle_group(
+            rrect(x, y, (inner_width - PADDING * 2.) / 3., CONTROL),
+            RESIZE_MODES,
+            &mut dialog.mode,
+        );
+        y += ROW;
+
+        let before = (dialog.width, dialog.height);
+        let boxes: Vec<(&str, &mut i32, i32)> = match dialog.mode {
+            0 => vec![
+                ("Width", &mut dialog.width, MAX_SIDE),
+                ("Height", &mut dialog.height, MAX_SIDE),
+            ],
+            1 => {
+                let max_percent = dialog.max_percent();
+                vec![("Percent", &mut dialog.percent, max_percent)]
+            }
+            _ => vec![("Long Edge", &mut dialog.long_edge, MAX_SIDE)],
+        };
+        let box_width = 70.;
+        for (i, (label, value, max)) in boxes.into_iter().enumerate() {
+            let bx = x + LABEL_WIDTH + (box_width + LABEL_WIDTH) * i as f32;
+            let editing = dialog.editing == Some(i);
+            if self.d.gui_value_box(
+                rrect(bx, y, box_width, CONTROL),
+                label,
+                value,
+                1,
+                max,
+                editing,
+            ) {
+                dialog.editing = if editing { None } else { Some(i) };
+            }
+        }
+        if dialog.mode == 0 {
+            self.d.gui_check_box(
+                rrect(x + inner_width - 100., y + 2., CONTROL - 4., CONTROL - 4.),
+                "Lock",
+                &mut dialog.lock_aspect,
+            );
+            dialog.keep_aspect(before);
+        }
+        y += ROW;
+
+        let filter_names: Vec<&str> = FILTERS.iter().map(|&(_, name)| name).collect();
+        let toggle_width = (inner_width - 2. * (FILTERS.len() - 1) as f32) / FILTERS.len() as f32;
+        self.d.gui_toggle_group(
+            rrect(x, y, toggle_width, CONTROL),
+            &filter_names.join(";"),
+            &mut dialog.filter,
+        );
+        y += ROW;
+
+        let (width, height) = dialog.target().dimensions(source_width, source_height);
+        let status = if dialog.preview_for == Some((dialog.target(), dialog.filter())) {
+            format!("Resized to {width}x{height}. Shown at 1:1")
+        } else {
+            format!("Resizing to {width}x{height}...")
+        };
+        self.d.draw_text_ex(
+            yudit,
+            &status,
+            rvec2(x, y),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent.alpha(0.5),
+        );
+        y += ROW;
+
+        let button_width = (inner_width - PADDING) / 2.;
+        let mut clicked = None;
+        if self
+            .d
+            .gui_button(rrect(x, y, button_width, CONTROL), "Save...")
+        {
+            clicked = Some(FoximgResizeAction::Save);
+        }
+        if self.d.gui_button(
+            rrect(x + button_width + PADDING, y, button_width, CONTROL),
+            "Cancel",
+        ) {
+            clicked = Some(FoximgResizeAction::Cancel);
+        }
+
+        clicked
+    }
+}
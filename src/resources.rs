@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use raylib::prelude::*;
 
 use crate::images;
@@ -20,6 +22,13 @@ pub struct FoximgResources {
     pub flip: Texture2D,
     pub grad: Texture2D,
     pub yudit: Font,
+    // raylib only draws through shaders borrowed mutably, while resources are shared when drawing.
+    /// Highlights clipped shadows and highlights.
+    pub clipping: RefCell<Shader>,
+    /// Highlights sharp edges.
+    pub peaking: RefCell<Shader>,
+    /// Shows single channels of images, and previews their colour adjustments.
+    pub channels: RefCell<Shader>,
 }
 
 impl FoximgResources {
@@ -27,27 +36,48 @@ impl FoximgResources {
         static FLIP: &[u8] = include_bytes!("resources/flip.png");
         static GRAD: &[u8] = include_bytes!("resources/grad.png");
         static YUDIT: &[u8] = include_bytes!("resources/yudit.ttf");
+        static CLIPPING: &str = include_str!("resources/clipping.fs");
+        static PEAKING: &str = include_str!("resources/peaking.fs");
+        static CHANNELS: &str = include_str!("resources/channels.fs");
 
         let flip = images::new_resource(rl, rl_thread, FLIP, "flip.png").unwrap();
         let grad = images::new_resource(rl, rl_thread, GRAD, "grad.png").unwrap();
         let yudit = rl
             .load_font_from_memory(rl_thread, ".ttf", YUDIT, self::YUDIT_SIZE as i32, None)
             .unwrap();
+        let clipping = RefCell::new(rl.load_shader_from_memory(rl_thread, None, Some(CLIPPING)));
+        let peaking = RefCell::new(rl.load_shader_from_memory(rl_thread, None, Some(PEAKING)));
+        let channels = RefCell::new(rl.load_shader_from_memory(rl_thread, None, Some(CHANNELS)));
 
         yudit
             .texture()
             .set_texture_filter(rl_thread, TextureFilter::TEXTURE_FILTER_BILINEAR);
-        rl.gui_set_font(&yudit);
-        rl.gui_set_style(
-            GuiControl::DEFAULT,
-            GuiDefaultProperty::TEXT_SIZE,
-            self::BUTTON_FONT_SIZE as i32,
-        );
+
+        let resources = Self {
+            flip,
+            grad,
+            yudit,
+            clipping,
+            peaking,
+            channels,
+        };
+        resources.set_gui_font(rl);
         rl.trace_log(
             TraceLogLevel::LOG_INFO,
             "FOXIMG: Resources initialized successfully",
         );
 
-        Self { flip, grad, yudit }
+        resources
+    }
+
+    /// Sets the font and text size of raygui to foximg's. This needs to be done again whenever the
+    /// raygui style gets reset.
+    pub fn set_gui_font(&self, rl: &mut RaylibHandle) {
+        rl.gui_set_font(&self.yudit);
+        rl.gui_set_style(
+            GuiControl::DEFAULT,
+            GuiDefaultProperty::TEXT_SIZE,
+            self::BUTTON_FONT_SIZE as i32,
+        );
     }
 }
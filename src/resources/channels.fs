@@ -0,0 +1,74 @@
+#version 330
+
+// Shows a single channel of the image as grayscale, the image with its alpha ignored, or its colour
+// premultiplied by its alpha. `mode` is the index of the `FoximgChannelView` to show. The colour
+// adjustments of the image are previewed first, the same way `FoximgAdjustments::bake` bakes them.
+
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+uniform int mode;
+uniform float brightness;
+uniform float contrast;
+uniform float gamma;
+uniform float saturation;
+uniform float hue;
+uniform int grayscale;
+uniform int invert;
+
+out vec4 finalColor;
+
+const vec3 LUMA = vec3(0.2126, 0.7152, 0.0722);
+
+vec3 adjust(vec3 c) {
+    c = clamp(c + brightness, 0.0, 1.0);
+    c = clamp((c - 0.5) * pow((100.0 + contrast) / 100.0, 2.0) + 0.5, 0.0, 1.0);
+    c = pow(c, vec3(1.0 / gamma));
+
+    float luma = dot(c, LUMA);
+    c = clamp(luma + (c - luma) * saturation, 0.0, 1.0);
+
+    // The same matrix as `huerotate`, column by column.
+    float cosv = cos(radians(hue));
+    float sinv = sin(radians(hue));
+    mat3 rotate = mat3(
+        0.213 + cosv * 0.787 - sinv * 0.213,
+        0.213 - cosv * 0.213 + sinv * 0.143,
+        0.213 - cosv * 0.213 - sinv * 0.787,
+        0.715 - cosv * 0.715 - sinv * 0.715,
+        0.715 + cosv * 0.285 + sinv * 0.140,
+        0.715 - cosv * 0.715 + sinv * 0.715,
+        0.072 - cosv * 0.072 + sinv * 0.928,
+        0.072 - cosv * 0.072 - sinv * 0.283,
+        0.072 + cosv * 0.928 + sinv * 0.072
+    );
+    c = clamp(rotate * c, 0.0, 1.0);
+
+    if (grayscale != 0) {
+        c = vec3(dot(c, LUMA));
+    }
+    if (invert != 0) {
+        c = 1.0 - c;
+    }
+    return c;
+}
+
+void main() {
+    vec4 texel = texture(texture0, fragTexCoord);
+    texel.rgb = adjust(texel.rgb);
+
+    vec4 color;
+    switch (mode) {
+        case 1: color = vec4(vec3(texel.r), 1.0); break;
+        case 2: color = vec4(vec3(texel.g), 1.0); break;
+        case 3: color = vec4(vec3(texel.b), 1.0); break;
+        case 4: color = vec4(vec3(texel.a), 1.0); break;
+        case 5: color = vec4(texel.rgb, 1.0); break;
+        case 6: color = vec4(texel.rgb * texel.a, 1.0); break;
+        default: color = texel; break;
+    }
+
+    finalColor = color * colDiffuse * fragColor;
+}
@@ -0,0 +1,30 @@
+#version 330
+
+// Highlights pixels with a channel at or below `low`, or at or above `high`. Everything else is
+// left transparent so that the image drawn underneath shows through.
+
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform float low;
+uniform float high;
+uniform vec4 shadowColor;
+uniform vec4 highlightColor;
+
+out vec4 finalColor;
+
+void main() {
+    vec4 texel = texture(texture0, fragTexCoord);
+    if (texel.a == 0.0) {
+        discard;
+    }
+
+    if (max(texel.r, max(texel.g, texel.b)) >= high) {
+        finalColor = highlightColor;
+    } else if (min(texel.r, min(texel.g, texel.b)) <= low) {
+        finalColor = shadowColor;
+    } else {
+        discard;
+    }
+}
@@ -0,0 +1,40 @@
+#version 330
+
+// Highlights edges where the luma changes by more than `threshold`, found with a Sobel filter.
+// Neighbours are sampled at least a screen pixel apart, so that edges still show up when the image
+// is scaled down to fit the window.
+
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform float threshold;
+uniform vec4 peakingColor;
+
+out vec4 finalColor;
+
+float luma(vec2 offset, vec2 stride) {
+    vec3 rgb = texture(texture0, fragTexCoord + offset * stride).rgb;
+    return dot(rgb, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    vec2 stride = max(1.0 / vec2(textureSize(texture0, 0)), fwidth(fragTexCoord));
+
+    float tl = luma(vec2(-1.0, -1.0), stride);
+    float t = luma(vec2(0.0, -1.0), stride);
+    float tr = luma(vec2(1.0, -1.0), stride);
+    float l = luma(vec2(-1.0, 0.0), stride);
+    float r = luma(vec2(1.0, 0.0), stride);
+    float bl = luma(vec2(-1.0, 1.0), stride);
+    float b = luma(vec2(0.0, 1.0), stride);
+    float br = luma(vec2(1.0, 1.0), stride);
+
+    float gx = (tr + 2.0 * r + br) - (tl + 2.0 * l + bl);
+    float gy = (bl + 2.0 * b + br) - (tl + 2.0 * t + tr);
+    if (length(vec2(gx, gy)) < threshold || texture(texture0, fragTexCoord).a == 0.0) {
+        discard;
+    }
+
+    finalColor = peakingColor;
+}
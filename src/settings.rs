@@ -0,0 +1,349 @@
+//! Defines the settings panel, which changes `FoximgStyle` and `FoximgSettings` from inside the app.
+//! Changes apply as soon as they're made, and are written to the config files once it's closed.
+
+use std::borrow::Cow;
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw,
+    config::{FoximgConfig, FoximgFiltering, FoximgSettings, FoximgStyle},
+    images,
+    keybinds::FoximgAction,
+    resources,
+};
+
+/// The tabs of the settings panel, separated by semicolons as raygui expects.
+const SETTINGS_TABS: &str = "Theme;Window;Viewing";
+const THEME_TAB: i32 = 0;
+const WINDOW_TAB: i32 = 1;
+
+/// Every way images can be filtered, in the order of the filtering toggle group.
+const FILTERINGS: [(FoximgFiltering, &str); 2] = [
+    (FoximgFiltering::Nearest, "Nearest"),
+    (FoximgFiltering::Bilinear, "Bilinear"),
+];
+
+const FONT_SIZE: f32 = 16.;
+const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+const PADDING: f32 = 10.;
+const ROW: f32 = 30.;
+const CONTROL: f32 = 22.;
+/// The side of the square of each colour picker.
+const PICKER: f32 = 100.;
+/// How many themes are shown in each row of the theme tab.
+const THEMES_PER_ROW: usize = 3;
+
+/// Returns the height of the theme tab when it shows `themes` themes.
+fn theme_height(themes: usize) -> f32 {
+    ROW * (themes.div_ceil(THEMES_PER_ROW) + 2) as f32 + PICKER + PADDING
+}
+
+/// What was clicked in the settings panel.
+#[derive(Clone, Copy)]
+enum FoximgSettingsAction {
+    Defaults,
+    Close,
+}
+
+impl Foximg {
+    /// Shows the settings panel. This runs its own loop until the panel is closed with its Close
+    /// button or Escape, after which whatever changed is saved. Does nothing if the UI is locked.
+    pub fn open_settings(&mut self) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        let (style_from, settings_from) = (self.style.clone(), self.settings.clone());
+        let themes = self.themes.clone();
+        let mut style = style_from.clone();
+        let mut settings = settings_from.clone();
+        let mut tab = THEME_TAB;
+        let mut action = None;
+        while !self.rl.window_should_close() {
+            self.update();
+
+            match action.take() {
+                Some(FoximgSettingsAction::Defaults) => {
+                    let defaults = FoximgSettings::default();
+                    match tab {
+                        THEME_TAB => style = FoximgStyle::default(),
+                        WINDOW_TAB => {
+                            settings = FoximgSettings {
+                                remember_window: defaults.remember_window,
+                                always_on_top: defaults.always_on_top,
+                                ..settings
+                            }
+                        }
+                        _ => {
+                            settings = FoximgSettings {
+                                remember_window: settings.remember_window,
+                                always_on_top: settings.always_on_top,
+                                theme: settings.theme.clone(),
+                                ..defaults
+                            }
+                        }
+                    }
+                }
+                Some(FoximgSettingsAction::Close) => break,
+                None => (),
+            }
+
+            if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                break;
+            }
+
+            if style != self.style {
+                // Picking a theme from the library selects it, and changing any of its colours
+                // goes back to the style file.
+                settings.theme = style.theme_name(&themes);
+                self.apply_style(style.clone());
+            }
+            if settings != self.settings {
+                self.apply_settings(settings.clone());
+            }
+
+            FoximgDraw::begin(self, |mut d, images| {
+                if let Some(images) = images {
+                    d.draw_current_img(images);
+                }
+                action = d.draw_settings_panel(&themes, &mut tab, &mut style, &mut settings);
+            });
+        }
+
+        let style_saved =
+            self.style == style_from || !self.uses_style_file() || self.style.to_file(&self.rl);
+        let settings_saved = self.settings == settings_from || self.settings.to_file(&self.rl);
+        if self.style == style_from && self.settings == settings_from {
+            return;
+        }
+
+        if style_saved && settings_saved {
+            self.toast("Saved settings");
+        } else {
+            self.toast("Couldn't save settings");
+        }
+    }
+
+    /// Opens the settings panel if `Settings` is pressed. Returns true if so.
+    pub fn settings_key(&mut self) -> bool {
+        if self.is_action_pressed(FoximgAction::Settings) {
+            self.open_settings();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the settings panel in the top right of the window, and returns what was clicked in it,
+    /// if anything.
+    fn draw_settings_panel(
+        &mut self,
+        themes: &[FoximgStyle],
+        tab: &mut i32,
+        style: &mut FoximgStyle,
+        settings: &mut FoximgSettings,
+    ) -> Option<FoximgSettingsAction> {
+        const WIDTH: f32 = 460.;
+        const LABEL_WIDTH: f32 = 110.;
+        const VALUE_WIDTH: f32 = 50.;
+
+        let content_height = match *tab {
+            THEME_TAB => self::theme_height(themes.len()),
+            WINDOW_TAB => ROW * 2.,
+            _ => ROW * 4.,
+        };
+        let screen_width = self.d.get_screen_width().as_f32();
+        let rect = rrect(
+            screen_width - WIDTH - PADDING,
+            PADDING,
+            WIDTH,
+            PADDING * 2. + ROW * 3. + content_height,
+        );
+        let x = rect.x + PADDING;
+        let inner_width = WIDTH - PADDING * 2.;
+        let mut y = rect.y + PADDING;
+
+        self.d.draw_rectangle_rec(rect, self.style.bg.alpha(0.9));
+        self.d
+            .draw_rectangle_lines_ex(rect, 1., self.style.accent.alpha(1.));
+        self.d.draw_text_ex(
+            &self.resources.yudit,
+            "Settings",
+            rvec2(x, y),
+            FONT_SIZE,
+            FONT_SPACING,
+            self.style.accent,
+        );
+        y += ROW;
+
+        // raygui lays out toggle groups from the size of their first toggle.
+        self.d.gui_toggle_group(
+            rrect(x, y, (inner_width - 4.) / 3., CONTROL),
+            SETTINGS_TABS,
+            tab,
+        );
+        y += ROW;
+
+        match *tab {
+            THEME_TAB => y = self.draw_theme_settings(x, y, inner_width, themes, style),
+            WINDOW_TAB => {
+                self.d.gui_check_box(
+                    rrect(x, y, CONTROL, CONTROL),
+                    "Restore the window and filmstrip on startup",
+                    &mut settings.remember_window,
+                );
+                y += ROW;
+                self.d.gui_check_box(
+                    rrect(x, y, CONTROL, CONTROL),
+                    "Always on top",
+                    &mut settings.always_on_top,
+                );
+                y += ROW;
+            }
+            _ => {
+                let slider_width = inner_width - LABEL_WIDTH - VALUE_WIDTH;
+                let mut cache_size = settings.cache_size as f32;
+                let sliders = [
+                    ("Zoom step", &mut settings.zoom_step, 0.1, 1., "", 2),
+                    ("Pan speed", &mut settings.pan_speed, 0.25, 4., "x", 2),
+                    (
+                        "Cached images",
+                        &mut cache_size,
+                        1.,
+                        images::MAX_CACHE_SIZE as f32,
+                        "",
+                        0,
+                    ),
+                ];
+                for (label, value, min, max, suffix, decimals) in sliders {
+                    let text = format!("{:.decimals$}{suffix}", *value);
+                    self.d.gui_slider_bar(
+                        rrect(x + LABEL_WIDTH, y, slider_width, CONTROL),
+                        label,
+                        &text,
+                        value,
+                        min,
+                        max,
+                    );
+                    y += ROW;
+                }
+                settings.cache_size = cache_size.round() as usize;
+
+                let mut filtering = FILTERINGS
+                    .iter()
+                    .position(|&(filtering, _)| filtering == settings.filtering)
+                    .unwrap_or_default() as i32;
+                let filtering_names: Vec<&str> = FILTERINGS.iter().map(|&(_, name)| name).collect();
+                self.d
+                    .gui_label(rrect(x, y, LABEL_WIDTH, CONTROL), "Filtering");
+                self.d.gui_toggle_group(
+                    rrect(
+                        x + LABEL_WIDTH,
+                        y,
+                        (inner_width - LABEL_WIDTH - 2.) / FILTERINGS.len() as f32,
+                        CONTROL,
+                    ),
+                    &filtering_names.join(";"),
+                    &mut filtering,
+                );
+                settings.filtering = FILTERINGS[filtering as usize].0;
+                y += ROW;
+            }
+        }
+
+        let button_width = (inner_width - PADDING) / 2.;
+        let mut clicked = None;
+        if self
+            .d
+            .gui_button(rrect(x, y, button_width, CONTROL), "Defaults")
+        {
+            clicked = Some(FoximgSettingsAction::Defaults);
+        }
+        if self.d.gui_button(
+            rrect(x + button_width + PADDING, y, button_width, CONTROL),
+            "Close",
+        ) {
+            clicked = Some(FoximgSettingsAction::Close);
+        }
+
+        clicked
+    }
+
+    /// Draws the theme tab of the settings panel starting at `y`, and returns where the tab ends.
+    /// Changing any colour of a theme turns it into a custom one.
+    fn draw_theme_settings(
+        &mut self,
+        x: f32,
+        mut y: f32,
+        width: f32,
+        themes: &[FoximgStyle],
+        style: &mut FoximgStyle,
+    ) -> f32 {
+        // raygui starts a new row of toggles after every newline.
+        let theme_names: Vec<String> = themes
+            .chunks(THEMES_PER_ROW)
+            .map(|row| {
+                row.iter()
+                    .map(|theme| theme.optionals.name.as_deref().unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join(";")
+            })
+            .collect();
+        let current = themes
+            .iter()
+            .position(|theme| theme == style)
+            .map_or(-1, |i| i as i32);
+        let mut theme = current;
+        self.d.gui_toggle_group(
+            rrect(
+                x,
+                y,
+                (width - 2. * (THEMES_PER_ROW - 1) as f32) / THEMES_PER_ROW as f32,
+                CONTROL,
+            ),
+            &theme_names.join("\n"),
+            &mut theme,
+        );
+        let end = y + self::theme_height(themes.len());
+        if theme != current && theme >= 0 {
+            *style = themes[theme as usize].clone();
+            return end;
+        }
+        y += ROW * themes.len().div_ceil(THEMES_PER_ROW) as f32;
+
+        let before = style.clone();
+        self.d
+            .gui_check_box(rrect(x, y, CONTROL, CONTROL), "Dark", &mut style.dark);
+        y += ROW;
+
+        let column = width / 3.;
+        let pickers = [
+            ("Accent", &mut style.accent),
+            ("Background", &mut style.bg),
+            ("Commands", &mut style.command),
+        ];
+        for (i, (label, color)) in pickers.into_iter().enumerate() {
+            let cx = x + column * i as f32;
+            self.d.draw_text_ex(
+                &self.resources.yudit,
+                label,
+                rvec2(cx, y),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent,
+            );
+            **color = self
+                .d
+                .gui_color_picker(rrect(cx, y + ROW, PICKER, PICKER), "", **color);
+        }
+
+        if *style != before {
+            style.optionals.name = Some(Cow::Borrowed("Custom"));
+        }
+
+        end
+    }
+}
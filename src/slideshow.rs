@@ -0,0 +1,256 @@
+//! Defines the slideshow, which advances through the gallery on its own every few seconds.
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw,
+    config::{FoximgConfig, FoximgSlideshowSettings},
+    images::FoximgImage,
+    keybinds::FoximgAction,
+};
+
+/// The previous image of the slideshow, drawn on top of the current one while it fades out.
+struct FoximgSlideshowFade {
+    image: Rc<RefCell<FoximgImage>>,
+    elapsed: f32,
+    duration: f32,
+}
+
+pub struct FoximgSlideshow {
+    /// Seconds each image is shown for. This is separate from the persisted settings so that
+    /// `--slideshow=SECONDS` doesn't overwrite them.
+    interval: f32,
+    elapsed: f32,
+    /// The order images are shown in, as indices into the gallery.
+    order: Vec<usize>,
+    position: usize,
+    shuffled: bool,
+    fade: Option<FoximgSlideshowFade>,
+    rng: u64,
+}
+
+impl FoximgSlideshow {
+    pub fn new(interval: f32) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Self {
+            interval,
+            elapsed: 0.,
+            order: Vec::new(),
+            position: 0,
+            shuffled: false,
+            fade: None,
+            // xorshift gets stuck on 0.
+            rng: seed | 1,
+        }
+    }
+
+    /// Returns the next number of a xorshift64* generator. Shuffling images doesn't need anything
+    /// better than this.
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Builds the order of the slideshow starting from `current`. Shuffled orders start with
+    /// `current` followed by every other image in random order.
+    fn reorder(&mut self, len: usize, current: usize, shuffle: bool) {
+        self.order = (0..len).collect();
+        self.shuffled = shuffle;
+
+        if !shuffle {
+            self.position = current;
+            return;
+        }
+
+        self.order.swap(0, current);
+        for i in (2..len).rev() {
+            let j = 1 + (self.next_random() % i as u64) as usize;
+            self.order.swap(i, j);
+        }
+        self.position = 0;
+    }
+
+    /// Keeps the order in sync with the gallery, which might have changed since the last frame.
+    fn sync(&mut self, len: usize, current: usize, shuffle: bool) {
+        if self.order.len() != len || self.shuffled != shuffle {
+            self.reorder(len, current, shuffle);
+            self.elapsed = 0.;
+        } else if self.order[self.position] != current {
+            // The image was changed by the user, so the interval starts over from there.
+            self.position = self.order.iter().position(|&i| i == current).unwrap();
+            self.elapsed = 0.;
+        }
+    }
+
+    /// Returns the index of the next image, or `None` if the slideshow is over.
+    fn next(&mut self, settings: &FoximgSlideshowSettings) -> Option<usize> {
+        if self.position + 1 < self.order.len() {
+            self.position += 1;
+        } else if settings.repeat {
+            let current = self.order[self.position];
+            self.reorder(self.order.len(), current, settings.shuffle);
+            // Reshuffling starts with the current image, so it has to be skipped.
+            if settings.shuffle && self.order.len() > 1 {
+                self.position = 1;
+            }
+        } else {
+            return None;
+        }
+
+        self.order.get(self.position).copied()
+    }
+
+    /// Returns the image being faded out, along with how opaque it is.
+    pub fn fade(&self) -> Option<(&Rc<RefCell<FoximgImage>>, f32)> {
+        self.fade
+            .as_ref()
+            .map(|fade| (&fade.image, 1. - fade.elapsed / fade.duration))
+    }
+}
+
+impl Foximg {
+    /// Starts the slideshow, or stops it if it's already playing. Does nothing if the UI is locked.
+    pub fn toggle_slideshow(&mut self) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        if self.slideshow.take().is_some() {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_INFO, "FOXIMG: Stopped slideshow");
+        } else if self.images.is_some() {
+            self.slideshow = Some(FoximgSlideshow::new(self.slideshow_settings.interval));
+            self.rl.trace_log(
+                TraceLogLevel::LOG_INFO,
+                &format!(
+                    "FOXIMG: Started slideshow ({}s)",
+                    self.slideshow_settings.interval
+                ),
+            );
+        }
+    }
+
+    /// Starts or stops the slideshow if `Slideshow` is pressed. Returns true if so.
+    pub fn play_slideshow(&mut self) -> bool {
+        if self.is_action_pressed(FoximgAction::Slideshow) {
+            self.toggle_slideshow();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Changes the slideshow settings with `f` and saves them.
+    pub fn set_slideshow_settings(&mut self, f: impl FnOnce(&mut FoximgSlideshowSettings)) {
+        f(&mut self.slideshow_settings);
+        if let Some(ref mut slideshow) = self.slideshow {
+            slideshow.interval = self.slideshow_settings.interval;
+        }
+
+        self.slideshow_settings.to_file(&self.rl);
+    }
+
+    /// Advances the slideshow once the interval has passed.
+    pub fn update_slideshow(&mut self) {
+        let Some(mut slideshow) = self.slideshow.take() else {
+            return;
+        };
+
+        let frame_time = self.rl.get_frame_time();
+        let mut playing = true;
+
+        if let Some(ref mut fade) = slideshow.fade {
+            fade.elapsed += frame_time;
+            if fade.elapsed >= fade.duration {
+                slideshow.fade = None;
+            }
+        }
+
+        self.images_with(|f, images| {
+            let current = images.img_current() - 1;
+            slideshow.sync(images.len(), current, f.slideshow_settings.shuffle);
+
+            if f.slideshow_settings.pause_on_zoom && f.mouse_wheel > 0. {
+                return;
+            }
+
+            slideshow.elapsed += frame_time;
+            if slideshow.elapsed < slideshow.interval {
+                return;
+            }
+
+            let looped = !f.slideshow_settings.wait_for_animations
+                || images
+                    .img_get(&mut f.rl, &f.rl_thread)
+                    .is_none_or(|img| img.borrow().looped());
+            if !looped {
+                return;
+            }
+
+            let Some(next) = slideshow.next(&f.slideshow_settings) else {
+                playing = false;
+                return;
+            };
+
+            if f.slideshow_settings.crossfade
+                && f.slideshow_settings.crossfade_duration > 0.
+                && next != current
+            {
+                slideshow.fade =
+                    images
+                        .img_get(&mut f.rl, &f.rl_thread)
+                        .map(|image| FoximgSlideshowFade {
+                            image,
+                            elapsed: 0.,
+                            duration: f
+                                .slideshow_settings
+                                .crossfade_duration
+                                .min(slideshow.interval),
+                        });
+            }
+
+            slideshow.elapsed = 0.;
+            images.set_current(next);
+            images.update_window(f);
+            images.img_with(&mut f.rl, &f.rl_thread, |mut img| img.restart_looped());
+        });
+
+        if playing {
+            self.slideshow = Some(slideshow);
+        } else {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_INFO, "FOXIMG: Slideshow finished");
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the previous image of the slideshow fading out on top of the current one.
+    pub fn draw_slideshow_fade(&mut self, screen_width: f32, screen_height: f32) {
+        let Some((image, opacity)) = self.slideshow.and_then(FoximgSlideshow::fade) else {
+            return;
+        };
+
+        let image = image.borrow();
+        let scale = self.img_scale(&image, screen_width, screen_height);
+        image.draw_center_scaled(
+            &mut self.d,
+            screen_width,
+            screen_height,
+            scale,
+            Color::WHITE.alpha(opacity),
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Generates thumbnails that follow the freedesktop thumbnail specification:
+//! https://specifications.freedesktop.org/thumbnail-spec/latest/
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use image::{
+    DynamicImage, ExtendedColorType, ImageEncoder, ImageFormat, ImageReader, RgbaImage,
+    codecs::png::{PngDecoder, PngEncoder},
+};
+
+use crate::xdg;
+
+mod loader;
+mod md5;
+
+pub use loader::{FoximgThumbnail, FoximgThumbnails};
+
+/// Formats foximg can open and thus thumbnail.
+pub const FORMATS: &[ImageFormat] = &[
+    ImageFormat::Bmp,
+    ImageFormat::Jpeg,
+    ImageFormat::Dds,
+    ImageFormat::Hdr,
+    ImageFormat::Ico,
+    ImageFormat::Qoi,
+    ImageFormat::Tiff,
+    ImageFormat::Pnm,
+    ImageFormat::OpenExr,
+    ImageFormat::Png,
+    ImageFormat::WebP,
+    ImageFormat::Gif,
+];
+
+/// The size buckets defined by the thumbnail specification.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FoximgThumbnailSize {
+    Normal,
+    Large,
+    XLarge,
+    XXLarge,
+}
+
+impl FoximgThumbnailSize {
+    /// Returns the smallest bucket that fits a thumbnail of `size` pixels. Sizes bigger than the
+    /// largest bucket get clamped to it.
+    pub fn from_requested(size: u32) -> Self {
+        match size {
+            0..=128 => Self::Normal,
+            129..=256 => Self::Large,
+            257..=512 => Self::XLarge,
+            _ => Self::XXLarge,
+        }
+    }
+
+    /// Returns the maximum width and height of a thumbnail in this bucket.
+    pub const fn pixels(self) -> u32 {
+        match self {
+            Self::Normal => 128,
+            Self::Large => 256,
+            Self::XLarge => 512,
+            Self::XXLarge => 1024,
+        }
+    }
+
+    /// Returns the name of the folder in the thumbnail cache holding thumbnails of this bucket.
+    pub const fn dir_name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Large => "large",
+            Self::XLarge => "x-large",
+            Self::XXLarge => "xx-large",
+        }
+    }
+}
+
+/// Returns the canonical `file://` URI of `path`.
+pub fn uri(path: &Path) -> String {
+    let prefix = if cfg!(target_os = "windows") {
+        "file:///"
+    } else {
+        "file://"
+    };
+
+    format!("{prefix}{}", xdg::percent_encode(path))
+}
+
+/// Returns the modification time of `path` in seconds since the Unix epoch.
+pub fn mtime(path: &Path) -> anyhow::Result<u64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// Decodes the image at `path` and scales it down to fit `size`. Images smaller than `size` are
+/// left as they are. Animated images are thumbnailed by their first frame. Returns the thumbnail
+/// along with the dimensions of the original image.
+pub fn generate(path: &Path, size: FoximgThumbnailSize) -> anyhow::Result<(RgbaImage, (u32, u32))> {
+    let image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+    let dimensions = (image.width(), image.height());
+    let side = size.pixels();
+    let image = if image.width() > side || image.height() > side {
+        image.thumbnail(side, side)
+    } else {
+        image
+    };
+
+    let thumbnail = match image {
+        DynamicImage::ImageRgba8(image) => image,
+        image => image.into_rgba8(),
+    };
+
+    Ok((thumbnail, dimensions))
+}
+
+/// Writes `thumbnail` as a PNG to `output` with the `Thumb::URI` and `Thumb::MTime` text chunks
+/// required by the specification, plus the optional size and dimension chunks.
+pub fn write(
+    thumbnail: &RgbaImage,
+    input: &Path,
+    input_dimensions: (u32, u32),
+    output: &Path,
+) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut encoder = PngEncoder::new(&mut writer);
+
+    encoder.add_text_chunk("Thumb::URI", self::uri(input));
+    encoder.add_text_chunk("Thumb::MTime", self::mtime(input)?.to_string());
+    encoder.add_text_chunk("Thumb::Size", fs::metadata(input)?.len().to_string());
+    encoder.add_text_chunk("Thumb::Image::Width", input_dimensions.0.to_string());
+    encoder.add_text_chunk("Thumb::Image::Height", input_dimensions.1.to_string());
+    encoder.add_text_chunk("Software", concat!("foximg ", env!("CARGO_PKG_VERSION")));
+    encoder.write_image(
+        thumbnail.as_raw(),
+        thumbnail.width(),
+        thumbnail.height(),
+        ExtendedColorType::Rgba8,
+    )?;
+
+    Ok(())
+}
+
+/// Returns the folder of the thumbnail cache holding thumbnails of `size`. This is
+/// `$XDG_CACHE_HOME/thumbnails/` or `$HOME/.cache/thumbnails/` if unset. Returns `None` if neither
+/// variable is set, in which case thumbnails simply don't get cached.
+fn cache_dir(size: FoximgThumbnailSize) -> Option<PathBuf> {
+    let mut path = xdg::cache_home()?;
+    path.push("thumbnails");
+    path.push(size.dir_name());
+    Some(path)
+}
+
+/// Returns where the thumbnail of `path` is cached. The file might not exist yet.
+pub fn cache_path(path: &Path, size: FoximgThumbnailSize) -> Option<PathBuf> {
+    let name = format!("{}.png", md5::hex_digest(self::uri(path).as_bytes()));
+    Some(self::cache_dir(size)?.join(name))
+}
+
+/// Loads the cached thumbnail of `path`. Returns `None` if there's no thumbnail cached or if it's
+/// outdated according to its `Thumb::MTime`.
+pub fn load_cached(path: &Path, size: FoximgThumbnailSize) -> Option<RgbaImage> {
+    let cache_path = self::cache_path(path, size)?;
+    let decoder = PngDecoder::new(BufReader::new(File::open(cache_path).ok()?)).ok()?;
+    let mtime = self::mtime(path).ok()?.to_string();
+
+    if decoder.text_chunk("Thumb::MTime") != Some(mtime.as_str()) {
+        return None;
+    }
+
+    Some(DynamicImage::from_decoder(decoder).ok()?.into_rgba8())
+}
+
+/// Saves `thumbnail` into the thumbnail cache. The thumbnail is written to a temporary file first
+/// and then renamed, so that other programs never read a half-written thumbnail.
+pub fn save_cached(
+    thumbnail: &RgbaImage,
+    path: &Path,
+    dimensions: (u32, u32),
+    size: FoximgThumbnailSize,
+) -> anyhow::Result<()> {
+    let cache_path = self::cache_path(path, size)
+        .ok_or_else(|| anyhow::anyhow!("Neither \"XDG_CACHE_HOME\" nor \"HOME\" are set"))?;
+    let cache_dir = cache_path.parent().unwrap();
+
+    if !cache_dir.exists() {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(cache_dir)?;
+    }
+
+    let tmp_path = cache_path.with_extension(format!("png.{}.tmp", std::process::id()));
+    self::write(thumbnail, path, dimensions, &tmp_path)?;
+
+    #[cfg(unix)]
+    fs::set_permissions(
+        &tmp_path,
+        std::os::unix::fs::PermissionsExt::from_mode(0o600),
+    )?;
+
+    fs::rename(tmp_path, cache_path)?;
+    Ok(())
+}
+
+/// Returns the thumbnail of `path`, either from the thumbnail cache or by generating and caching
+/// it.
+pub fn load_or_generate(path: &Path, size: FoximgThumbnailSize) -> anyhow::Result<RgbaImage> {
+    if let Some(thumbnail) = self::load_cached(path, size) {
+        return Ok(thumbnail);
+    }
+
+    let (thumbnail, dimensions) = self::generate(path, size)?;
+    // Failing to cache a thumbnail shouldn't stop it from being shown.
+    let _ = self::save_cached(&thumbnail, path, dimensions, size);
+    Ok(thumbnail)
+}
+
+/// Returns the contents of a `.thumbnailer` file registering foximg as the thumbnailer for every
+/// format in `FORMATS`.
+pub fn thumbnailer() -> String {
+    // File managers report the specific PNM types rather than the generic one `ImageFormat` gives.
+    const PNM_MIME_TYPES: [&str; 3] = [
+        "image/x-portable-bitmap",
+        "image/x-portable-graymap",
+        "image/x-portable-pixmap",
+    ];
+
+    let mut mime_types: Vec<&str> = FORMATS.iter().map(|format| format.to_mime_type()).collect();
+    if FORMATS.contains(&ImageFormat::Pnm) {
+        mime_types.extend(PNM_MIME_TYPES);
+    }
+    mime_types.dedup();
+
+    format!(
+        "[Thumbnailer Entry]\nTryExec=foximg\nExec=foximg --thumbnail %i %o %s\nMimeType={};\n",
+        mime_types.join(";")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn shipped_thumbnailer_is_up_to_date() {
+        // Packages ship the committed file, which `make thumbnailer` regenerates.
+        assert_eq!(
+            super::thumbnailer(),
+            include_str!("../share/thumbnailers/foximg.thumbnailer")
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Loads thumbnails on background threads and keeps the ones that are currently needed as textures.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::c_void,
+    mem::ManuallyDrop,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Condvar, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
+
+use image::RgbaImage;
+use raylib::prelude::*;
+
+use super::FoximgThumbnailSize;
+
+type FoximgThumbnailResult = (PathBuf, anyhow::Result<RgbaImage>);
+
+struct FoximgThumbnailQueue {
+    jobs: VecDeque<PathBuf>,
+    closed: bool,
+}
+
+type FoximgThumbnailQueueLock = Arc<(Mutex<FoximgThumbnailQueue>, Condvar)>;
+
+pub enum FoximgThumbnail {
+    Loading,
+    Loaded(Texture2D),
+    Failed,
+}
+
+/// A pool of worker threads that load thumbnails, along with the textures of the thumbnails that
+/// have been loaded so far. Thumbnails get unloaded with `retain`, which allows recycling them as
+/// a view scrolls through a gallery.
+pub struct FoximgThumbnails {
+    queue: FoximgThumbnailQueueLock,
+    results: Receiver<FoximgThumbnailResult>,
+    thumbnails: HashMap<PathBuf, FoximgThumbnail>,
+}
+
+impl FoximgThumbnails {
+    const MAX_WORKERS: usize = 4;
+
+    pub fn new(rl: &RaylibHandle, size: FoximgThumbnailSize) -> Self {
+        let queue = Arc::new((
+            Mutex::new(FoximgThumbnailQueue {
+                jobs: VecDeque::new(),
+                closed: false,
+            }),
+            Condvar::new(),
+        ));
+
+        let (sender, results) = mpsc::channel();
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(Self::MAX_WORKERS);
+
+        for i in 0..workers {
+            let queue = queue.clone();
+            let sender = sender.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("foximg-thumbnailer-{i}"))
+                .spawn(move || Self::worker(queue, sender, size));
+
+            if let Err(e) = spawned {
+                rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    "FOXIMG: Failed to spawn thumbnailer thread:",
+                );
+                rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+            }
+        }
+
+        rl.trace_log(
+            TraceLogLevel::LOG_DEBUG,
+            &format!("FOXIMG: Started {workers} thumbnailer threads ({size:?})"),
+        );
+
+        Self {
+            queue,
+            results,
+            thumbnails: HashMap::new(),
+        }
+    }
+
+    fn worker(
+        queue: FoximgThumbnailQueueLock,
+        sender: Sender<FoximgThumbnailResult>,
+        size: FoximgThumbnailSize,
+    ) {
+        let (queue, condvar) = &*queue;
+        loop {
+            let path = {
+                let mut queue = queue.lock().unwrap();
+                while queue.jobs.is_empty() && !queue.closed {
+                    queue = condvar.wait(queue).unwrap();
+                }
+
+                if queue.closed {
+                    return;
+                }
+                queue.jobs.pop_front().unwrap()
+            };
+
+            let thumbnail = super::load_or_generate(&path, size);
+            if sender.send((path, thumbnail)).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn load_texture(
+        rl: &mut RaylibHandle,
+        rl_thread: &RaylibThread,
+        thumbnail: &RgbaImage,
+    ) -> anyhow::Result<Texture2D> {
+        let image = ManuallyDrop::new(unsafe {
+            Image::from_raw(ffi::Image {
+                data: thumbnail.as_ptr() as *mut c_void,
+                width: thumbnail.width() as i32,
+                height: thumbnail.height() as i32,
+                mipmaps: 1,
+                format: ffi::PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+            })
+        });
+
+        let texture = rl.load_texture_from_image(rl_thread, &image)?;
+        texture.set_texture_filter(rl_thread, TextureFilter::TEXTURE_FILTER_BILINEAR);
+        Ok(texture)
+    }
+
+    /// Queues the thumbnail of `path` to be loaded. Does nothing if it's already loaded or queued.
+    pub fn request(&mut self, path: &Path) {
+        if self.thumbnails.contains_key(path) {
+            return;
+        }
+
+        self.thumbnails
+            .insert(path.to_path_buf(), FoximgThumbnail::Loading);
+
+        let (queue, condvar) = &*self.queue;
+        queue.lock().unwrap().jobs.push_back(path.to_path_buf());
+        condvar.notify_one();
+    }
+
+    /// Returns the state of the thumbnail of `path`, or `None` if it was never requested.
+    pub fn get(&self, path: &Path) -> Option<&FoximgThumbnail> {
+        self.thumbnails.get(path)
+    }
+
+    /// Unloads every thumbnail for which `keep` returns false. Queued thumbnails that haven't been
+    /// loaded yet get dropped from the queue.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        self.thumbnails.retain(|path, _| keep(path));
+
+        let (queue, _) = &*self.queue;
+        queue
+            .lock()
+            .unwrap()
+            .jobs
+            .retain(|path| self.thumbnails.contains_key(path));
+    }
+
+    /// Uploads the thumbnails the worker threads finished loading since the last call.
+    pub fn update(&mut self, rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
+        while let Ok((path, thumbnail)) = self.results.try_recv() {
+            // The thumbnail might have been unloaded while it was being loaded.
+            let Some(entry) = self.thumbnails.get_mut(&path) else {
+                continue;
+            };
+
+            *entry = match thumbnail
+                .and_then(|thumbnail| Self::load_texture(rl, rl_thread, &thumbnail))
+            {
+                Ok(texture) => FoximgThumbnail::Loaded(texture),
+                Err(e) => {
+                    rl.trace_log(
+                        TraceLogLevel::LOG_WARNING,
+                        &format!("FOXIMG: Failed to load thumbnail of {path:?}:"),
+                    );
+                    rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                    FoximgThumbnail::Failed
+                }
+            };
+        }
+    }
+}
+
+impl Drop for FoximgThumbnails {
+    fn drop(&mut self) {
+        // Worker threads aren't joined, since they might be busy decoding a huge image. They exit
+        // once they finish and see the queue is closed.
+        let (queue, condvar) = &*self.queue;
+        queue.lock().unwrap().closed = true;
+        condvar.notify_all();
+    }
+}
@@ -0,0 +1,111 @@
+//! Defines toasts, short messages that show up at the bottom of the window and fade out on their
+//! own.
+
+use std::collections::VecDeque;
+
+use raylib::prelude::*;
+
+use crate::{Foximg, FoximgDraw, resources};
+
+struct FoximgToast {
+    text: String,
+    remaining: f32,
+}
+
+#[derive(Default)]
+pub struct FoximgToasts {
+    toasts: VecDeque<FoximgToast>,
+}
+
+impl FoximgToasts {
+    /// Seconds a toast is shown for.
+    const DURATION: f32 = 2.5;
+    /// Seconds a notice is shown for. Notices are toasts about something the user needs to act on,
+    /// so they stay for longer.
+    const NOTICE_DURATION: f32 = 8.;
+    /// Seconds a toast takes to fade out at the end of its duration.
+    const FADE: f32 = 0.5;
+    /// Toasts shown at once. Older toasts get dropped to make room for new ones.
+    const MAX: usize = 4;
+
+    pub fn push(&mut self, text: String) {
+        self.push_for(text, Self::DURATION);
+    }
+
+    fn push_for(&mut self, text: String, duration: f32) {
+        if self.toasts.len() == Self::MAX {
+            self.toasts.pop_front();
+        }
+
+        self.toasts.push_back(FoximgToast {
+            text,
+            remaining: duration,
+        });
+    }
+
+    pub fn update(&mut self, frame_time: f32) {
+        for toast in &mut self.toasts {
+            toast.remaining -= frame_time;
+        }
+
+        self.toasts.retain(|toast| toast.remaining > 0.);
+    }
+}
+
+impl Foximg {
+    /// Shows `text` as a toast and logs it.
+    pub fn toast(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        self.rl
+            .trace_log(TraceLogLevel::LOG_INFO, &format!("FOXIMG: {text}"));
+        self.toasts.push(text);
+    }
+
+    /// Shows `text` as a toast that stays for longer than usual, and logs it as a warning.
+    pub fn notice(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        self.rl
+            .trace_log(TraceLogLevel::LOG_WARNING, &format!("FOXIMG: {text}"));
+        self.toasts.push_for(text, FoximgToasts::NOTICE_DURATION);
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the toasts stacked above the bottom edge of the window, newest at the bottom.
+    pub fn draw_toasts(&mut self) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const PADDING: f32 = 6.;
+        const MARGIN: f32 = 10.;
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let mut y = self.d.get_screen_height().as_f32() - self.filmstrip_height() - MARGIN;
+
+        for toast in self.toasts.toasts.iter().rev() {
+            let opacity = (toast.remaining / FoximgToasts::FADE).min(1.);
+            let text_size = self
+                .resources
+                .yudit
+                .measure_text(&toast.text, FONT_SIZE, FONT_SPACING);
+            let rect = rrect(
+                screen_width / 2. - text_size.x / 2. - PADDING,
+                y - text_size.y - PADDING * 2.,
+                text_size.x + PADDING * 2.,
+                text_size.y + PADDING * 2.,
+            );
+
+            self.d
+                .draw_rectangle_rec(rect, self.style.bg.alpha(0.8 * opacity));
+            self.d.draw_text_ex(
+                &self.resources.yudit,
+                &toast.text,
+                rvec2(rect.x + PADDING, rect.y + PADDING),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent.alpha(opacity),
+            );
+
+            y = rect.y - PADDING;
+        }
+    }
+}
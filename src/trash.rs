@@ -0,0 +1,410 @@
+//! Moves images to the trash following the freedesktop trash specification:
+//! https://specifications.freedesktop.org/trash-spec/latest/
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    Foximg, FoximgDraw, config::FoximgConfig, culling::FoximgFileOp, images::FoximgImageLoader,
+    keybinds::FoximgAction, xdg, xmp,
+};
+
+/// A file that was moved to the trash, along with everything needed to restore it.
+pub struct FoximgTrashed {
+    original: PathBuf,
+    file: PathBuf,
+    info: PathBuf,
+}
+
+/// An image that was trashed from the gallery. It remembers where it was in the gallery so that
+/// undoing puts it back in the same place.
+pub struct FoximgTrashedImage {
+    trashed: FoximgTrashed,
+    /// The XMP sidecar of the image, trashed along with it.
+    sidecar: Option<FoximgTrashed>,
+    index: usize,
+    loader: FoximgImageLoader,
+}
+
+fn create_dir(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+/// Returns the home trash, `$XDG_DATA_HOME/Trash`, creating it if it doesn't exist.
+fn trash_dir() -> anyhow::Result<PathBuf> {
+    let trash = xdg::data_home()
+        .ok_or_else(|| anyhow::anyhow!("Neither \"XDG_DATA_HOME\" nor \"HOME\" are set"))?
+        .join("Trash");
+
+    self::create_dir(&trash.join("files"))?;
+    self::create_dir(&trash.join("info"))?;
+    Ok(trash)
+}
+
+/// Returns the trash at the top of the filesystem `path` is on, creating it if it doesn't exist,
+/// along with that top folder. Returns `None` if `path` is on the same filesystem as `home`, the home
+/// trash. The shared `$topdir/.Trash/$uid` is used if the admin set it up, falling back to
+/// `$topdir/.Trash-$uid` otherwise.
+#[cfg(unix)]
+fn topdir_trash(path: &Path, home: &Path) -> io::Result<Option<(PathBuf, PathBuf)>> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let parent = path
+        .parent()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Path has no parent folder"))?;
+    let device = fs::metadata(parent)?.dev();
+    if fs::metadata(home)?.dev() == device {
+        return Ok(None);
+    }
+
+    // The top folder is the highest one still on the same filesystem, usually where it's mounted.
+    let mut topdir = parent;
+    while let Some(up) = topdir.parent() {
+        if fs::metadata(up)?.dev() != device {
+            break;
+        }
+        topdir = up;
+    }
+
+    // Files are owned by whoever created them, so the uid is read off one created in the home
+    // trash. `/proc/self` would only work on Linux.
+    let probe = home.join(format!(".foximg-uid-{}", std::process::id()));
+    let uid = fs::File::create(&probe)?.metadata()?.uid();
+    fs::remove_file(&probe)?;
+    let create = |trash: &Path| {
+        self::create_dir(&trash.join("files"))?;
+        self::create_dir(&trash.join("info"))
+    };
+
+    // The spec only allows using a shared trash that isn't a symbolic link and has the sticky bit
+    // set, so that users can't remove each other's files.
+    let shared = topdir.join(".Trash");
+    let shared_usable = fs::symlink_metadata(&shared)
+        .is_ok_and(|metadata| metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0);
+    if shared_usable {
+        let trash = shared.join(uid.to_string());
+        if create(&trash).is_ok() {
+            return Ok(Some((trash, topdir.to_path_buf())));
+        }
+    }
+
+    let trash = topdir.join(format!(".Trash-{uid}"));
+    create(&trash)?;
+    Ok(Some((trash, topdir.to_path_buf())))
+}
+
+/// Moves `from` to `to`. Files on other filesystems can't be renamed into the home trash, so they
+/// get copied and deleted instead.
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+        res => res,
+    }
+}
+
+/// Returns the `n`th candidate name for `path` inside the trash: `name.ext`, `name.2.ext`,
+/// `name.3.ext` and so on.
+fn trash_name(path: &Path, n: usize) -> OsString {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    if n > 1 {
+        name.push(format!(".{n}"));
+    }
+    if let Some(ext) = path.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    name
+}
+
+impl FoximgTrashed {
+    /// Moves `path` to the trash. `path` must be absolute. Files on other filesystems than the home
+    /// trash go to the trash at the top of their own filesystem, and only get copied into the home
+    /// trash if that one can't be used.
+    pub fn trash(path: &Path) -> anyhow::Result<Self> {
+        if cfg!(target_os = "windows") {
+            anyhow::bail!("Moving files to the trash isn't supported on Windows");
+        }
+
+        let home = self::trash_dir()?;
+        #[cfg(unix)]
+        let (trash, topdir) = match self::topdir_trash(path, &home) {
+            Ok(Some((trash, topdir))) => (trash, Some(topdir)),
+            _ => (home, None),
+        };
+        #[cfg(not(unix))]
+        let (trash, topdir) = (home, None::<PathBuf>);
+        // Paths in a topdir trash are relative to the top folder, so that they still work if the
+        // filesystem gets mounted somewhere else.
+        let info_path = topdir
+            .as_deref()
+            .and_then(|topdir| path.strip_prefix(topdir).ok())
+            .unwrap_or(path);
+
+        let (file, info, mut info_file) = (1..)
+            .map(|n| self::trash_name(path, n))
+            .find_map(|name| {
+                let file = trash.join("files").join(&name);
+                let mut info_name = name;
+                info_name.push(".trashinfo");
+                let info = trash.join("info").join(info_name);
+
+                if file.exists() {
+                    return None;
+                }
+
+                // Creating the info file is what reserves the name in the trash.
+                match OpenOptions::new().write(true).create_new(true).open(&info) {
+                    Ok(info_file) => Some(Ok((file, info, info_file))),
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => None,
+                    Err(e) => Some(Err(e)),
+                }
+            })
+            .unwrap()?;
+
+        let written = write!(
+            info_file,
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            xdg::percent_encode(info_path),
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        )
+        .and_then(|_| self::move_file(path, &file));
+
+        if let Err(e) = written {
+            let _ = fs::remove_file(&info);
+            return Err(e.into());
+        }
+
+        Ok(Self {
+            original: path.to_path_buf(),
+            file,
+            info,
+        })
+    }
+
+    /// Moves the file back to where it was before it was trashed. The file is restored even if its
+    /// trash info can't be removed afterwards, which only gets logged.
+    pub fn restore(&self, rl: &mut RaylibHandle) -> anyhow::Result<()> {
+        if self.original.exists() {
+            anyhow::bail!("{:?} already exists", self.original);
+        }
+
+        self::move_file(&self.file, &self.original)?;
+        if let Err(e) = fs::remove_file(&self.info) {
+            rl.trace_log(
+                TraceLogLevel::LOG_WARNING,
+                &format!("FOXIMG: Failed to remove {:?} after restoring:", self.info),
+            );
+            rl.trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+        }
+        Ok(())
+    }
+
+    pub fn original(&self) -> &Path {
+        &self.original
+    }
+}
+
+impl Foximg {
+    /// Shows a prompt asking whether to trash `name`. This runs its own loop until the prompt gets
+    /// answered. Returns true if the user agreed.
+    fn confirm_trash(&mut self, name: &str) -> bool {
+        let message = format!("Move \"{name}\" to the trash?");
+
+        // The keys that trashed the image are still pressed until a frame of the prompt is drawn.
+        let mut drawn = false;
+        while !self.rl.window_should_close() {
+            self.update();
+
+            if drawn && self.is_action_pressed(FoximgAction::ConfirmTrash) {
+                return true;
+            } else if drawn && self.is_action_pressed(FoximgAction::CancelTrash) {
+                return false;
+            }
+
+            let mut result = -1;
+            FoximgDraw::begin(self, |mut d, images| {
+                if let Some(images) = images {
+                    d.draw_current_img(images);
+                }
+
+                result = d.draw_trash_prompt(&message);
+            });
+            drawn = true;
+
+            match result {
+                1 => return true,
+                0 | 2 => return false,
+                _ => (),
+            }
+        }
+
+        false
+    }
+
+    /// Moves the current image to the trash and removes it from the gallery. Asks first if
+    /// `trash_settings.confirm` is set. Does nothing if the UI is locked.
+    pub fn trash_current(&mut self) {
+        let Some(ref images) = self.images else {
+            return;
+        };
+
+        if self.lock.is_some() {
+            return;
+        }
+
+        let path = images.img_path().to_path_buf();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if self.trash_settings.confirm && !self.confirm_trash(&name) {
+            self.rl
+                .trace_log(TraceLogLevel::LOG_DEBUG, "FOXIMG: Cancelled trashing");
+            return;
+        }
+
+        self.images_with(|f, images| match FoximgTrashed::trash(&path) {
+            Ok(trashed) => {
+                let index = images.img_current() - 1;
+                let (_, loader) = images.remove(index);
+                let sidecar = f.trash_sidecar(&path);
+
+                f.toast(format!("Moved \"{name}\" to the trash"));
+                f.file_ops.push(FoximgFileOp::Trash(FoximgTrashedImage {
+                    trashed,
+                    sidecar,
+                    index,
+                    loader,
+                }));
+
+                if images.len() > 0 {
+                    images.update_window(f);
+                }
+            }
+            Err(e) => {
+                f.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    &format!("FOXIMG: Failed to move {path:?} to the trash:"),
+                );
+                f.rl.trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                f.toast(format!("Couldn't move \"{name}\" to the trash"));
+            }
+        });
+
+        self.close_gallery_if_empty();
+    }
+
+    /// Moves the XMP sidecar of the image at `path` to the trash, if it has one, so that it isn't left
+    /// behind without its image.
+    fn trash_sidecar(&mut self, path: &Path) -> Option<FoximgTrashed> {
+        let sidecar = xmp::sidecar_path(path);
+        if !sidecar.exists() {
+            return None;
+        }
+
+        match FoximgTrashed::trash(&sidecar) {
+            Ok(trashed) => Some(trashed),
+            Err(e) => {
+                self.rl.trace_log(
+                    TraceLogLevel::LOG_WARNING,
+                    &format!("FOXIMG: Failed to move sidecar {sidecar:?} to the trash:"),
+                );
+                self.rl
+                    .trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+                None
+            }
+        }
+    }
+
+    /// Trashes the current image if `Trash` is pressed. Returns true if so.
+    pub fn trash_img(&mut self) -> bool {
+        if self.is_action_pressed(FoximgAction::Trash) {
+            self.trash_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Restores an image that was trashed and puts it back into the gallery.
+    pub fn restore_trashed(&mut self, trashed: FoximgTrashedImage) {
+        let original = trashed.trashed.original();
+        let name = original.file_name().unwrap_or_default().to_string_lossy();
+
+        if let Err(e) = trashed.trashed.restore(&mut self.rl) {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_ERROR,
+                &format!("FOXIMG: Failed to restore {original:?}. It's still in the trash:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+            self.toast(format!("Couldn't restore \"{name}\""));
+            return;
+        }
+
+        if let Some(ref sidecar) = trashed.sidecar
+            && let Err(e) = sidecar.restore(&mut self.rl)
+        {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_WARNING,
+                &format!(
+                    "FOXIMG: Failed to restore sidecar {:?}. It's still in the trash:",
+                    sidecar.original()
+                ),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+        }
+
+        self.toast(format!("Restored \"{name}\" from the trash"));
+        self.reinsert_img(original, trashed.index, trashed.loader);
+    }
+
+    /// Toggles whether trashing asks first and saves the setting.
+    pub fn toggle_trash_confirm(&mut self) {
+        self.trash_settings.confirm = !self.trash_settings.confirm;
+        self.trash_settings.to_file(&self.rl);
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the prompt asking whether to trash the current image. Returns 1 if it was accepted, 0
+    /// or 2 if it was closed or cancelled, and -1 if it's still waiting for an answer.
+    fn draw_trash_prompt(&mut self, message: &str) -> i32 {
+        const WIDTH: f32 = 320.;
+        const HEIGHT: f32 = 120.;
+
+        let screen_width = self.d.get_screen_width().as_f32();
+        let screen_height = self.d.get_screen_height().as_f32();
+
+        self.d.draw_rectangle_rec(
+            rrect(0, 0, screen_width, screen_height),
+            self.style.bg.alpha(0.5),
+        );
+        self.d.gui_message_box(
+            rrect(
+                screen_width / 2. - WIDTH / 2.,
+                screen_height / 2. - HEIGHT / 2.,
+                WIDTH,
+                HEIGHT,
+            ),
+            "Move to Trash",
+            message,
+            "Trash;Cancel",
+        )
+    }
+}
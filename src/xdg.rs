@@ -0,0 +1,50 @@
+//! Helpers for the freedesktop base directory specification and the other freedesktop
+//! specifications built on top of it.
+
+use std::path::{Path, PathBuf};
+
+/// Returns `$XDG_<var>` or `$HOME/<fallback>` if unset. Returns `None` if neither variable is set.
+fn base_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    match std::env::var(var) {
+        Ok(dir) if !dir.is_empty() => Some(dir.into()),
+        _ => Some(Path::new(&std::env::var("HOME").ok()?).join(fallback)),
+    }
+}
+
+/// Returns `$XDG_CACHE_HOME`, or `$HOME/.cache` if unset.
+pub fn cache_home() -> Option<PathBuf> {
+    self::base_dir("XDG_CACHE_HOME", ".cache")
+}
+
+/// Returns `$XDG_DATA_HOME`, or `$HOME/.local/share` if unset.
+pub fn data_home() -> Option<PathBuf> {
+    self::base_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Percent-encodes every byte of `path` outside of the unreserved set of RFC 3986. Slashes are left
+/// as they are. Paths are raw bytes on unix, which are encoded as they are even if they aren't
+/// UTF-8.
+pub fn percent_encode(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+    #[cfg(not(unix))]
+    let path = path.to_string_lossy();
+    #[cfg(not(unix))]
+    let bytes = path.as_bytes();
+    let mut encoded = String::with_capacity(bytes.len());
+
+    for &b in bytes {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(b as char)
+            }
+            #[cfg(target_os = "windows")]
+            b'\\' => encoded.push('/'),
+            #[cfg(target_os = "windows")]
+            b':' => encoded.push(':'),
+            b => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+
+    encoded
+}
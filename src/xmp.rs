@@ -0,0 +1,532 @@
+//! Reads and writes the rating, colour label and tags of images in XMP sidecars, the way darktable
+//! and digiKam do. Sidecars are named after the whole image file, as in `photo.jpg.xmp`.
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use quick_xml::{
+    Reader, Writer,
+    events::{BytesEnd, BytesStart, BytesText, Event},
+};
+use raylib::prelude::*;
+use serde::Serialize;
+
+use crate::{Foximg, FoximgDraw, keybinds::FoximgAction, prompt::FoximgTextInput, resources};
+
+/// What a new sidecar starts out as before foximg writes its properties into it.
+const EMPTY_SIDECAR: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="foximg">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""/>
+ </rdf:RDF>
+</x:xmpmeta>
+"#;
+
+/// The namespaces of the properties foximg writes.
+const NAMESPACES: [(&str, &str); 3] = [
+    ("xmlns:xmp", "http://ns.adobe.com/xap/1.0/"),
+    ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
+    ("xmlns:darktable", "http://darktable.sf.net/"),
+];
+
+/// The properties foximg manages. Any other property in a sidecar is left as it is.
+const PROPERTIES: [&[u8]; 4] = [
+    b"xmp:Rating",
+    b"xmp:Label",
+    b"darktable:colorlabels",
+    b"dc:subject",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub enum FoximgLabel {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl FoximgLabel {
+    /// Every label, in the order darktable numbers them.
+    pub const ALL: [Self; 5] = [
+        Self::Red,
+        Self::Yellow,
+        Self::Green,
+        Self::Blue,
+        Self::Purple,
+    ];
+
+    /// Returns the name of the label as written in `xmp:Label`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Red => "Red",
+            Self::Yellow => "Yellow",
+            Self::Green => "Green",
+            Self::Blue => "Blue",
+            Self::Purple => "Purple",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|label| label.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Parses a label as darktable numbers them in `darktable:colorlabels`.
+    fn from_darktable(n: &str) -> Option<Self> {
+        Self::ALL.get(n.trim().parse::<usize>().ok()?).copied()
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::Red => Color::RED,
+            Self::Yellow => Color::GOLD,
+            Self::Green => Color::GREEN,
+            Self::Blue => Color::BLUE,
+            Self::Purple => Color::PURPLE,
+        }
+    }
+}
+
+/// The rating, colour label and tags of an image.
+#[derive(Clone, Default, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FoximgXmp {
+    /// From 0 to 5 stars, or -1 if darktable rejected the image.
+    pub rating: i8,
+    pub label: Option<FoximgLabel>,
+    pub tags: Vec<String>,
+}
+
+/// Returns the path of the sidecar of the image at `path`. digiKam can also name sidecars after the
+/// image without its extension, so those are used if they're the only ones that exist.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_os_string();
+    sidecar.push(".xmp");
+    let sidecar = PathBuf::from(sidecar);
+
+    let without_extension = path.with_extension("xmp");
+    if !sidecar.exists() && without_extension.exists() {
+        without_extension
+    } else {
+        sidecar
+    }
+}
+
+/// Returns the sidecar of the image at `from`, if it has one, along with what it's called once the
+/// image is at `to`. Sidecars keep naming themselves the same way.
+fn sidecar_paths(from: &Path, to: &Path) -> Option<(PathBuf, PathBuf)> {
+    let sidecar = self::sidecar_path(from);
+    if !sidecar.exists() {
+        return None;
+    }
+
+    let new_sidecar = if sidecar == from.with_extension("xmp") {
+        to.with_extension("xmp")
+    } else {
+        let mut new_sidecar = to.as_os_str().to_os_string();
+        new_sidecar.push(".xmp");
+        PathBuf::from(new_sidecar)
+    };
+    Some((sidecar, new_sidecar))
+}
+
+/// Writes `property` as an RDF container of type `container` holding `items`.
+fn write_container<'a>(
+    writer: &mut Writer<Vec<u8>>,
+    property: &str,
+    container: &str,
+    items: impl IntoIterator<Item = &'a str>,
+) -> anyhow::Result<()> {
+    writer.write_event(Event::Start(BytesStart::new(property)))?;
+    writer.write_event(Event::Start(BytesStart::new(container)))?;
+    for item in items {
+        writer.write_event(Event::Start(BytesStart::new("rdf:li")))?;
+        writer.write_event(Event::Text(BytesText::new(item)))?;
+        writer.write_event(Event::End(BytesEnd::new("rdf:li")))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new(container)))?;
+    writer.write_event(Event::End(BytesEnd::new(property)))?;
+    Ok(())
+}
+
+impl FoximgXmp {
+    /// Reads the sidecar of the image at `path`. Images without a sidecar have no rating, label or
+    /// tags.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        match fs::read_to_string(self::sidecar_path(path)) {
+            Ok(xml) => Self::parse(&xml),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes to the sidecar of the image at `path`. Everything foximg doesn't manage in an existing
+    /// sidecar is kept as it is.
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let sidecar = self::sidecar_path(path);
+        let xml = match fs::read_to_string(&sidecar) {
+            Ok(xml) => xml,
+            Err(e) if e.kind() == ErrorKind::NotFound && *self == Self::default() => return Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => EMPTY_SIDECAR.to_string(),
+            Err(e) => return Err(e.into()),
+        };
+
+        // The sidecar is written next to the old one and then moved over it, so that it's never left
+        // half written.
+        let mut tmp_path = sidecar.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&self.update(&xml)?)?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp_path, &sidecar)?;
+        Ok(())
+    }
+
+    fn parse(xml: &str) -> anyhow::Result<Self> {
+        let mut xmp = Self::default();
+        let mut reader = Reader::from_str(xml);
+        // The property whose value is being read. Values can be nested inside of RDF containers.
+        let mut property: Option<Vec<u8>> = None;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"rdf:Description" => {
+                    for attr in e.attributes() {
+                        let attr = attr?;
+                        xmp.set_property(attr.key.as_ref(), &attr.unescape_value()?);
+                    }
+                }
+                Event::Start(e) if PROPERTIES.contains(&e.name().as_ref()) => {
+                    property = Some(e.name().as_ref().to_vec());
+                }
+                Event::End(e) if property.as_deref() == Some(e.name().as_ref()) => property = None,
+                Event::Text(e) => {
+                    if let Some(ref property) = property {
+                        let text = e.unescape()?;
+                        if !text.trim().is_empty() {
+                            xmp.set_property(property, text.trim());
+                        }
+                    }
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        Ok(xmp)
+    }
+
+    fn set_property(&mut self, property: &[u8], value: &str) {
+        match property {
+            // darktable rates rejected images -1.
+            b"xmp:Rating" => self.rating = value.trim().parse::<i8>().map_or(0, |r| r.clamp(-1, 5)),
+            b"xmp:Label" => self.label = self.label.or(FoximgLabel::from_name(value)),
+            b"darktable:colorlabels" => {
+                self.label = self.label.or(FoximgLabel::from_darktable(value))
+            }
+            b"dc:subject" if !self.tags.iter().any(|tag| tag == value) => {
+                self.tags.push(value.to_string())
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns `description` with the properties foximg manages replaced with the ones of `self`.
+    fn description(&self, description: &BytesStart) -> anyhow::Result<BytesStart<'static>> {
+        let mut start = BytesStart::new("rdf:Description");
+        for attr in description.attributes() {
+            let attr = attr?;
+            if !PROPERTIES.contains(&attr.key.as_ref()) {
+                start.push_attribute(attr);
+            }
+        }
+
+        for (namespace, uri) in NAMESPACES {
+            if description.try_get_attribute(namespace)?.is_none() {
+                start.push_attribute((namespace, uri));
+            }
+        }
+
+        start.push_attribute(("xmp:Rating", self.rating.to_string().as_str()));
+        if let Some(label) = self.label {
+            start.push_attribute(("xmp:Label", label.name()));
+        }
+        Ok(start)
+    }
+
+    fn write_properties(&self, writer: &mut Writer<Vec<u8>>) -> anyhow::Result<()> {
+        if let Some(label) = self.label {
+            let n = FoximgLabel::ALL.iter().position(|&other| other == label);
+            let n = n.unwrap_or_default().to_string();
+            self::write_container(writer, "darktable:colorlabels", "rdf:Seq", [n.as_str()])?;
+        }
+
+        if !self.tags.is_empty() {
+            self::write_container(
+                writer,
+                "dc:subject",
+                "rdf:Bag",
+                self.tags.iter().map(String::as_str),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `xml` with the properties foximg manages dropped from it, and the ones of `self`
+    /// written into its first `rdf:Description`.
+    fn update(&self, xml: &str) -> anyhow::Result<Vec<u8>> {
+        let mut reader = Reader::from_str(xml);
+        let mut writer = Writer::new(Vec::new());
+        let mut written = false;
+        // How deep into a dropped property the reader is.
+        let mut skipping = 0;
+
+        loop {
+            let event = reader.read_event()?;
+            if skipping > 0 {
+                match event {
+                    Event::Start(_) => skipping += 1,
+                    Event::End(_) => skipping -= 1,
+                    Event::Eof => anyhow::bail!("Unexpected end of sidecar"),
+                    _ => (),
+                }
+                continue;
+            }
+
+            match event {
+                Event::Start(e) if !written && e.name().as_ref() == b"rdf:Description" => {
+                    writer.write_event(Event::Start(self.description(&e)?))?;
+                    self.write_properties(&mut writer)?;
+                    written = true;
+                }
+                Event::Empty(e) if !written && e.name().as_ref() == b"rdf:Description" => {
+                    let start = self.description(&e)?;
+                    let end = start.to_end().into_owned();
+                    writer.write_event(Event::Start(start))?;
+                    self.write_properties(&mut writer)?;
+                    writer.write_event(Event::End(end))?;
+                    written = true;
+                }
+                Event::Start(e) if PROPERTIES.contains(&e.name().as_ref()) => skipping = 1,
+                Event::Empty(e) if PROPERTIES.contains(&e.name().as_ref()) => (),
+                Event::Eof => break,
+                event => writer.write_event(event)?,
+            }
+        }
+
+        if !written {
+            anyhow::bail!("The sidecar doesn't have an rdf:Description");
+        }
+        Ok(writer.into_inner())
+    }
+}
+
+impl Foximg {
+    /// Changes the rating, label or tags of the current image with `edit` and writes them to its
+    /// sidecar. Does nothing if the UI is locked.
+    pub fn edit_xmp(&mut self, edit: impl FnOnce(&mut FoximgXmp)) {
+        if self.lock.is_some() {
+            return;
+        }
+
+        self.images_with(|f, images| {
+            let i = images.img_current() - 1;
+            let path = images.img_path().to_path_buf();
+            let mut xmp = images.xmp(i).clone();
+            edit(&mut xmp);
+
+            if let Err(e) = xmp.write(&path) {
+                f.rl.trace_log(
+                    TraceLogLevel::LOG_ERROR,
+                    &format!("FOXIMG: Failed to write the sidecar of {path:?}:"),
+                );
+                f.rl.trace_log(TraceLogLevel::LOG_ERROR, &format!("    > {e}"));
+                f.toast(format!(
+                    "Couldn't save \"{}\"",
+                    self::sidecar_path(&path)
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                ));
+                return;
+            }
+
+            images.set_xmp(i, xmp);
+            images.update_window(f);
+        });
+    }
+
+    /// Gives the current image `label`, or takes it away if it already has it. `None` takes away
+    /// any label.
+    pub fn toggle_label_current(&mut self, label: Option<FoximgLabel>) {
+        self.edit_xmp(|xmp| {
+            xmp.label = if xmp.label == label { None } else { label };
+        });
+    }
+
+    /// Shows a prompt for editing the tags of the current image as a comma-separated list.
+    pub fn edit_tags(&mut self) {
+        let Some(ref mut images) = self.images else {
+            return;
+        };
+
+        if self.lock.is_some() {
+            return;
+        }
+
+        let tags = images.xmp(images.img_current() - 1).tags.join(", ");
+        let input = FoximgTextInput::new(
+            "Tags",
+            "Separate tags with commas. Enter to save, Esc to cancel",
+            tags,
+        );
+
+        let Some(tags) = self.prompt_text(input, |tags| {
+            Ok::<_, String>(
+                tags.split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>(),
+            )
+        }) else {
+            return;
+        };
+
+        self.edit_xmp(|xmp| xmp.tags = tags);
+    }
+
+    /// Rates the current image if one of `Rate0` to `Rate5` is pressed, or labels it if
+    /// `ClearLabel` or one of the label actions is. Returns true if so.
+    pub fn rate_img(&mut self) -> bool {
+        const RATINGS: [FoximgAction; 6] = [
+            FoximgAction::Rate0,
+            FoximgAction::Rate1,
+            FoximgAction::Rate2,
+            FoximgAction::Rate3,
+            FoximgAction::Rate4,
+            FoximgAction::Rate5,
+        ];
+        const LABELS: [FoximgAction; 6] = [
+            FoximgAction::ClearLabel,
+            FoximgAction::LabelRed,
+            FoximgAction::LabelYellow,
+            FoximgAction::LabelGreen,
+            FoximgAction::LabelBlue,
+            FoximgAction::LabelPurple,
+        ];
+
+        if self.images.is_none() {
+            return false;
+        }
+
+        if let Some(n) = self.which_action_pressed(&RATINGS) {
+            self.edit_xmp(|xmp| xmp.rating = n as i8);
+        } else if let Some(n) = self.which_action_pressed(&LABELS) {
+            self.toggle_label_current(n.checked_sub(1).map(|n| FoximgLabel::ALL[n]));
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Edits the tags of the current image if `Tags` is pressed. Returns true if so.
+    pub fn tag_img(&mut self) -> bool {
+        if self.images.is_some() && self.is_action_pressed(FoximgAction::Tags) {
+            self.edit_tags();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves the sidecar of an image that was moved from `from` to `to` along with it, if it has one.
+    pub fn move_sidecar(&mut self, from: &Path, to: &Path) {
+        let Some((sidecar, new_sidecar)) = self::sidecar_paths(from, to) else {
+            return;
+        };
+
+        if let Err(e) = crate::trash::move_file(&sidecar, &new_sidecar) {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_WARNING,
+                &format!("FOXIMG: Failed to move sidecar {sidecar:?} to {new_sidecar:?}:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+        }
+    }
+
+    /// Copies the sidecar of an image that was copied from `from` to `to` along with it, if it has
+    /// one.
+    pub fn copy_sidecar(&mut self, from: &Path, to: &Path) {
+        let Some((sidecar, new_sidecar)) = self::sidecar_paths(from, to) else {
+            return;
+        };
+
+        if let Err(e) = fs::copy(&sidecar, &new_sidecar) {
+            self.rl.trace_log(
+                TraceLogLevel::LOG_WARNING,
+                &format!("FOXIMG: Failed to copy sidecar {sidecar:?} to {new_sidecar:?}:"),
+            );
+            self.rl
+                .trace_log(TraceLogLevel::LOG_WARNING, &format!("    > {e}"));
+        }
+    }
+}
+
+impl FoximgDraw<'_> {
+    /// Draws the rating, label and tags of an image in the top left corner of the window. Nothing is
+    /// drawn for images without any.
+    pub fn draw_xmp(&mut self, xmp: &FoximgXmp) {
+        const FONT_SIZE: f32 = 16.;
+        const FONT_SPACING: f32 = resources::yudit_spacing(FONT_SIZE);
+        const RADIUS: f32 = 6.;
+        const MARGIN: f32 = 10.;
+
+        if *xmp == FoximgXmp::default() {
+            return;
+        }
+
+        let mut position = rvec2(MARGIN + RADIUS, MARGIN + RADIUS);
+        // Make room for the title drawn in fullscreen.
+        if self.state.fullscreen {
+            position.y += FONT_SIZE + MARGIN;
+        }
+
+        if let Some(label) = xmp.label {
+            self.d.draw_circle_v(position, RADIUS, label.color());
+            position.x += RADIUS * 3.;
+        }
+
+        for star in 0..5 {
+            if star < xmp.rating {
+                self.d
+                    .draw_poly(position, 5, RADIUS, -90., self.style.accent);
+            } else {
+                self.d
+                    .draw_poly_lines(position, 5, RADIUS, -90., self.style.accent.alpha(0.5));
+            }
+            position.x += RADIUS * 2.5;
+        }
+
+        if !xmp.tags.is_empty() {
+            self.d.draw_text_ex(
+                &self.resources.yudit,
+                &xmp.tags.join(", "),
+                rvec2(position.x, position.y - FONT_SIZE / 2.),
+                FONT_SIZE,
+                FONT_SPACING,
+                self.style.accent,
+            );
+        }
+    }
+}